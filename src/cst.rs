@@ -0,0 +1,372 @@
+//! A lossless concrete syntax tree.
+//!
+//! Unlike the AST, the CST keeps every token of the source code, trivia (whitespace and
+//! comments) included: printing a CST gives back the exact source it was built from, even
+//! if the source is not a valid program.
+//! It only captures the structure that tools working on the source text need (statements,
+//! blocks, parenthesized groups), while expressions are kept as flat sequences of tokens.
+use crate::scanner::{Scanner, Token, TokenDiscriminant};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyntaxKind {
+    /// The whole source file.
+    Root,
+    /// `{ ... }`
+    Block,
+    /// `( ... )`, e.g. the condition of an `if` or the arguments of a call.
+    Group,
+    If,
+    While,
+    For,
+    /// A function declaration, including its name, parameters and body.
+    Function,
+    Try,
+    Test,
+    Export,
+    /// A `var` or `const` declaration.
+    Variable,
+    Print,
+    Return,
+    Throw,
+    Yield,
+    Import,
+    Expression,
+    /// Tokens that cannot start a statement, e.g. an unbalanced `}`.
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct SyntaxNode {
+    pub kind: SyntaxKind,
+    pub children: Vec<SyntaxElement>,
+}
+
+#[derive(Debug, Clone)]
+pub enum SyntaxElement {
+    Node(SyntaxNode),
+    Token(Token),
+}
+
+impl SyntaxNode {
+    /// Build the CST of `source`. It never fails: invalid code ends up in the tree as well.
+    pub fn parse(source: &str) -> Self {
+        let mut builder = Builder {
+            tokens: Scanner::new(source).collect(),
+            position: 0,
+            stack: vec![],
+        };
+        builder.start_node(SyntaxKind::Root);
+        while builder.peek().is_some() {
+            builder.statement();
+        }
+        builder.trivia();
+        builder.stack.pop().unwrap()
+    }
+
+    /// All the tokens in the node, in source order.
+    pub fn tokens(&self) -> Box<dyn Iterator<Item = &Token> + '_> {
+        Box::new(self.children.iter().flat_map(|child| match child {
+            SyntaxElement::Node(node) => node.tokens(),
+            SyntaxElement::Token(token) => Box::new(std::iter::once(token)),
+        }))
+    }
+}
+
+impl Display for SyntaxNode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for token in self.tokens() {
+            write!(f, "{}", token.clone().lexeme())?;
+        }
+        Ok(())
+    }
+}
+
+/// Builds the tree bottom-up, rowan-style: nodes are opened on a stack and tokens are
+/// appended to the innermost open node.
+struct Builder {
+    tokens: Vec<Token>,
+    /// The index of the next token to attach.
+    position: usize,
+    stack: Vec<SyntaxNode>,
+}
+
+impl Builder {
+    fn start_node(&mut self, kind: SyntaxKind) {
+        self.stack.push(SyntaxNode {
+            kind,
+            children: vec![],
+        });
+    }
+
+    fn finish_node(&mut self) {
+        if self.stack.len() > 1 {
+            let node = self.stack.pop().unwrap();
+            self.push(SyntaxElement::Node(node));
+        }
+    }
+
+    fn push(&mut self, element: SyntaxElement) {
+        self.stack.last_mut().unwrap().children.push(element);
+    }
+
+    /// Attach the upcoming trivia tokens to the current node.
+    fn trivia(&mut self) {
+        while let Some(token) = self.tokens.get(self.position) {
+            if token.discriminant() != TokenDiscriminant::Trivia {
+                break;
+            }
+            self.push(SyntaxElement::Token(token.clone()));
+            self.position += 1;
+        }
+    }
+
+    /// The upcoming significant token. The trivia before it is not consumed.
+    fn peek(&self) -> Option<TokenDiscriminant> {
+        self.tokens[self.position..]
+            .iter()
+            .map(|t| t.discriminant())
+            .find(|d| *d != TokenDiscriminant::Trivia)
+    }
+
+    /// Whether the upcoming significant tokens are `test` and a string literal: `test` is
+    /// only a keyword before the name of a test.
+    fn starts_test_block(&self) -> bool {
+        let mut significant = self.tokens[self.position..]
+            .iter()
+            .filter(|t| t.discriminant() != TokenDiscriminant::Trivia);
+        let is_test = significant
+            .next()
+            .map(|t| t.clone().lexeme() == "test")
+            .unwrap_or(false);
+        is_test
+            && significant
+                .next()
+                .map(|t| t.discriminant() == TokenDiscriminant::String)
+                .unwrap_or(false)
+    }
+
+    /// Attach the upcoming significant token, and the trivia that precedes it, to the
+    /// current node.
+    fn bump(&mut self) {
+        self.trivia();
+        if let Some(token) = self.tokens.get(self.position) {
+            self.push(SyntaxElement::Token(token.clone()));
+            self.position += 1;
+        }
+    }
+
+    fn bump_if(&mut self, kind: TokenDiscriminant) -> bool {
+        let matches = self.peek() == Some(kind);
+        if matches {
+            self.bump();
+        }
+        matches
+    }
+
+    /// It consumes at least one token, unless the input is over.
+    /// The trivia before the statement is attached to the enclosing node.
+    fn statement(&mut self) {
+        self.trivia();
+        let Some(upcoming) = self.peek() else { return };
+        match upcoming {
+            TokenDiscriminant::LeftBrace => self.block(),
+            TokenDiscriminant::If => {
+                self.start_node(SyntaxKind::If);
+                self.bump();
+                self.group();
+                self.statement();
+                if self.bump_if(TokenDiscriminant::Else) {
+                    self.statement();
+                }
+                self.finish_node();
+            }
+            TokenDiscriminant::While | TokenDiscriminant::For => {
+                let kind = if upcoming == TokenDiscriminant::While {
+                    SyntaxKind::While
+                } else {
+                    SyntaxKind::For
+                };
+                self.start_node(kind);
+                self.bump();
+                self.group();
+                self.statement();
+                self.finish_node();
+            }
+            TokenDiscriminant::Fun => {
+                self.start_node(SyntaxKind::Function);
+                self.bump();
+                // `*` and the name of the function
+                while !matches!(
+                    self.peek(),
+                    None | Some(
+                        TokenDiscriminant::LeftParen
+                            | TokenDiscriminant::LeftBrace
+                            | TokenDiscriminant::RightBrace
+                            | TokenDiscriminant::Semicolon
+                    )
+                ) {
+                    self.bump();
+                }
+                self.group();
+                if self.peek() == Some(TokenDiscriminant::LeftBrace) {
+                    self.block();
+                }
+                self.finish_node();
+            }
+            TokenDiscriminant::Try => {
+                self.start_node(SyntaxKind::Try);
+                self.bump();
+                self.block();
+                while let Some(clause @ (TokenDiscriminant::Catch | TokenDiscriminant::Finally)) =
+                    self.peek()
+                {
+                    self.bump();
+                    if clause == TokenDiscriminant::Catch {
+                        self.group();
+                    }
+                    self.block();
+                }
+                self.finish_node();
+            }
+            TokenDiscriminant::Identifier if self.starts_test_block() => {
+                self.start_node(SyntaxKind::Test);
+                self.bump();
+                self.bump_if(TokenDiscriminant::String);
+                self.block();
+                self.finish_node();
+            }
+            TokenDiscriminant::Export => {
+                self.start_node(SyntaxKind::Export);
+                self.bump();
+                self.statement();
+                self.finish_node();
+            }
+            TokenDiscriminant::RightBrace | TokenDiscriminant::RightParen => {
+                self.start_node(SyntaxKind::Error);
+                self.bump();
+                self.finish_node();
+            }
+            upcoming => {
+                let kind = match upcoming {
+                    TokenDiscriminant::Var | TokenDiscriminant::Const => SyntaxKind::Variable,
+                    TokenDiscriminant::Print => SyntaxKind::Print,
+                    TokenDiscriminant::Return => SyntaxKind::Return,
+                    TokenDiscriminant::Throw => SyntaxKind::Throw,
+                    TokenDiscriminant::Yield => SyntaxKind::Yield,
+                    TokenDiscriminant::Import => SyntaxKind::Import,
+                    _ => SyntaxKind::Expression,
+                };
+                self.start_node(kind);
+                while let Some(upcoming) = self.peek() {
+                    match upcoming {
+                        TokenDiscriminant::Semicolon => {
+                            self.bump();
+                            break;
+                        }
+                        // The end of the enclosing block: the semicolon is missing.
+                        TokenDiscriminant::RightBrace => break,
+                        TokenDiscriminant::LeftParen => self.group(),
+                        TokenDiscriminant::LeftBrace => self.block(),
+                        _ => self.bump(),
+                    }
+                }
+                self.finish_node();
+            }
+        }
+    }
+
+    /// `{ ... }`, if the upcoming token is `{`.
+    fn block(&mut self) {
+        if self.peek() != Some(TokenDiscriminant::LeftBrace) {
+            return;
+        }
+        self.trivia();
+        self.start_node(SyntaxKind::Block);
+        self.bump();
+        loop {
+            match self.peek() {
+                None => break,
+                Some(TokenDiscriminant::RightBrace) => {
+                    self.bump();
+                    break;
+                }
+                Some(_) => self.statement(),
+            }
+        }
+        self.finish_node();
+    }
+
+    /// `( ... )`, if the upcoming token is `(`.
+    fn group(&mut self) {
+        if self.peek() != Some(TokenDiscriminant::LeftParen) {
+            return;
+        }
+        self.trivia();
+        self.start_node(SyntaxKind::Group);
+        self.bump();
+        loop {
+            match self.peek() {
+                // Leave unbalanced braces to the enclosing block.
+                None | Some(TokenDiscriminant::RightBrace) => break,
+                Some(TokenDiscriminant::RightParen) => {
+                    self.bump();
+                    break;
+                }
+                Some(TokenDiscriminant::LeftParen) => self.group(),
+                Some(_) => self.bump(),
+            }
+        }
+        self.finish_node();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode};
+
+    #[test]
+    fn the_tree_round_trips_the_source() {
+        let sources = [
+            "",
+            "var a = 1; // comment\n\n  print a;\n",
+            "fun f(a, b) { if (a) { return b; } else return a; }",
+            "try { f(); } catch (e) { print e; } finally { print 1; }",
+            // Invalid programs
+            "} print (1;",
+            "fun f( { print \"unterminated",
+            "var a = 1 }",
+        ];
+        for source in sources {
+            assert_eq!(SyntaxNode::parse(source).to_string(), source);
+        }
+    }
+
+    /// The kinds of the top-level statements of `source`.
+    fn statement_kinds(source: &str) -> Vec<SyntaxKind> {
+        SyntaxNode::parse(source)
+            .children
+            .iter()
+            .filter_map(|child| match child {
+                SyntaxElement::Node(node) => Some(node.kind),
+                SyntaxElement::Token(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn statements_are_nodes() {
+        assert_eq!(
+            statement_kinds("var a = 1;\nif (a) { print a; }\n}"),
+            [SyntaxKind::Variable, SyntaxKind::If, SyntaxKind::Error]
+        );
+    }
+
+    #[test]
+    fn test_is_only_a_keyword_before_a_test_name() {
+        assert_eq!(
+            statement_kinds("test = 1;\ntest // the name\n  \"t\" {}"),
+            [SyntaxKind::Expression, SyntaxKind::Test]
+        );
+    }
+}
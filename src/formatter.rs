@@ -0,0 +1,434 @@
+//! `jlox fmt`: print Lox source code in a canonical style.
+//!
+//! The style is not configurable:
+//! - one statement per line, blocks indented by four spaces;
+//! - opening braces on the same line as the statement they belong to, `else`, `catch` and
+//!   `finally` on the same line as the preceding closing brace;
+//! - a single space around binary operators and after commas and keywords;
+//! - argument and parameter lists that would not fit in 100 columns are split, one item
+//!   per line.
+//!
+//! Comments are preserved, as well as (at most one) blank line between statements.
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, Token, TokenDiscriminant};
+use crate::ExecuteRawError;
+
+const INDENT: &str = "    ";
+const MAX_WIDTH: usize = 100;
+
+/// Format `source`. It returns `Err` if `source` is not a valid program.
+pub fn format_source(source: &str) -> Result<String, ExecuteRawError> {
+    // Formatting is only guaranteed to preserve the meaning of valid programs.
+    Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+    let mut printer = Printer::default();
+    printer.node(&SyntaxNode::parse(source));
+    Ok(printer.finish())
+}
+
+enum Separator {
+    Nothing,
+    Space,
+    LineBreak,
+}
+
+#[derive(Default, Clone)]
+struct Printer {
+    out: String,
+    indent: usize,
+    /// The line breaks to emit before the next token: 2 leaves a blank line.
+    pending_breaks: usize,
+    /// Whether the pending line break interrupts a statement, e.g. after a comment.
+    continuation: bool,
+    /// The last significant token written.
+    previous: Option<TokenDiscriminant>,
+    /// Whether the previous token ends an operand: a `-` that follows it is a binary
+    /// operator, a `(` starts the arguments of a call.
+    after_operand: bool,
+    /// Whether the previous token is a prefix operator, e.g. `!`.
+    after_prefix: bool,
+    /// Whether the source has a line break between the previous token and the next one.
+    source_break: bool,
+}
+
+impl Printer {
+    fn finish(mut self) -> String {
+        if !self.out.is_empty() {
+            self.out.push('\n');
+        }
+        self.out
+    }
+
+    fn node(&mut self, node: &SyntaxNode) {
+        match node.kind {
+            SyntaxKind::Root => self.statements(&node.children),
+            SyntaxKind::Block => self.block(node),
+            SyntaxKind::Group => self.group(node),
+            kind => {
+                for child in &node.children {
+                    self.element(child);
+                    let is_condition =
+                        matches!(child, SyntaxElement::Node(n) if n.kind == SyntaxKind::Group);
+                    if is_condition
+                        && matches!(kind, SyntaxKind::If | SyntaxKind::While | SyntaxKind::For)
+                    {
+                        // What follows a condition is a statement, not a call.
+                        self.after_operand = false;
+                    }
+                }
+            }
+        }
+    }
+
+    fn element(&mut self, element: &SyntaxElement) {
+        match element {
+            SyntaxElement::Node(node) => self.node(node),
+            SyntaxElement::Token(token) => self.token(token, false),
+        }
+    }
+
+    /// A sequence of statements, at the top level or in a block.
+    fn statements(&mut self, elements: &[SyntaxElement]) {
+        for element in elements {
+            match element {
+                SyntaxElement::Node(node) => {
+                    self.line_break();
+                    self.node(node);
+                }
+                SyntaxElement::Token(token) => self.token(token, true),
+            }
+        }
+    }
+
+    fn block(&mut self, node: &SyntaxNode) {
+        let [open, inner @ .., close] = node.children.as_slice() else {
+            unreachable!("Blocks in valid programs are delimited by braces")
+        };
+        self.element(open);
+        let is_empty = inner.iter().all(|element| match element {
+            SyntaxElement::Node(_) => false,
+            SyntaxElement::Token(token) => !token.is_comment(),
+        });
+        if !is_empty {
+            self.indent += 1;
+            self.statements(inner);
+            self.indent -= 1;
+            self.pending_breaks = 1;
+        }
+        self.element(close);
+    }
+
+    /// A parenthesized group, split over multiple lines if it is too long.
+    fn group(&mut self, node: &SyntaxNode) {
+        // Print the group on a single line first, to measure it.
+        let out = std::mem::take(&mut self.out);
+        let mut flat = self.clone();
+        self.out = out;
+        flat.pending_breaks = 0;
+        for child in &node.children {
+            flat.element(child);
+        }
+        let column = self.out.len() - self.out.rfind('\n').map_or(0, |i| i + 1);
+        let has_items = node.children.iter().any(
+            |child| matches!(child, SyntaxElement::Token(t) if t.discriminant() == TokenDiscriminant::Comma),
+        );
+        // Leave room for what usually follows a group, e.g. `) {` or `);`.
+        let fits = !flat.out.contains('\n') && column + flat.out.len() + 2 <= MAX_WIDTH;
+        if fits || !has_items || self.pending_breaks > 0 {
+            for child in &node.children {
+                self.element(child);
+            }
+            return;
+        }
+
+        let last = node.children.len() - 1;
+        for (i, child) in node.children.iter().enumerate() {
+            match child {
+                SyntaxElement::Token(t) if i == last => {
+                    self.indent -= 1;
+                    self.pending_breaks = 1;
+                    self.token(t, false);
+                }
+                SyntaxElement::Token(t) if i == 0 => {
+                    self.token(t, false);
+                    self.indent += 1;
+                    self.pending_breaks = 1;
+                }
+                SyntaxElement::Token(t) if t.discriminant() == TokenDiscriminant::Comma => {
+                    self.token(t, false);
+                    self.pending_breaks = 1;
+                }
+                child => self.element(child),
+            }
+        }
+    }
+
+    /// `in_statements` is `true` for the trivia between statements.
+    fn token(&mut self, token: &Token, in_statements: bool) {
+        if token.is_comment() {
+            self.comment(token, in_statements);
+        } else if token.discriminant() == TokenDiscriminant::Trivia {
+            let line_breaks = token.clone().lexeme().matches('\n').count();
+            if line_breaks > 0 {
+                self.source_break = true;
+            }
+            let after_open_brace = self.out.ends_with('{');
+            if in_statements && line_breaks > 1 && !self.out.is_empty() && !after_open_brace {
+                self.pending_breaks = 2;
+            }
+        } else {
+            self.significant(token);
+        }
+    }
+
+    fn comment(&mut self, comment: &Token, in_statements: bool) {
+        let comment = comment.clone().lexeme();
+        // A comment in the middle of a statement breaks a line that would otherwise
+        // continue: what follows gets indented further.
+        let interrupts = !in_statements && self.pending_breaks == 0;
+        if !self.source_break && !self.out.is_empty() {
+            // A trailing comment, on the same line as the code before it.
+            self.out.push(' ');
+        } else {
+            self.continuation = interrupts;
+            self.line_break();
+            self.flush();
+        }
+        self.out.push_str(comment.trim_end());
+        self.line_break();
+        self.continuation = interrupts;
+        self.source_break = false;
+    }
+
+    fn significant(&mut self, token: &Token) {
+        let kind = token.discriminant();
+        let separator = if self.pending_breaks > 0 {
+            Separator::LineBreak
+        } else {
+            self.separator(kind)
+        };
+        match separator {
+            Separator::Nothing => {}
+            Separator::Space => self.out.push(' '),
+            Separator::LineBreak => {
+                self.line_break();
+                self.flush();
+            }
+        }
+        self.out.push_str(&token.clone().lexeme());
+
+        let is_prefix = match kind {
+            TokenDiscriminant::Bang => true,
+            TokenDiscriminant::Minus
+            | TokenDiscriminant::PlusPlus
+            | TokenDiscriminant::MinusMinus => !self.after_operand,
+            _ => false,
+        };
+        self.after_operand = match kind {
+            TokenDiscriminant::Identifier
+            | TokenDiscriminant::String
+            | TokenDiscriminant::Number
+            | TokenDiscriminant::Integer
+            | TokenDiscriminant::True
+            | TokenDiscriminant::False
+            | TokenDiscriminant::Nil
+            | TokenDiscriminant::This
+            | TokenDiscriminant::Super
+            | TokenDiscriminant::RightParen => true,
+            // Postfix updates, e.g. `a++`
+            TokenDiscriminant::PlusPlus | TokenDiscriminant::MinusMinus => self.after_operand,
+            _ => false,
+        };
+        self.after_prefix = is_prefix;
+        self.previous = Some(kind);
+        self.source_break = false;
+    }
+
+    /// What goes between the previous token and the next one, on the same line.
+    fn separator(&self, next: TokenDiscriminant) -> Separator {
+        use TokenDiscriminant as T;
+
+        let Some(previous) = self.previous else {
+            return Separator::Nothing;
+        };
+        match (previous, next) {
+            (_, T::Semicolon | T::Comma | T::RightParen | T::Dot) => Separator::Nothing,
+            (T::LeftParen | T::Dot | T::Ellipsis, _) => Separator::Nothing,
+            (T::LeftBrace, T::RightBrace) => Separator::Nothing,
+            // `- -a` and `- --a`: without the space, the minuses would be scanned together
+            (T::Minus, T::Minus | T::MinusMinus) if self.after_prefix => Separator::Space,
+            _ if self.after_prefix => Separator::Nothing,
+            // Postfix updates and calls
+            (_, T::PlusPlus | T::MinusMinus | T::LeftParen) if self.after_operand => {
+                Separator::Nothing
+            }
+            // `fun*`
+            (T::Fun, T::Star) => Separator::Nothing,
+            (T::RightBrace, T::Else | T::Catch | T::Finally) => Separator::Space,
+            (_, T::Else | T::Catch | T::Finally) => Separator::LineBreak,
+            _ => Separator::Space,
+        }
+    }
+
+    /// End the current line, unless nothing has been written yet.
+    fn line_break(&mut self) {
+        if !self.out.is_empty() {
+            self.pending_breaks = self.pending_breaks.max(1);
+        }
+    }
+
+    fn flush(&mut self) {
+        if self.pending_breaks == 0 {
+            return;
+        }
+        for _ in 0..self.pending_breaks {
+            self.out.push('\n');
+        }
+        let indent = self.indent + usize::from(self.continuation);
+        self.out.push_str(&INDENT.repeat(indent));
+        self.pending_breaks = 0;
+        self.continuation = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::format_source;
+    use crate::scanner::{Scanner, TokenDiscriminant};
+    use insta::assert_snapshot;
+
+    fn format(source: &str) -> String {
+        let formatted = format_source(source).unwrap();
+        assert_eq!(
+            format_source(&formatted).unwrap(),
+            formatted,
+            "Formatting is not idempotent"
+        );
+        let significant = |source| -> Vec<String> {
+            Scanner::new(source)
+                .filter(|t| t.discriminant() != TokenDiscriminant::Trivia || t.is_comment())
+                .map(|t| t.lexeme())
+                .collect()
+        };
+        assert_eq!(
+            significant(source),
+            significant(&formatted),
+            "Formatting changed more than trivia"
+        );
+        formatted
+    }
+
+    #[test]
+    fn format_statements() {
+        let source = r#"var   a=1;var b = -a+2*(3-  4);
+fun  add (x,y){return x+y;}
+fun* gen(n){ for(var i=0;i<n;i++) yield i; }
+while (!false) { a++; --b; }
+export const c = a ? b : - 1;
+import "lib.lox" as lib;
+test "adds" { assert(add(1,2)==3, "sum"); }
+for (;;) {}"#;
+        assert_snapshot!(format(source), @r###"
+        var a = 1;
+        var b = -a + 2 * (3 - 4);
+        fun add(x, y) {
+            return x + y;
+        }
+        fun* gen(n) {
+            for (var i = 0; i < n; i++) yield i;
+        }
+        while (!false) {
+            a++;
+            --b;
+        }
+        export const c = a ? b : -1;
+        import "lib.lox" as lib;
+        test "adds" {
+            assert(add(1, 2) == 3, "sum");
+        }
+        for (;;) {}
+        "###);
+    }
+
+    #[test]
+    fn format_branches() {
+        let source = r#"if(a==1){print "one";}else if (a == 2) print "two"; else {
+  print "many";
+}
+try{throw "x";}catch(e){print e;}finally{}"#;
+        assert_snapshot!(format(source), @r###"
+        if (a == 1) {
+            print "one";
+        } else if (a == 2) print "two";
+        else {
+            print "many";
+        }
+        try {
+            throw "x";
+        } catch (e) {
+            print e;
+        } finally {}
+        "###);
+    }
+
+    #[test]
+    fn comments_and_blank_lines_are_preserved() {
+        let source = r#"// A header comment
+var a = 1;   // trailing
+
+
+
+var b = 2;
+{
+    // inside a block
+
+    print a; }
+print f(a, // after an argument
+  b);"#;
+        assert_snapshot!(format(source), @r###"
+        // A header comment
+        var a = 1; // trailing
+
+        var b = 2;
+        {
+            // inside a block
+
+            print a;
+        }
+        print f(
+            a, // after an argument
+            b
+        );
+        "###);
+    }
+
+    #[test]
+    fn long_argument_lists_are_split() {
+        let source = "fun f() { return chain(argument_number_one, argument_number_two, argument_number_three, argument_number_four); }";
+        assert_snapshot!(format(source), @r###"
+        fun f() {
+            return chain(
+                argument_number_one,
+                argument_number_two,
+                argument_number_three,
+                argument_number_four
+            );
+        }
+        "###);
+    }
+
+    #[test]
+    fn consecutive_negations_are_kept_apart() {
+        assert_snapshot!(format("print - -1; print -  --a; print -(-1); print !!a;"), @r###"
+        print - -1;
+        print - --a;
+        print -(-1);
+        print !!a;
+        "###);
+    }
+
+    #[test]
+    fn invalid_programs_are_not_formatted() {
+        assert!(format_source("print (1;").is_err());
+    }
+}
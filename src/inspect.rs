@@ -0,0 +1,75 @@
+//! The intermediate representations of a Lox program, as printed by the `tokens`, `ast`
+//! and `check` subcommands.
+use crate::parser::{display_ast, Parser};
+use crate::resolver::resolve;
+use crate::scanner::Scanner;
+use crate::ExecuteRawError;
+
+/// The tokens in `source`, one per line.
+pub fn display_tokens(source: &str) -> String {
+    Scanner::new(source).map(|t| format!("{t}\n")).collect()
+}
+
+/// The syntax tree of each statement in `source`.
+/// It returns `Err` if `source` cannot be parsed.
+pub fn display_syntax_tree(source: &str) -> Result<String, ExecuteRawError> {
+    let statements = Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+    let mut buffer = String::new();
+    for statement in &statements {
+        // Writing to a `String` never fails
+        buffer.push_str(&display_ast(statement).unwrap());
+    }
+    Ok(buffer)
+}
+
+/// Scan, parse and resolve `source`, without executing it.
+pub fn check(source: &str) -> Result<(), ExecuteRawError> {
+    let statements = Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+    resolve(&statements).map_err(ExecuteRawError::ResolverError)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{check, display_syntax_tree, display_tokens};
+    use insta::assert_snapshot;
+
+    #[test]
+    fn tokens_are_printed_one_per_line() {
+        assert_snapshot!(display_tokens("print 1;"), @r###"
+        L1 - Print print
+        L1 - Trivia  
+        L1 - Integer 1 1
+        L1 - Semicolon ;
+        "###);
+    }
+
+    #[test]
+    fn every_statement_is_printed() {
+        assert_snapshot!(display_syntax_tree("var a = 1; print a;").unwrap(), @r###"
+        Variable Declaration
+         Identifier
+         Literal
+          Integer 1
+        Print
+         Variable Reference
+          Identifier
+        "###);
+    }
+
+    #[test]
+    fn check_reports_every_syntax_error() {
+        let source = "var a = ;\nprint 1 @ 2;\nfun f() {\n";
+        assert_snapshot!(check(source).unwrap_err(), @r###"
+        [line 1] Error at ';': Unexpected `;`.
+        [line 2] Error: Unexpected `@`.
+        [line 4] Error at end: Unexpected end of input.
+        "###);
+        assert_snapshot!(display_syntax_tree("print (1;").unwrap_err(), @"[line 1] Error at ';': Unexpected `;`.");
+    }
+
+    #[test]
+    fn check_reports_resolver_errors() {
+        let source = "print 1;\nyield 2;";
+        assert_snapshot!(check(source).unwrap_err(), @"[line 2] Error at 'yield': Can only yield from a generator.");
+    }
+}
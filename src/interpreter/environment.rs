@@ -1,5 +1,7 @@
 use crate::interpreter::lox_value::LoxValue;
+use crate::interpreter::natives::define_natives;
 use crate::interpreter::tree_walker::RuntimeError;
+use crate::scanner::Token;
 use drop_bomb::DropBomb;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -12,11 +14,14 @@ pub struct Environment {
 }
 
 impl Environment {
+    /// An environment with the native functions already defined.
     pub fn new() -> Self {
-        Self {
+        let mut environment = Self {
             current_scope: Default::default(),
             parent_scopes: vec![],
-        }
+        };
+        define_natives(&mut environment);
+        environment
     }
 
     pub(in crate::interpreter) fn enter_scope(&mut self) -> ScopeGuard {
@@ -32,20 +37,33 @@ impl Environment {
     }
 
     pub(in crate::interpreter) fn define(&mut self, variable_name: String, value: LoxValue) {
-        self.current_scope.define(variable_name, value);
+        self.current_scope.define(variable_name, value, true);
     }
 
-    pub(in crate::interpreter) fn assign(
+    /// Define a binding that cannot be re-assigned after its initialization.
+    pub(in crate::interpreter) fn define_constant(
         &mut self,
         variable_name: String,
         value: LoxValue,
+    ) {
+        self.current_scope.define(variable_name, value, false);
+    }
+
+    pub(in crate::interpreter) fn assign(
+        &mut self,
+        variable: &Token,
+        value: LoxValue,
     ) -> Result<(), RuntimeError> {
-        if self.current_scope.assign(&variable_name, &value).is_ok() {
-            return Ok(());
-        }
-        for scope in self.parent_scopes.iter_mut().rev() {
-            if scope.assign(&variable_name, &value).is_ok() {
-                return Ok(());
+        let variable_name = variable.clone().lexeme();
+        let scopes =
+            std::iter::once(&mut self.current_scope).chain(self.parent_scopes.iter_mut().rev());
+        for scope in scopes {
+            match scope.assign(&variable_name, &value) {
+                Ok(()) => return Ok(()),
+                Err(AssignmentError::Constant) => {
+                    return Err(RuntimeError::assignment_to_constant(variable.clone()))
+                }
+                Err(AssignmentError::Undefined) => continue,
             }
         }
         Err(RuntimeError::undefined_variable(&variable_name))
@@ -65,28 +83,66 @@ impl Environment {
         }
         Err(RuntimeError::undefined_variable(variable_name))
     }
+
+    /// The names bound in the global scope, in no particular order.
+    pub(crate) fn global_names(&self) -> impl Iterator<Item = &str> {
+        self.globals().0.keys().map(String::as_str)
+    }
+
+    /// The bindings in the global scope, sorted by name, with the debugging representation
+    /// of their values.
+    pub(crate) fn describe_globals(&self) -> Vec<(String, String)> {
+        let mut globals: Vec<_> = self
+            .globals()
+            .0
+            .iter()
+            .map(|(name, binding)| (name.clone(), binding.slot.borrow().repr().to_string()))
+            .collect();
+        globals.sort();
+        globals
+    }
+
+    fn globals(&self) -> &Scope {
+        self.parent_scopes.first().unwrap_or(&self.current_scope)
+    }
 }
 
 #[derive(Default, Debug, Clone)]
-pub(in crate::interpreter) struct Scope(HashMap<String, Rc<RefCell<LoxValue>>>);
+pub(in crate::interpreter) struct Scope(HashMap<String, Binding>);
+
+#[derive(Debug, Clone)]
+pub(in crate::interpreter) struct Binding {
+    slot: Rc<RefCell<LoxValue>>,
+    mutable: bool,
+}
+
+pub(in crate::interpreter) enum AssignmentError {
+    Undefined,
+    Constant,
+}
 
 impl Scope {
-    pub fn define(&mut self, variable_name: String, value: LoxValue) {
-        self.0.insert(variable_name, Rc::new(RefCell::new(value)));
+    pub fn define(&mut self, variable_name: String, value: LoxValue, mutable: bool) {
+        let binding = Binding {
+            slot: Rc::new(RefCell::new(value)),
+            mutable,
+        };
+        self.0.insert(variable_name, binding);
     }
 
-    pub fn assign(&mut self, variable_name: &str, value: &LoxValue) -> Result<(), ()> {
+    pub fn assign(&mut self, variable_name: &str, value: &LoxValue) -> Result<(), AssignmentError> {
         match self.0.get(variable_name) {
-            None => Err(()),
-            Some(slot) => {
-                *slot.borrow_mut() = value.to_owned();
+            None => Err(AssignmentError::Undefined),
+            Some(binding) if !binding.mutable => Err(AssignmentError::Constant),
+            Some(binding) => {
+                *binding.slot.borrow_mut() = value.to_owned();
                 Ok(())
             }
         }
     }
 
     pub fn get_value(&self, variable_name: &str) -> Option<LoxValue> {
-        self.0.get(variable_name).map(|v| v.borrow().clone())
+        self.0.get(variable_name).map(|b| b.slot.borrow().clone())
     }
 }
 
@@ -0,0 +1,457 @@
+use crate::interpreter::environment::{Environment, ScopeGuard};
+use crate::interpreter::iteration::LoxIterator;
+use crate::interpreter::lox_callable::Arity;
+use crate::interpreter::lox_value::{LoxValue, NativeFunction};
+use crate::interpreter::tree_walker::RuntimeErrorOrReturn;
+use crate::parser::ast::{
+    BlockStatement, CatchClause, Expression, ForInStatement, IfElseStatement, Statement,
+    TryStatement, WhileStatement, YieldStatement,
+};
+use crate::scanner::Token;
+use crate::{Interpreter, RuntimeError};
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
+use std::rc::Rc;
+
+/// The object returned by a call to a generator function (`fun*`).
+///
+/// The body of the generator can't be executed by the recursive `_execute`, since it has
+/// to be suspended at every `yield` and resumed by `next()`.
+/// Statements that contain a `yield` are therefore unrolled into an explicit stack of
+/// [`Frame`]s, which survives in between calls to `next()`.
+/// Statements that do not contain a `yield` (and expressions - `yield` is a statement)
+/// run to completion in one go, via `_execute`.
+pub(in crate::interpreter) struct Generator {
+    name: String,
+    /// A private copy of the environment of the function: the scopes opened by a suspended
+    /// generator must not leak into the environment of its caller.
+    environment: Rc<RefCell<Environment>>,
+    /// The innermost statement that is being executed is the last one.
+    /// The generator is exhausted when the stack is empty.
+    frames: Vec<Frame>,
+}
+
+impl Generator {
+    /// `environment` must already contain the arguments of the call.
+    pub(in crate::interpreter) fn new(
+        name: String,
+        environment: Rc<RefCell<Environment>>,
+        body: Vec<Statement>,
+    ) -> Self {
+        Self {
+            name,
+            environment,
+            frames: vec![Frame::Block {
+                statements: body,
+                next: 0,
+                guard: None,
+            }],
+        }
+    }
+
+    /// Execute the body of the generator until the next `yield`.
+    /// It returns `None` when the generator is exhausted.
+    fn resume(&mut self, interpreter: &Interpreter) -> Result<Option<LoxValue>, RuntimeError> {
+        let mut scoped_interpreter = interpreter.fork(Rc::clone(&self.environment));
+        while let Some(frame) = self.frames.last_mut() {
+            match frame.step(&mut scoped_interpreter) {
+                Ok(Step::Continue) => {}
+                Ok(Step::Push(frame)) => self.frames.push(*frame),
+                Ok(Step::Pop) => {
+                    let frame = self.frames.pop().unwrap();
+                    frame.exit(&mut self.environment.borrow_mut());
+                }
+                Ok(Step::Yield(value)) => return Ok(Some(value)),
+                Err(e) => match self.unwind(e) {
+                    Ok(()) => {}
+                    // `return` ends the generator, its value is discarded.
+                    Err(RuntimeErrorOrReturn::Return(_)) => return Ok(None),
+                    Err(RuntimeErrorOrReturn::RuntimeError(e)) => return Err(e),
+                },
+            }
+        }
+        Ok(None)
+    }
+
+    /// Pop frames until we find a `try` statement that handles `error`.
+    /// It returns the error back if no frame handles it, leaving the generator exhausted.
+    fn unwind(&mut self, mut error: RuntimeErrorOrReturn) -> Result<(), RuntimeErrorOrReturn> {
+        while let Some(frame) = self.frames.pop() {
+            let mut environment = self.environment.borrow_mut();
+            let Frame::Try {
+                catch,
+                finally,
+                stage,
+                guard,
+                pending,
+                ..
+            } = frame
+            else {
+                frame.exit(&mut environment);
+                continue;
+            };
+            if let Some(guard) = guard {
+                environment.exit_scope(guard);
+            }
+            match (stage, error) {
+                (TryStage::Body, RuntimeErrorOrReturn::RuntimeError(e))
+                    if catch.is_some() && e.exit_code().is_none() =>
+                {
+                    let CatchClause { variable, body } = catch.unwrap();
+                    let guard = environment.enter_scope();
+                    environment.define(variable.lexeme(), e.into_value());
+                    let catch_body = Frame::block(body, &mut environment);
+                    self.frames.push(Frame::Try {
+                        body: None,
+                        catch: None,
+                        finally,
+                        stage: TryStage::Catch,
+                        guard: Some(guard),
+                        pending,
+                    });
+                    self.frames.push(catch_body);
+                    return Ok(());
+                }
+                (TryStage::Body | TryStage::Catch, e) if finally.is_some() => {
+                    let finally_body = Frame::block(finally.unwrap(), &mut environment);
+                    self.frames.push(Frame::Try {
+                        body: None,
+                        catch: None,
+                        finally: None,
+                        stage: TryStage::Finally,
+                        guard: None,
+                        pending: Some(e),
+                    });
+                    self.frames.push(finally_body);
+                    return Ok(());
+                }
+                // If `finally` fails (or returns) its outcome takes precedence over
+                // the outcome of the `try` and `catch` blocks.
+                (_, e) => error = e,
+            }
+        }
+        Err(error)
+    }
+}
+
+impl Drop for Generator {
+    fn drop(&mut self) {
+        // A generator that is dropped before being exhausted still has scopes open.
+        let mut environment = self.environment.borrow_mut();
+        while let Some(frame) = self.frames.pop() {
+            frame.exit(&mut environment);
+        }
+    }
+}
+
+impl std::fmt::Debug for Generator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Generator")
+            .field("name", &self.name)
+            .field("frames", &self.frames.len())
+            .finish()
+    }
+}
+
+impl Display for Generator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<generator {}>", self.name)
+    }
+}
+
+/// Resume `generator`, failing if it is already running (i.e. it is resuming itself).
+pub(in crate::interpreter) fn resume(
+    generator: &Rc<RefCell<Generator>>,
+    interpreter: &Interpreter,
+) -> Result<Option<LoxValue>, RuntimeError> {
+    match generator.try_borrow_mut() {
+        Ok(mut generator) => generator.resume(interpreter),
+        Err(_) => Err(RuntimeError::native("The generator is already running.")),
+    }
+}
+
+/// The `next` method of a generator: it returns the next yielded value, `nil` once the
+/// generator is exhausted.
+pub(in crate::interpreter) fn next_method(generator: Rc<RefCell<Generator>>) -> LoxValue {
+    LoxValue::NativeFunction(NativeFunction {
+        name: "next".into(),
+        arity: Arity {
+            min: 0,
+            max: Some(0),
+        },
+        function: Rc::new(move |interpreter, _| {
+            Ok(resume(&generator, interpreter)?.unwrap_or(LoxValue::Null))
+        }),
+    })
+}
+
+/// A statement containing a `yield` that is being executed.
+enum Frame {
+    Block {
+        statements: Vec<Statement>,
+        /// The index of the next statement to be executed.
+        next: usize,
+        /// `None` for the body of the generator, whose scope is the one holding the parameters.
+        guard: Option<ScopeGuard>,
+    },
+    While {
+        condition: Expression,
+        body: Statement,
+    },
+    ForIn {
+        variable: Token,
+        keyword: Token,
+        iterator: LoxIterator,
+        body: Statement,
+        /// The scope of the current iteration.
+        guard: Option<ScopeGuard>,
+    },
+    Try {
+        /// Taken when the execution of the body starts.
+        body: Option<BlockStatement>,
+        catch: Option<CatchClause>,
+        finally: Option<BlockStatement>,
+        stage: TryStage,
+        /// The scope holding the variable of the `catch` clause, while it is executing.
+        guard: Option<ScopeGuard>,
+        /// The error (or return) to resume unwinding with once `finally` completes.
+        pending: Option<RuntimeErrorOrReturn>,
+    },
+}
+
+/// Which part of a `try` statement is being executed.
+enum TryStage {
+    Start,
+    Body,
+    Catch,
+    Finally,
+}
+
+/// What the generator has to do after a frame has taken a step.
+enum Step {
+    /// Keep executing the innermost frame.
+    Continue,
+    /// A nested statement, to be executed before the innermost frame takes its next step.
+    Push(Box<Frame>),
+    /// The innermost frame completed.
+    Pop,
+    /// Suspend the generator.
+    Yield(LoxValue),
+}
+
+impl Frame {
+    fn block(
+        BlockStatement { statements, .. }: BlockStatement,
+        environment: &mut Environment,
+    ) -> Self {
+        Frame::Block {
+            statements,
+            next: 0,
+            guard: Some(environment.enter_scope()),
+        }
+    }
+
+    /// Make progress on the statement, either because it was just pushed or because the
+    /// nested statement it was waiting for completed.
+    fn step(&mut self, interpreter: &mut Interpreter) -> Result<Step, RuntimeErrorOrReturn> {
+        match self {
+            Frame::Block {
+                statements, next, ..
+            } => match statements.get(*next).cloned() {
+                Some(statement) => {
+                    *next += 1;
+                    start(statement, interpreter)
+                }
+                None => Ok(Step::Pop),
+            },
+            Frame::While { condition, body } => {
+                if interpreter.eval(condition.clone())?.is_truthy() {
+                    start(body.clone(), interpreter)
+                } else {
+                    Ok(Step::Pop)
+                }
+            }
+            Frame::ForIn {
+                variable,
+                keyword,
+                iterator,
+                body,
+                guard,
+            } => {
+                if let Some(guard) = guard.take() {
+                    interpreter.environment.borrow_mut().exit_scope(guard);
+                }
+                match iterator.next(interpreter, keyword)? {
+                    Some(element) => {
+                        let mut environment = interpreter.environment.borrow_mut();
+                        *guard = Some(environment.enter_scope());
+                        environment.define(variable.clone().lexeme(), element);
+                        drop(environment);
+                        start(body.clone(), interpreter)
+                    }
+                    None => Ok(Step::Pop),
+                }
+            }
+            Frame::Try {
+                body,
+                finally,
+                stage,
+                guard,
+                pending,
+                ..
+            } => {
+                if let Some(guard) = guard.take() {
+                    interpreter.environment.borrow_mut().exit_scope(guard);
+                }
+                match stage {
+                    TryStage::Start => {
+                        *stage = TryStage::Body;
+                        // Safe because the body is only taken when leaving `TryStage::Start`
+                        let body = body.take().unwrap();
+                        let mut environment = interpreter.environment.borrow_mut();
+                        Ok(Step::Push(Box::new(Frame::block(body, &mut environment))))
+                    }
+                    TryStage::Body | TryStage::Catch => match finally.take() {
+                        Some(finally) => {
+                            *stage = TryStage::Finally;
+                            let mut environment = interpreter.environment.borrow_mut();
+                            Ok(Step::Push(Box::new(Frame::block(
+                                finally,
+                                &mut environment,
+                            ))))
+                        }
+                        None => Ok(Step::Pop),
+                    },
+                    TryStage::Finally => match pending.take() {
+                        Some(e) => Err(e),
+                        None => Ok(Step::Pop),
+                    },
+                }
+            }
+        }
+    }
+
+    /// Close the scopes opened by the frame.
+    fn exit(self, environment: &mut Environment) {
+        match self {
+            Frame::Block { guard, .. } | Frame::ForIn { guard, .. } | Frame::Try { guard, .. } => {
+                if let Some(guard) = guard {
+                    environment.exit_scope(guard);
+                }
+            }
+            Frame::While { .. } => {}
+        }
+    }
+}
+
+/// Start executing `statement`.
+fn start(
+    statement: Statement,
+    interpreter: &mut Interpreter,
+) -> Result<Step, RuntimeErrorOrReturn> {
+    if !contains_yield(&statement) {
+        interpreter._execute(statement)?;
+        return Ok(Step::Continue);
+    }
+    match statement {
+        Statement::Yield(YieldStatement { value, .. }) => Ok(Step::Yield(interpreter.eval(value)?)),
+        Statement::Block(block) => {
+            let mut environment = interpreter.environment.borrow_mut();
+            Ok(Step::Push(Box::new(Frame::block(block, &mut environment))))
+        }
+        Statement::IfElse(IfElseStatement {
+            condition,
+            if_branch,
+            else_branch,
+        }) => {
+            if interpreter.eval(condition)?.is_truthy() {
+                start(*if_branch, interpreter)
+            } else if let Some(else_branch) = else_branch {
+                start(*else_branch, interpreter)
+            } else {
+                Ok(Step::Continue)
+            }
+        }
+        Statement::While(WhileStatement { condition, body }) => {
+            Ok(Step::Push(Box::new(Frame::While {
+                condition,
+                body: *body,
+            })))
+        }
+        Statement::ForIn(ForInStatement {
+            variable,
+            keyword,
+            iterable,
+            body,
+        }) => {
+            let iterator = interpreter
+                .eval(iterable)?
+                .iter()
+                .map_err(|e| e.or_at(keyword.clone()))?;
+            Ok(Step::Push(Box::new(Frame::ForIn {
+                variable,
+                keyword,
+                iterator,
+                body: *body,
+                guard: None,
+            })))
+        }
+        Statement::Try(TryStatement {
+            body,
+            catch,
+            finally,
+        }) => Ok(Step::Push(Box::new(Frame::Try {
+            body: Some(body),
+            catch,
+            finally,
+            stage: TryStage::Start,
+            guard: None,
+            pending: None,
+        }))),
+        // `contains_yield` returns `false` for all other statements
+        _ => unreachable!(),
+    }
+}
+
+/// `true` if executing `statement` may suspend the generator.
+fn contains_yield(statement: &Statement) -> bool {
+    match statement {
+        Statement::Yield(_) => true,
+        Statement::Block(BlockStatement { statements, .. }) => {
+            statements.iter().any(contains_yield)
+        }
+        Statement::IfElse(IfElseStatement {
+            if_branch,
+            else_branch,
+            ..
+        }) => {
+            contains_yield(if_branch) || else_branch.as_deref().map(contains_yield).unwrap_or(false)
+        }
+        Statement::While(WhileStatement { body, .. })
+        | Statement::ForIn(ForInStatement { body, .. }) => contains_yield(body),
+        Statement::Try(TryStatement {
+            body,
+            catch,
+            finally,
+        }) => {
+            body.statements.iter().any(contains_yield)
+                || catch
+                    .iter()
+                    .any(|c| c.body.statements.iter().any(contains_yield))
+                || finally
+                    .iter()
+                    .any(|f| f.statements.iter().any(contains_yield))
+        }
+        // A nested function is a separate generator (or not a generator at all).
+        Statement::Expression(_)
+        | Statement::Print(_)
+        | Statement::VariableDeclaration(_)
+        | Statement::FunctionDeclaration(_)
+        | Statement::Return(_)
+        | Statement::Throw(_)
+        | Statement::Import(_)
+        | Statement::Export(_)
+        | Statement::Test(_)
+        | Statement::Error(_) => false,
+    }
+}
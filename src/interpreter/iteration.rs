@@ -0,0 +1,131 @@
+use crate::interpreter::generators::{resume, Generator};
+use crate::interpreter::lox_value::{LoxValue, Range};
+use crate::scanner::Token;
+use crate::{Interpreter, RuntimeError};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// The state of a `for-in` loop over a Lox value.
+///
+/// New iterable types are supported by adding a variant here and mapping the value
+/// to it in [`LoxValue::iter`].
+pub(in crate::interpreter) enum LoxIterator {
+    Characters {
+        characters: Vec<char>,
+        position: usize,
+    },
+    Integers {
+        next: i64,
+        end: i64,
+        step: i64,
+    },
+    Floats {
+        next: f64,
+        end: f64,
+        step: f64,
+    },
+    /// Mutations of the list while iterating over it are observed by the loop.
+    List {
+        elements: Rc<RefCell<Vec<LoxValue>>>,
+        position: usize,
+    },
+    /// A callable that takes no arguments and returns the next element every time it is
+    /// called, or `nil` when there are no elements left.
+    Callable(LoxValue),
+    Generator(Rc<RefCell<Generator>>),
+}
+
+impl LoxValue {
+    /// Start iterating over the value, if it is iterable.
+    pub(in crate::interpreter) fn iter(self) -> Result<LoxIterator, RuntimeError> {
+        match self {
+            LoxValue::String(s) => Ok(LoxIterator::Characters {
+                characters: s.chars().collect(),
+                position: 0,
+            }),
+            LoxValue::Range(Range::Integers { start, end, step }) => Ok(LoxIterator::Integers {
+                next: start,
+                end,
+                step,
+            }),
+            LoxValue::Range(Range::Floats { start, end, step }) => Ok(LoxIterator::Floats {
+                next: start,
+                end,
+                step,
+            }),
+            LoxValue::List(elements) => Ok(LoxIterator::List {
+                elements,
+                position: 0,
+            }),
+            LoxValue::Function(_) | LoxValue::NativeFunction(_) => Ok(LoxIterator::Callable(self)),
+            LoxValue::Generator(generator) => Ok(LoxIterator::Generator(generator)),
+            LoxValue::Boolean(_)
+            | LoxValue::Null
+            | LoxValue::Number(_)
+            | LoxValue::Integer(_)
+            | LoxValue::Error(_)
+            | LoxValue::Module(_) => Err(RuntimeError::not_iterable(&self)),
+        }
+    }
+}
+
+impl LoxIterator {
+    /// Produce the next element, `None` if the iteration is over.
+    ///
+    /// `location` is used to attribute errors raised by callable iterators.
+    pub(in crate::interpreter) fn next(
+        &mut self,
+        interpreter: &Interpreter,
+        location: &Token,
+    ) -> Result<Option<LoxValue>, RuntimeError> {
+        match self {
+            LoxIterator::Characters {
+                characters,
+                position,
+            } => {
+                let next = characters
+                    .get(*position)
+                    .map(|c| LoxValue::String(c.to_string()));
+                *position += 1;
+                Ok(next)
+            }
+            LoxIterator::Integers { next, end, step } => {
+                let is_done = if *step > 0 { next >= end } else { next <= end };
+                if is_done {
+                    return Ok(None);
+                }
+                let current = *next;
+                // Overflowing means that we went past `end`.
+                *next = next.checked_add(*step).unwrap_or(*end);
+                Ok(Some(LoxValue::Integer(current)))
+            }
+            LoxIterator::Floats { next, end, step } => {
+                let is_done = if *step > 0.0 {
+                    next >= end
+                } else {
+                    next <= end
+                };
+                if is_done {
+                    return Ok(None);
+                }
+                let current = *next;
+                *next += *step;
+                Ok(Some(LoxValue::Number(current)))
+            }
+            LoxIterator::List { elements, position } => {
+                let next = elements.borrow().get(*position).cloned();
+                *position += 1;
+                Ok(next)
+            }
+            LoxIterator::Callable(callee) => {
+                match interpreter.call(callee.clone(), vec![], location)? {
+                    LoxValue::Null => Ok(None),
+                    next => Ok(Some(next)),
+                }
+            }
+            LoxIterator::Generator(generator) => {
+                resume(generator, interpreter).map_err(|e| e.or_at(location.to_owned()))
+            }
+        }
+    }
+}
@@ -1,11 +1,14 @@
-use crate::interpreter::lox_value::{Function, LoxValue};
+use crate::interpreter::generators::Generator;
+use crate::interpreter::lox_value::{Function, LoxValue, NativeFunction};
 use crate::interpreter::tree_walker::RuntimeErrorOrReturn;
+use crate::parser::ast::FunctionDeclarationStatement;
 use crate::{Interpreter, RuntimeError};
-use std::iter::zip;
+use std::cell::RefCell;
+use std::fmt::{Display, Formatter};
 use std::rc::Rc;
 
 pub(in crate::interpreter) trait LoxCallable {
-    fn arity(&self) -> u8;
+    fn arity(&self) -> Arity;
     fn call(
         self,
         interpreter: &Interpreter,
@@ -13,10 +16,54 @@ pub(in crate::interpreter) trait LoxCallable {
     ) -> Result<LoxValue, RuntimeError>;
 }
 
-impl LoxCallable for Function {
-    fn arity(&self) -> u8 {
+/// The range of argument counts accepted by a callable.
+#[derive(Debug, Clone, Copy)]
+pub struct Arity {
+    pub(in crate::interpreter) min: u8,
+    /// `None` if the callable accepts any number of arguments in excess of `min`.
+    pub(in crate::interpreter) max: Option<u8>,
+}
+
+impl Arity {
+    /// The arity of a function declared in Lox code.
+    pub(crate) fn of(declaration: &FunctionDeclarationStatement) -> Self {
+        let parameters = &declaration.parameters;
         // Safe because the parser enforces that we do not have more than 255 parameters
-        self.declaration.parameters.len() as u8
+        let n_required = parameters.iter().filter(|p| p.default.is_none()).count() as u8;
+        let max = if declaration.rest.is_some() {
+            None
+        } else {
+            Some(parameters.len() as u8)
+        };
+        Arity {
+            min: n_required,
+            max,
+        }
+    }
+
+    pub(crate) fn accepts(&self, n_arguments: u8) -> bool {
+        n_arguments >= self.min && self.max.map(|max| n_arguments <= max).unwrap_or(true)
+    }
+
+    /// Whether the arity reads as a single argument, i.e. "1" or "at least 1".
+    pub(crate) fn is_singular(&self) -> bool {
+        self.min == 1 && self.max.map(|max| max == 1).unwrap_or(true)
+    }
+}
+
+impl Display for Arity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.max {
+            Some(max) if max == self.min => write!(f, "{max}"),
+            Some(max) => write!(f, "{} to {max}", self.min),
+            None => write!(f, "at least {}", self.min),
+        }
+    }
+}
+
+impl LoxCallable for Function {
+    fn arity(&self) -> Arity {
+        Arity::of(&self.declaration)
     }
 
     fn call(
@@ -24,15 +71,23 @@ impl LoxCallable for Function {
         interpreter: &Interpreter,
         arguments: Vec<LoxValue>,
     ) -> Result<LoxValue, RuntimeError> {
-        let mut scoped_interpreter = interpreter.fork(Rc::clone(&self.closure));
-
-        for (parameter, argument) in zip(self.declaration.parameters, arguments) {
-            (*scoped_interpreter.environment)
-                .borrow_mut()
-                .define(parameter.lexeme(), argument);
+        if self.declaration.generator {
+            // The generator gets its own copy of the environment, since it outlives the call.
+            let environment = Rc::new(RefCell::new(self.closure.borrow().clone()));
+            let mut scoped_interpreter = interpreter.fork(Rc::clone(&environment));
+            self.bind_arguments(&mut scoped_interpreter, arguments)?;
+            let generator = Generator::new(
+                self.declaration.name.clone().lexeme(),
+                environment,
+                self.declaration.body.clone(),
+            );
+            return Ok(LoxValue::Generator(Rc::new(RefCell::new(generator))));
         }
-        for statement in self.declaration.body {
-            if let Err(e) = scoped_interpreter._execute(statement) {
+
+        let mut scoped_interpreter = interpreter.fork(Rc::clone(&self.closure));
+        self.bind_arguments(&mut scoped_interpreter, arguments)?;
+        for statement in &self.declaration.body {
+            if let Err(e) = scoped_interpreter._execute(statement.clone()) {
                 return match e {
                     RuntimeErrorOrReturn::RuntimeError(e) => Err(e),
                     RuntimeErrorOrReturn::Return(v) => Ok(v.0),
@@ -42,3 +97,56 @@ impl LoxCallable for Function {
         Ok(LoxValue::Null)
     }
 }
+
+impl Function {
+    /// Define the parameters of the function in the environment of `scoped_interpreter`.
+    fn bind_arguments(
+        &self,
+        scoped_interpreter: &mut Interpreter,
+        arguments: Vec<LoxValue>,
+    ) -> Result<(), RuntimeError> {
+        let mut arguments = arguments.into_iter();
+        for parameter in &self.declaration.parameters {
+            let value = match (arguments.next(), &parameter.default) {
+                (Some(argument), _) => argument,
+                // Defaults are evaluated at call time, in the scope of the function:
+                // they can refer to the parameters that come before them.
+                (None, Some(default)) => {
+                    scoped_interpreter
+                        .eval(default.clone())
+                        .map_err(|e| match e {
+                            RuntimeErrorOrReturn::RuntimeError(e) => e,
+                            // Expressions can't contain a `return` statement
+                            RuntimeErrorOrReturn::Return(_) => unreachable!(),
+                        })?
+                }
+                // The interpreter checks the arity of the function before calling it
+                (None, None) => unreachable!(),
+            };
+            (*scoped_interpreter.environment)
+                .borrow_mut()
+                .define(parameter.name.clone().lexeme(), value);
+        }
+        if let Some(rest) = &self.declaration.rest {
+            let excess = LoxValue::List(Rc::new(RefCell::new(arguments.collect())));
+            (*scoped_interpreter.environment)
+                .borrow_mut()
+                .define(rest.clone().lexeme(), excess);
+        }
+        Ok(())
+    }
+}
+
+impl LoxCallable for NativeFunction {
+    fn arity(&self) -> Arity {
+        self.arity
+    }
+
+    fn call(
+        self,
+        interpreter: &Interpreter,
+        arguments: Vec<LoxValue>,
+    ) -> Result<LoxValue, RuntimeError> {
+        (self.function)(interpreter, arguments)
+    }
+}
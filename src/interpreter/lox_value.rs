@@ -1,5 +1,9 @@
 use crate::interpreter::environment::Environment;
+use crate::interpreter::generators::Generator;
+use crate::interpreter::lox_callable::Arity;
+use crate::interpreter::modules::Module;
 use crate::parser::ast::FunctionDeclarationStatement;
+use crate::{Interpreter, RuntimeError};
 use std::cell::RefCell;
 use std::fmt::{Display, Formatter};
 use std::rc::Rc;
@@ -10,7 +14,14 @@ pub(in crate::interpreter) enum LoxValue {
     Null,
     String(String),
     Number(f64),
+    Integer(i64),
     Function(Function),
+    NativeFunction(NativeFunction),
+    Error(Rc<ErrorValue>),
+    Module(Rc<Module>),
+    List(Rc<RefCell<Vec<LoxValue>>>),
+    Range(Range),
+    Generator(Rc<RefCell<Generator>>),
 }
 
 impl LoxValue {
@@ -24,33 +35,127 @@ impl LoxValue {
         }
     }
 
+    /// The value as a float, if it is a number.
+    pub(in crate::interpreter) fn as_f64(&self) -> Option<f64> {
+        match self {
+            LoxValue::Number(n) => Some(*n),
+            LoxValue::Integer(n) => Some(*n as f64),
+            _ => None,
+        }
+    }
+
     pub fn is_equal(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Null, Self::Null) => true,
             (Self::String(s), Self::String(r)) => s == r,
             (Self::Boolean(s), Self::Boolean(r)) => s == r,
             (Self::Number(s), Self::Number(r)) => s == r,
+            (Self::Integer(s), Self::Integer(r)) => s == r,
+            (Self::Integer(i), Self::Number(n)) | (Self::Number(n), Self::Integer(i)) => {
+                *i as f64 == *n
+            }
+            (Self::Error(s), Self::Error(r)) => Rc::ptr_eq(s, r),
+            (Self::Module(s), Self::Module(r)) => Rc::ptr_eq(s, r),
+            (Self::List(s), Self::List(r)) => Rc::ptr_eq(s, r),
+            (Self::NativeFunction(s), Self::NativeFunction(r)) => {
+                Rc::ptr_eq(&s.function, &r.function)
+            }
+            (Self::Range(s), Self::Range(r)) => s == r,
+            (Self::Generator(s), Self::Generator(r)) => Rc::ptr_eq(s, r),
             (_, _) => false,
         }
     }
 }
 
+/// The output of `print`, matching the reference implementation of Lox.
 impl Display for LoxValue {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             LoxValue::Boolean(b) => b.fmt(f),
-            LoxValue::Null => write!(f, "`nil`"),
+            LoxValue::Null => write!(f, "nil"),
             LoxValue::String(s) => s.fmt(f),
-            LoxValue::Number(n) => n.fmt(f),
+            LoxValue::Number(n) => write!(f, "{}", format_number(*n)),
+            LoxValue::Integer(n) => n.fmt(f),
             LoxValue::Function(function) => function.fmt(f),
+            LoxValue::NativeFunction(function) => function.fmt(f),
+            LoxValue::Error(error) => error.fmt(f),
+            LoxValue::Module(module) => module.fmt(f),
+            LoxValue::List(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.fmt(f)?;
+                }
+                write!(f, "]")
+            }
+            LoxValue::Range(range) => range.fmt(f),
+            LoxValue::Generator(generator) => generator.borrow().fmt(f),
+        }
+    }
+}
+
+impl LoxValue {
+    /// A representation of the value meant for debugging, returned by the `repr` native
+    /// function. It is the way values were printed before `print` matched the reference
+    /// implementation: `nil` is in backticks and floats use Rust's formatting.
+    pub(in crate::interpreter) fn repr(&self) -> Repr<'_> {
+        Repr(self)
+    }
+}
+
+pub(in crate::interpreter) struct Repr<'a>(&'a LoxValue);
+
+impl Display for Repr<'_> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            LoxValue::Null => write!(f, "`nil`"),
+            LoxValue::Number(n) => n.fmt(f),
+            LoxValue::List(elements) => {
+                write!(f, "[")?;
+                for (i, element) in elements.borrow().iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    element.repr().fmt(f)?;
+                }
+                write!(f, "]")
+            }
+            value => value.fmt(f),
         }
     }
 }
 
+/// Format a float the way the reference implementation does, i.e. like Java's
+/// `Double.toString` without the trailing `.0` of integral values: `3`, `0.5`, `1.0E7`,
+/// `1.5E-4`, `Infinity`, `NaN`.
+fn format_number(n: f64) -> String {
+    if n.is_nan() {
+        return "NaN".into();
+    }
+    if n.is_infinite() {
+        return if n > 0.0 { "Infinity" } else { "-Infinity" }.into();
+    }
+    let magnitude = n.abs();
+    if n == 0.0 || (1e-3..1e7).contains(&magnitude) {
+        // Rust already omits the fractional part of integral values.
+        return n.to_string();
+    }
+    // Scientific notation, the mantissa always has a fractional part.
+    let scientific = format!("{n:e}");
+    let (mantissa, exponent) = scientific.split_once('e').unwrap();
+    if mantissa.contains('.') {
+        format!("{mantissa}E{exponent}")
+    } else {
+        format!("{mantissa}.0E{exponent}")
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(in crate::interpreter) struct Function {
     pub(in crate::interpreter) closure: Rc<RefCell<Environment>>,
-    pub(in crate::interpreter) declaration: FunctionDeclarationStatement,
+    pub(in crate::interpreter) declaration: Rc<FunctionDeclarationStatement>,
 }
 
 impl Display for Function {
@@ -58,3 +163,71 @@ impl Display for Function {
         write!(f, "<fn {}>", self.declaration.name.clone().lexeme())
     }
 }
+
+/// A function implemented in Rust and exposed to Lox programs.
+#[derive(Clone)]
+pub(in crate::interpreter) struct NativeFunction {
+    pub(in crate::interpreter) name: String,
+    pub(in crate::interpreter) arity: Arity,
+    #[allow(clippy::type_complexity)]
+    pub(in crate::interpreter) function:
+        Rc<dyn Fn(&Interpreter, Vec<LoxValue>) -> Result<LoxValue, RuntimeError>>,
+}
+
+impl std::fmt::Debug for NativeFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativeFunction")
+            .field("name", &self.name)
+            .field("arity", &self.arity)
+            .finish()
+    }
+}
+
+impl Display for NativeFunction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<native fn {}>", self.name)
+    }
+}
+
+/// The numbers from `start` (included) to `end` (excluded), `step` apart, as returned by
+/// the `range` native function.
+///
+/// The range is made of integers if all its bounds are integers.
+#[derive(Debug, Clone, PartialEq)]
+pub(in crate::interpreter) enum Range {
+    Integers { start: i64, end: i64, step: i64 },
+    Floats { start: f64, end: f64, step: f64 },
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Range::Integers { start, end, step } if *step == 1 => {
+                write!(f, "<range {start}..{end}>")
+            }
+            Range::Integers { start, end, step } => write!(f, "<range {start}..{end} by {step}>"),
+            Range::Floats { start, end, step } => {
+                let (start, end) = (format_number(*start), format_number(*end));
+                if *step == 1.0 {
+                    write!(f, "<range {start}..{end}>")
+                } else {
+                    write!(f, "<range {start}..{end} by {}>", format_number(*step))
+                }
+            }
+        }
+    }
+}
+
+/// The value bound to the variable of a `catch` clause when a runtime error is caught.
+#[derive(Debug)]
+pub(in crate::interpreter) struct ErrorValue {
+    pub(in crate::interpreter) message: String,
+    /// The line where the error occurred, if known.
+    pub(in crate::interpreter) line: Option<u64>,
+}
+
+impl Display for ErrorValue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<error: {}>", self.message)
+    }
+}
@@ -1,7 +1,18 @@
 mod environment;
+mod generators;
+mod iteration;
 mod lox_callable;
 mod lox_value;
+mod module_loader;
+mod modules;
+mod natives;
+mod testing;
 mod tree_walker;
 
 pub use environment::Environment;
-pub use tree_walker::{ExecuteRawError, Interpreter, RuntimeError};
+pub(crate) use lox_callable::Arity;
+pub use module_loader::{FileSystemLoader, InMemoryLoader, ModuleLoader, ModuleLoaderError};
+pub(crate) use modules::module_name;
+pub use testing::{TestReport, TestResult};
+pub(crate) use tree_walker::display_errors;
+pub use tree_walker::{ExecuteModuleError, ExecuteRawError, Interpreter, RuntimeError};
@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// `ModuleLoader` is how the interpreter gets hold of the source code of the modules imported
+/// by a Lox program.
+///
+/// Hosts that do not keep their scripts on the filesystem can provide their own implementation.
+pub trait ModuleLoader {
+    /// Turn the specifier of an `import` statement (e.g. `"lib/math.lox"`) into the canonical
+    /// identifier of a module.
+    ///
+    /// `importer` is the identifier of the module that contains the `import` statement, `None`
+    /// for the entry script and the REPL.
+    /// Two specifiers that refer to the same module must resolve to the same identifier:
+    /// identifiers are used to make sure that each module is executed only once and to detect
+    /// import cycles.
+    fn resolve(&self, specifier: &str, importer: Option<&str>)
+        -> Result<String, ModuleLoaderError>;
+
+    /// Retrieve the source code of the module with the given identifier, as returned
+    /// by [`ModuleLoader::resolve`].
+    fn load(&self, id: &str) -> Result<String, ModuleLoaderError>;
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ModuleLoaderError {
+    #[error("There is no module named '{0}'")]
+    NotFound(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Load modules from the filesystem.
+///
+/// Specifiers are paths, relative to the importing module or, for the entry script,
+/// relative to `root`.
+pub struct FileSystemLoader {
+    root: PathBuf,
+}
+
+impl FileSystemLoader {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl ModuleLoader for FileSystemLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        importer: Option<&str>,
+    ) -> Result<String, ModuleLoaderError> {
+        let base_directory = importer
+            .and_then(|i| Path::new(i).parent())
+            .unwrap_or(&self.root);
+        let path = base_directory.join(specifier).canonicalize()?;
+        Ok(path.to_string_lossy().into_owned())
+    }
+
+    fn load(&self, id: &str) -> Result<String, ModuleLoaderError> {
+        Ok(std::fs::read_to_string(id)?)
+    }
+}
+
+/// Serve modules from memory, identified by name.
+///
+/// Specifiers are module names, regardless of the importing module.
+#[derive(Default)]
+pub struct InMemoryLoader {
+    modules: HashMap<String, String>,
+}
+
+impl InMemoryLoader {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make a module available for import under `name`.
+    pub fn insert(&mut self, name: impl Into<String>, source: impl Into<String>) {
+        self.modules.insert(name.into(), source.into());
+    }
+}
+
+impl ModuleLoader for InMemoryLoader {
+    fn resolve(
+        &self,
+        specifier: &str,
+        _importer: Option<&str>,
+    ) -> Result<String, ModuleLoaderError> {
+        if self.modules.contains_key(specifier) {
+            Ok(specifier.to_owned())
+        } else {
+            Err(ModuleLoaderError::NotFound(specifier.to_owned()))
+        }
+    }
+
+    fn load(&self, id: &str) -> Result<String, ModuleLoaderError> {
+        self.modules
+            .get(id)
+            .cloned()
+            .ok_or_else(|| ModuleLoaderError::NotFound(id.to_owned()))
+    }
+}
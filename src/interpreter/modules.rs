@@ -0,0 +1,154 @@
+use crate::interpreter::environment::Environment;
+use crate::interpreter::lox_value::LoxValue;
+use crate::interpreter::module_loader::ModuleLoader;
+use crate::interpreter::tree_walker::RuntimeError;
+use crate::scanner::Token;
+use crate::{ExecuteRawError, Interpreter};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use std::rc::Rc;
+
+/// The namespace value that an `import` statement binds to.
+///
+/// Lookups go through the module's global environment, therefore importers
+/// always observe the current value of an exported binding.
+#[derive(Debug)]
+pub(in crate::interpreter) struct Module {
+    pub(in crate::interpreter) name: String,
+    pub(in crate::interpreter) environment: Rc<RefCell<Environment>>,
+    pub(in crate::interpreter) exports: Vec<String>,
+}
+
+impl Module {
+    pub(in crate::interpreter) fn get(&self, name: Token) -> Result<LoxValue, RuntimeError> {
+        let binding = name.clone().lexeme();
+        if !self.exports.contains(&binding) {
+            let msg = format!("Module '{}' does not export '{binding}'", self.name);
+            return Err(RuntimeError::new(name, msg));
+        }
+        (*self.environment).borrow().get_value(&binding)
+    }
+}
+
+impl Display for Module {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "<module {}>", self.name)
+    }
+}
+
+/// Book-keeping for all the modules imported by a program, shared by all the interpreter
+/// instances that are executing it.
+pub(in crate::interpreter) struct ModuleRegistry<'a> {
+    pub(in crate::interpreter) loader: Rc<dyn ModuleLoader + 'a>,
+    /// Modules that have already been executed, keyed by their identifier.
+    cache: HashMap<String, Rc<Module>>,
+    /// The chain of modules that are currently being executed, used to detect import cycles.
+    loading: Vec<String>,
+}
+
+impl<'a> ModuleRegistry<'a> {
+    pub(in crate::interpreter) fn new(loader: Rc<dyn ModuleLoader + 'a>) -> Self {
+        Self {
+            loader,
+            cache: Default::default(),
+            loading: vec![],
+        }
+    }
+
+    pub(in crate::interpreter) fn start_loading(&mut self, id: String) {
+        self.loading.push(id);
+    }
+
+    pub(in crate::interpreter) fn finish_loading(&mut self) {
+        self.loading.pop();
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Execute the module at `specifier` (unless it was already imported) and return its
+    /// namespace.
+    ///
+    /// `specifier` is resolved by the [`ModuleLoader`] the interpreter was created with.
+    pub(in crate::interpreter) fn import(
+        &self,
+        keyword: &Token,
+        specifier: &str,
+    ) -> Result<Rc<Module>, RuntimeError> {
+        let import_error = |msg: String| {
+            RuntimeError::new(
+                keyword.to_owned(),
+                format!("Failed to import '{specifier}'. {msg}"),
+            )
+        };
+
+        // Cloning the `Rc` to avoid holding a borrow on the registry while
+        // the loader is doing its job.
+        let loader = Rc::clone(&self.modules.borrow().loader);
+        let id = loader
+            .resolve(specifier, self.current_module.as_deref())
+            .map_err(|e| import_error(e.to_string()))?;
+
+        if let Some(module) = self.modules.borrow().cache.get(&id) {
+            return Ok(Rc::clone(module));
+        }
+        if let Some(start) = self.modules.borrow().loading.iter().position(|i| i == &id) {
+            let cycle = self.modules.borrow().loading[start..]
+                .iter()
+                .chain(std::iter::once(&id))
+                .map(|i| display_name(i))
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            return Err(import_error(format!("Import cycle detected: {cycle}")));
+        }
+
+        let source = loader.load(&id).map_err(|e| import_error(e.to_string()))?;
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        let mut module_interpreter = self.fork(Rc::clone(&environment));
+        module_interpreter.current_module = Some(id.clone());
+
+        self.modules.borrow_mut().start_loading(id.clone());
+        let outcome = module_interpreter.execute_raw(&source);
+        self.modules.borrow_mut().finish_loading();
+        outcome.map_err(|e| match e {
+            // `exit` ends the whole program, not just the module
+            ExecuteRawError::RuntimeError(e) if e.exit_code().is_some() => e,
+            ExecuteRawError::RuntimeError(e) => import_error(e.message().to_owned()),
+            e => import_error(e.to_string()),
+        })?;
+
+        let module = Rc::new(Module {
+            name: module_name(Path::new(&id)).unwrap_or_else(|| display_name(&id)),
+            environment,
+            exports: module_interpreter.exports,
+        });
+        self.modules
+            .borrow_mut()
+            .cache
+            .insert(id, Rc::clone(&module));
+        Ok(module)
+    }
+}
+
+/// The name a module gets bound to when the `import` statement does not specify one:
+/// the file name without its extension, if it is a valid identifier.
+pub(crate) fn module_name(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_str()?;
+    let mut chars = stem.chars();
+    let is_identifier = chars
+        .next()
+        .map(|c| c.is_ascii_alphabetic() || c == '_')
+        .unwrap_or(false)
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    is_identifier.then(|| stem.to_owned())
+}
+
+/// A short name for a module identifier, to be used in error messages.
+/// For most loaders identifiers are paths: we only keep the last segment.
+fn display_name(id: &str) -> String {
+    Path::new(id)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| id.to_owned())
+}
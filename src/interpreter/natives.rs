@@ -0,0 +1,135 @@
+use crate::interpreter::environment::Environment;
+use crate::interpreter::lox_callable::Arity;
+use crate::interpreter::lox_value::{LoxValue, NativeFunction, Range};
+use crate::{Interpreter, RuntimeError};
+use std::rc::Rc;
+
+/// Define the functions that every Lox program can use without importing them.
+pub(in crate::interpreter) fn define_natives(environment: &mut Environment) {
+    define(
+        environment,
+        "range",
+        Arity {
+            min: 2,
+            max: Some(3),
+        },
+        range,
+    );
+    define(
+        environment,
+        "repr",
+        Arity {
+            min: 1,
+            max: Some(1),
+        },
+        repr,
+    );
+    define(
+        environment,
+        "assert",
+        Arity {
+            min: 1,
+            max: Some(2),
+        },
+        assert,
+    );
+    define(
+        environment,
+        "exit",
+        Arity {
+            min: 0,
+            max: Some(1),
+        },
+        exit,
+    );
+}
+
+fn define(
+    environment: &mut Environment,
+    name: &str,
+    arity: Arity,
+    function: fn(&Interpreter, Vec<LoxValue>) -> Result<LoxValue, RuntimeError>,
+) {
+    let function = NativeFunction {
+        name: name.to_owned(),
+        arity,
+        function: Rc::new(function),
+    };
+    environment.define(name.to_owned(), LoxValue::NativeFunction(function));
+}
+
+/// `range(start, end, step = 1)`: the numbers from `start` (included) to `end` (excluded),
+/// `step` apart.
+fn range(_interpreter: &Interpreter, arguments: Vec<LoxValue>) -> Result<LoxValue, RuntimeError> {
+    if let Some(v) = arguments
+        .iter()
+        .find(|v| !matches!(v, LoxValue::Integer(_) | LoxValue::Number(_)))
+    {
+        return Err(RuntimeError::native(format!(
+            "The arguments of `range` must be numbers, found `{v}`."
+        )));
+    }
+    let zero_step = || RuntimeError::native("The step of a range cannot be zero.");
+    // The interpreter checks the arity of the function before calling it
+    let range = match arguments.as_slice() {
+        [LoxValue::Integer(start), LoxValue::Integer(end)] => Range::Integers {
+            start: *start,
+            end: *end,
+            step: 1,
+        },
+        [LoxValue::Integer(start), LoxValue::Integer(end), LoxValue::Integer(step)] => {
+            if *step == 0 {
+                return Err(zero_step());
+            }
+            Range::Integers {
+                start: *start,
+                end: *end,
+                step: *step,
+            }
+        }
+        // At least one of the bounds is a float: all of them get promoted.
+        numbers => {
+            let mut numbers = numbers.iter().map(|n| n.as_f64().unwrap());
+            let start = numbers.next().unwrap();
+            let end = numbers.next().unwrap();
+            let step = numbers.next().unwrap_or(1.0);
+            if step == 0.0 {
+                return Err(zero_step());
+            }
+            Range::Floats { start, end, step }
+        }
+    };
+    Ok(LoxValue::Range(range))
+}
+
+/// `repr(value)`: the debugging representation of `value`, as a string.
+fn repr(_interpreter: &Interpreter, arguments: Vec<LoxValue>) -> Result<LoxValue, RuntimeError> {
+    Ok(LoxValue::String(arguments[0].repr().to_string()))
+}
+
+/// `assert(condition, message = nil)`: raise an error if `condition` is falsy.
+fn assert(_interpreter: &Interpreter, arguments: Vec<LoxValue>) -> Result<LoxValue, RuntimeError> {
+    if arguments[0].is_truthy() {
+        return Ok(LoxValue::Null);
+    }
+    match arguments.get(1) {
+        None | Some(LoxValue::Null) => Err(RuntimeError::native("Assertion failed.")),
+        Some(message) => Err(RuntimeError::native(format!("Assertion failed: {message}"))),
+    }
+}
+
+/// `exit(code = 0)`: stop the program, which terminates with status `code`.
+fn exit(_interpreter: &Interpreter, arguments: Vec<LoxValue>) -> Result<LoxValue, RuntimeError> {
+    let code = match arguments.first() {
+        None => 0,
+        Some(LoxValue::Integer(code)) => i32::try_from(*code).map_err(|_| {
+            RuntimeError::native(format!("The exit status `{code}` is out of range."))
+        })?,
+        Some(v) => {
+            return Err(RuntimeError::native(format!(
+                "The exit status must be an integer, found `{v}`."
+            )))
+        }
+    };
+    Err(RuntimeError::exit(code))
+}
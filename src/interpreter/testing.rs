@@ -0,0 +1,99 @@
+use crate::interpreter::tree_walker::RuntimeError;
+use crate::parser::ast::{Statement, TestStatement};
+use crate::{ExecuteModuleError, ExecuteRawError, Interpreter};
+use std::fmt::{Display, Formatter};
+
+/// The outcome of the `test` blocks of a module, as returned by [`Interpreter::test_module`].
+#[derive(Debug)]
+pub struct TestReport {
+    pub results: Vec<TestResult>,
+}
+
+#[derive(Debug)]
+pub struct TestResult {
+    pub name: String,
+    /// The line of the `test` keyword.
+    pub line: u64,
+    pub outcome: Result<(), RuntimeError>,
+}
+
+impl TestReport {
+    pub fn n_passed(&self) -> usize {
+        self.results.iter().filter(|r| r.outcome.is_ok()).count()
+    }
+
+    pub fn n_failed(&self) -> usize {
+        self.results.len() - self.n_passed()
+    }
+}
+
+impl Display for TestReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for result in &self.results {
+            match &result.outcome {
+                Ok(()) => writeln!(f, "PASS {}", result.name)?,
+                Err(e) => {
+                    writeln!(f, "FAIL {} (line {})", result.name, result.line)?;
+                    match e.line() {
+                        Some(line) => writeln!(f, "  [line {line}] {}", e.message())?,
+                        None => writeln!(f, "  {}", e.message())?,
+                    }
+                }
+            }
+        }
+        write!(f, "{} passed, {} failed", self.n_passed(), self.n_failed())
+    }
+}
+
+impl<'a> Interpreter<'a> {
+    /// Execute the module at `specifier`, like [`Interpreter::execute_module`], and then run
+    /// its `test` blocks.
+    ///
+    /// Each test is executed in its own scope, after the whole module: tests can refer to
+    /// everything the module defines. A test fails if it raises a runtime error, e.g. via
+    /// `assert`. `test` blocks in imported modules are not executed.
+    ///
+    /// A test that calls `exit` ends the whole run: the error is returned, with its exit
+    /// code, instead of a report.
+    pub fn test_module(&mut self, specifier: &str) -> Result<TestReport, ExecuteModuleError> {
+        self.run_tests(|interpreter| interpreter.execute_module(specifier))
+    }
+
+    /// Execute `source`, like [`Interpreter::execute_raw`], and then run its `test` blocks.
+    /// See [`Interpreter::test_module`].
+    pub fn test_raw(&mut self, source: &str) -> Result<TestReport, ExecuteRawError> {
+        self.run_tests(|interpreter| interpreter.execute_raw(source))
+    }
+
+    fn run_tests<E: From<ExecuteRawError>>(
+        &mut self,
+        execute: impl FnOnce(&mut Self) -> Result<(), E>,
+    ) -> Result<TestReport, E> {
+        let previous_tests = self.tests.replace(vec![]);
+        let outcome = execute(self);
+        let tests = std::mem::replace(&mut self.tests, previous_tests).unwrap_or_default();
+        outcome?;
+
+        let mut results = vec![];
+        for TestStatement {
+            keyword,
+            name,
+            body,
+        } in tests
+        {
+            let outcome = match self.execute(Statement::Block(body)) {
+                Err(e) if e.exit_code().is_some() => {
+                    return Err(ExecuteRawError::RuntimeError(e).into())
+                }
+                outcome => outcome,
+            };
+            results.push(TestResult {
+                // Safe because the parser only accepts string literals as test names
+                name: name.ty().to_owned().string().unwrap(),
+                line: keyword.line(),
+                outcome,
+            });
+        }
+        Ok(TestReport { results })
+    }
+}
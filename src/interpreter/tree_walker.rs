@@ -1,31 +1,56 @@
 use crate::interpreter::environment::Environment;
-use crate::interpreter::lox_callable::LoxCallable;
-use crate::interpreter::lox_value::{Function, LoxValue};
+use crate::interpreter::generators::next_method;
+use crate::interpreter::lox_callable::{Arity, LoxCallable};
+use crate::interpreter::lox_value::{ErrorValue, Function, LoxValue};
+use crate::interpreter::module_loader::{ModuleLoader, ModuleLoaderError};
+use crate::interpreter::modules::{module_name, ModuleRegistry};
 use crate::parser::ast::{
-    BinaryExpression, BlockStatement, ExpressionStatement, IfElseStatement, LiteralExpression,
-    PrintStatement, ReturnStatement, Statement, UnaryExpression, VariableDeclarationStatement,
-    WhileStatement,
+    BinaryExpression, BlockStatement, CatchClause, ExportStatement, ExpressionStatement,
+    ForInStatement, IfElseStatement, ImportStatement, LiteralExpression, PrintStatement,
+    ReturnStatement, Statement, TestStatement, ThrowStatement, TryStatement, UnaryExpression,
+    VariableDeclarationStatement, WhileStatement, YieldStatement,
 };
-use crate::parser::{ast::Expression, Parser};
-use crate::scanner::{Scanner, Token, TokenDiscriminant};
+use crate::parser::{ast::Expression, Parser, SyntaxError};
+use crate::resolver::{resolve, ResolverError};
+use crate::scanner::{Scanner, Token, TokenDiscriminant, TokenType};
 use std::cell::RefCell;
+use std::cmp::Ordering;
 use std::io::Write;
+use std::path::Path;
 use std::rc::Rc;
 use std::sync::Mutex;
 
 pub struct Interpreter<'a> {
     pub(in crate::interpreter) environment: Rc<RefCell<Environment>>,
     output_stream: Rc<Mutex<dyn Write + 'a>>,
+    pub(in crate::interpreter) modules: Rc<RefCell<ModuleRegistry<'a>>>,
+    /// The identifier of the module that is being executed, if any.
+    pub(in crate::interpreter) current_module: Option<String>,
+    /// The names exported by the module that is being executed.
+    pub(in crate::interpreter) exports: Vec<String>,
+    /// The `test` blocks encountered so far, if we are collecting them.
+    /// `None` in normal runs, where tests are skipped.
+    pub(in crate::interpreter) tests: Option<Vec<TestStatement>>,
 }
 
 impl<'a> Interpreter<'a> {
-    pub fn new<OutputStream>(output: OutputStream, environment: Rc<RefCell<Environment>>) -> Self
+    /// `loader` is used to retrieve the source code of the modules imported via `import`.
+    pub fn new<OutputStream, Loader>(
+        output: OutputStream,
+        environment: Rc<RefCell<Environment>>,
+        loader: Loader,
+    ) -> Self
     where
         OutputStream: Write + 'a,
+        Loader: ModuleLoader + 'a,
     {
         Self {
             environment,
             output_stream: Rc::new(Mutex::new(output)),
+            modules: Rc::new(RefCell::new(ModuleRegistry::new(Rc::new(loader)))),
+            current_module: None,
+            exports: vec![],
+            tests: None,
         }
     }
 
@@ -40,9 +65,32 @@ impl<'a> Interpreter<'a> {
         Interpreter {
             environment,
             output_stream: Rc::clone(&self.output_stream),
+            modules: Rc::clone(&self.modules),
+            current_module: self.current_module.clone(),
+            exports: vec![],
+            tests: None,
         }
     }
 
+    /// Retrieve the source code of the module at `specifier` using the interpreter's
+    /// [`ModuleLoader`] and execute it.
+    ///
+    /// Unlike imported modules, the entry module is executed in the interpreter's environment.
+    /// Unlike [`Interpreter::execute_raw`], its `import` statements are resolved relative to
+    /// the module itself.
+    pub fn execute_module(&mut self, specifier: &str) -> Result<(), ExecuteModuleError> {
+        let loader = Rc::clone(&self.modules.borrow().loader);
+        let id = loader.resolve(specifier, None)?;
+        let source = loader.load(&id)?;
+
+        let previous_module = self.current_module.replace(id.clone());
+        self.modules.borrow_mut().start_loading(id);
+        let outcome = self.execute_raw(&source);
+        self.modules.borrow_mut().finish_loading();
+        self.current_module = previous_module;
+        Ok(outcome?)
+    }
+
     /// Scan, parse and then execute a Lox source file.
     ///
     /// It returns `Err` if an error was encountered while interpreting the code.
@@ -51,10 +99,28 @@ impl<'a> Interpreter<'a> {
     pub fn execute_raw(&mut self, source: &str) -> Result<(), ExecuteRawError> {
         let statements =
             Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+        resolve(&statements).map_err(ExecuteRawError::ResolverError)?;
         self.batch_execute(statements)
             .map_err(ExecuteRawError::RuntimeError)
     }
 
+    /// Evaluate a single expression and return the debugging representation of its value,
+    /// e.g. to echo it in the REPL. Strings are quoted, to tell them apart from other values.
+    pub(crate) fn evaluate(&mut self, expression: Expression) -> Result<String, ExecuteRawError> {
+        let statement = Statement::Expression(ExpressionStatement(expression.clone()));
+        resolve(&[statement]).map_err(ExecuteRawError::ResolverError)?;
+        let value = self.eval(expression).map_err(|e| match e {
+            RuntimeErrorOrReturn::RuntimeError(e) => e,
+            RuntimeErrorOrReturn::Return(_) => RuntimeError::unexpected_return(),
+        });
+        value
+            .map(|v| match v {
+                LoxValue::String(s) => format!("\"{s}\""),
+                v => v.repr().to_string(),
+            })
+            .map_err(ExecuteRawError::RuntimeError)
+    }
+
     /// Execute a series of statements.
     /// It exits as soon as a runtime error is encountered.
     pub fn batch_execute(&mut self, statements: Vec<Statement>) -> Result<(), RuntimeError> {
@@ -89,17 +155,21 @@ impl<'a> Interpreter<'a> {
             Statement::VariableDeclaration(VariableDeclarationStatement {
                 initializer,
                 identifier,
+                constant,
             }) => {
                 let value = if let Some(initializer) = initializer {
                     self.eval(initializer)?
                 } else {
                     LoxValue::Null
                 };
-                (*self.environment)
-                    .borrow_mut()
-                    .define(identifier.lexeme(), value);
+                let mut environment = (*self.environment).borrow_mut();
+                if constant {
+                    environment.define_constant(identifier.lexeme(), value);
+                } else {
+                    environment.define(identifier.lexeme(), value);
+                }
             }
-            Statement::Block(BlockStatement(statements)) => {
+            Statement::Block(BlockStatement { statements, .. }) => {
                 let guard = (*self.environment).borrow_mut().enter_scope();
                 let mut error = None;
                 for statement in statements {
@@ -129,10 +199,32 @@ impl<'a> Interpreter<'a> {
                     self._execute(*body.clone())?;
                 }
             }
+            Statement::ForIn(ForInStatement {
+                variable,
+                keyword,
+                iterable,
+                body,
+            }) => {
+                let mut iterator = self
+                    .eval(iterable)?
+                    .iter()
+                    .map_err(|e| e.or_at(keyword.clone()))?;
+                while let Some(element) = iterator.next(self, &keyword)? {
+                    // A fresh scope for every iteration: closures created in the body
+                    // capture the element of the iteration they were created in.
+                    let guard = (*self.environment).borrow_mut().enter_scope();
+                    (*self.environment)
+                        .borrow_mut()
+                        .define(variable.clone().lexeme(), element);
+                    let outcome = self._execute(*body.clone());
+                    (*self.environment).borrow_mut().exit_scope(guard);
+                    outcome?;
+                }
+            }
             Statement::FunctionDeclaration(statement) => {
                 let function = Function {
                     closure: Rc::new(RefCell::new(self.environment.borrow().to_owned())),
-                    declaration: statement,
+                    declaration: Rc::new(statement),
                 };
                 (*self.environment).borrow_mut().define(
                     function.declaration.name.clone().lexeme(),
@@ -149,11 +241,91 @@ impl<'a> Interpreter<'a> {
                 let value = self.eval(value)?;
                 return Err(Return(value).into());
             }
+            Statement::Throw(ThrowStatement { keyword, value }) => {
+                let value = self.eval(value)?;
+                return Err(RuntimeError::thrown(keyword, value).into());
+            }
+            Statement::Yield(YieldStatement { keyword, .. }) => {
+                // The resolver rejects `yield` outside of generators, and generators
+                // execute the statements that contain a `yield` on their own.
+                return Err(RuntimeError::new(keyword, "Can only yield from a generator.").into());
+            }
+            Statement::Import(ImportStatement {
+                keyword,
+                path,
+                alias,
+            }) => {
+                // Safe because the parser only accepts string literals as import paths
+                let specifier = path.ty().to_owned().string().unwrap();
+                let module = self.import(&keyword, &specifier)?;
+                let name = match alias {
+                    Some(alias) => alias.lexeme(),
+                    None => module_name(Path::new(&specifier)).ok_or_else(|| {
+                        RuntimeError::new(
+                            keyword,
+                            format!(
+                                "Cannot derive a valid name for '{specifier}', \
+                                use `import \"{specifier}\" as <name>;` instead"
+                            ),
+                        )
+                    })?,
+                };
+                (*self.environment)
+                    .borrow_mut()
+                    .define_constant(name, LoxValue::Module(module));
+            }
+            Statement::Export(ExportStatement { declaration, .. }) => {
+                let name = match declaration.as_ref() {
+                    Statement::VariableDeclaration(d) => d.identifier.clone().lexeme(),
+                    Statement::FunctionDeclaration(d) => d.name.clone().lexeme(),
+                    // The parser only allows declarations to be exported
+                    _ => unreachable!(),
+                };
+                self._execute(*declaration)?;
+                self.exports.push(name);
+            }
+            Statement::Test(test) => {
+                if let Some(tests) = &mut self.tests {
+                    tests.push(test);
+                }
+            }
+            Statement::Try(TryStatement {
+                body,
+                catch,
+                finally,
+            }) => {
+                let mut outcome = self._execute(Statement::Block(body));
+                if let Some(CatchClause { variable, body }) = catch {
+                    outcome = match outcome {
+                        Err(RuntimeErrorOrReturn::RuntimeError(e)) if e.exit_code().is_none() => {
+                            let guard = (*self.environment).borrow_mut().enter_scope();
+                            (*self.environment)
+                                .borrow_mut()
+                                .define(variable.lexeme(), e.into_value());
+                            let outcome = self._execute(Statement::Block(body));
+                            (*self.environment).borrow_mut().exit_scope(guard);
+                            outcome
+                        }
+                        outcome => outcome,
+                    };
+                }
+                if let Some(finally) = finally {
+                    // If `finally` fails (or returns) its outcome takes precedence over
+                    // the outcome of the `try` and `catch` blocks.
+                    self._execute(Statement::Block(finally))?;
+                }
+                outcome?;
+            }
+            // Programs with syntax errors are never executed
+            Statement::Error(_) => unreachable!(),
         }
         Ok(())
     }
 
-    fn eval(&mut self, e: Expression) -> Result<LoxValue, RuntimeErrorOrReturn> {
+    pub(in crate::interpreter) fn eval(
+        &mut self,
+        e: Expression,
+    ) -> Result<LoxValue, RuntimeErrorOrReturn> {
         match e {
             Expression::Binary(b) => {
                 let BinaryExpression {
@@ -179,43 +351,7 @@ impl<'a> Interpreter<'a> {
                 }
 
                 let right = self.eval(*right)?;
-                match operator.discriminant() {
-                    TokenDiscriminant::Minus => {
-                        num_op(left, right, operator, |l, r| LoxValue::Number(l - r))
-                    }
-                    TokenDiscriminant::Plus => match (left, right) {
-                        (LoxValue::Number(l), LoxValue::Number(r)) => Ok(LoxValue::Number(l + r)),
-                        (LoxValue::String(l), LoxValue::String(r)) => Ok(LoxValue::String(l + &r)),
-                        (_, _) => Err(RuntimeError::new(
-                            operator,
-                            "`+` operands must either be both numbers or both strings",
-                        )
-                        .into()),
-                    },
-                    TokenDiscriminant::Slash => {
-                        num_op(left, right, operator, |l, r| LoxValue::Number(l / r))
-                    }
-                    TokenDiscriminant::Star => {
-                        num_op(left, right, operator, |l, r| LoxValue::Number(l * r))
-                    }
-                    TokenDiscriminant::GreaterEqual => {
-                        num_op(left, right, operator, |l, r| LoxValue::Boolean(l > r))
-                    }
-                    TokenDiscriminant::Greater => {
-                        num_op(left, right, operator, |l, r| LoxValue::Boolean(l >= r))
-                    }
-                    TokenDiscriminant::Less => {
-                        num_op(left, right, operator, |l, r| LoxValue::Boolean(l < r))
-                    }
-                    TokenDiscriminant::LessEqual => {
-                        num_op(left, right, operator, |l, r| LoxValue::Boolean(l <= r))
-                    }
-                    TokenDiscriminant::EqualEqual => Ok(LoxValue::Boolean(left.is_equal(&right))),
-                    TokenDiscriminant::BangEqual => Ok(LoxValue::Boolean(!left.is_equal(&right))),
-                    _ => {
-                        Err(RuntimeError::new(operator, "It is not a valid binary operator").into())
-                    }
-                }
+                binary_operation(left, operator.discriminant(), operator, right)
             }
             Expression::Unary(u) => {
                 let UnaryExpression { operand, operator } = u;
@@ -223,7 +359,11 @@ impl<'a> Interpreter<'a> {
                 match operator.discriminant() {
                     TokenDiscriminant::Minus => match value {
                         LoxValue::Number(n) => Ok(LoxValue::Number(-n)),
-                        _ => Err(RuntimeError::new(operator, "Operand must be a number").into()),
+                        LoxValue::Integer(n) => Ok(LoxValue::Integer(
+                            n.checked_neg()
+                                .ok_or_else(|| RuntimeError::integer_overflow(operator))?,
+                        )),
+                        _ => Err(RuntimeError::new(operator, "Operand must be a number.").into()),
                     },
                     TokenDiscriminant::Bang => Ok(LoxValue::Boolean(!value.is_truthy())),
                     _ => Err(RuntimeError::new(
@@ -234,32 +374,104 @@ impl<'a> Interpreter<'a> {
                 }
             }
             Expression::Literal(l) => match l {
-                LiteralExpression::Boolean(b) => Ok(LoxValue::Boolean(b)),
+                LiteralExpression::Boolean(b, _) => Ok(LoxValue::Boolean(b)),
                 LiteralExpression::Null(_) => Ok(LoxValue::Null),
                 LiteralExpression::String(s) => {
                     // Avoidable .to_owned()
                     let s = s.ty().to_owned().string().unwrap();
                     Ok(LoxValue::String(s))
                 }
-                LiteralExpression::Number(n) => {
+                LiteralExpression::Number(n) => match n.ty() {
+                    TokenType::Integer(n) => Ok(LoxValue::Integer(*n)),
                     // Avoidable .to_owned()
-                    let n = n.ty().to_owned().number().unwrap();
-                    Ok(LoxValue::Number(n))
-                }
+                    _ => Ok(LoxValue::Number(n.ty().to_owned().number().unwrap())),
+                },
             },
             Expression::Grouping(g) => self.eval(*g.0),
             Expression::VariableReference(v) => {
-                let name = v.identifier.lexeme();
-                Ok((*self.environment).borrow().get_value(&name)?)
+                let name = v.identifier.clone().lexeme();
+                let value = (*self.environment)
+                    .borrow()
+                    .get_value(&name)
+                    .map_err(|e| e.or_at(v.identifier))?;
+                Ok(value)
             }
             Expression::VariableAssignment(v) => {
-                let name = v.identifier.lexeme();
                 let value = self.eval(*v.value)?;
                 (*self.environment)
                     .borrow_mut()
-                    .assign(name, value.clone())?;
+                    .assign(&v.identifier, value.clone())?;
                 Ok(value)
             }
+            Expression::CompoundAssignment(c) => {
+                let current = (*self.environment)
+                    .borrow()
+                    .get_value(&c.identifier.clone().lexeme())?;
+                let value = self.eval(*c.value)?;
+                let operation = match c.operator.discriminant() {
+                    TokenDiscriminant::PlusEqual => TokenDiscriminant::Plus,
+                    TokenDiscriminant::MinusEqual => TokenDiscriminant::Minus,
+                    TokenDiscriminant::StarEqual => TokenDiscriminant::Star,
+                    TokenDiscriminant::SlashEqual => TokenDiscriminant::Slash,
+                    _ => {
+                        return Err(RuntimeError::new(
+                            c.operator,
+                            "It is not a valid compound assignment operator",
+                        )
+                        .into())
+                    }
+                };
+                let value = binary_operation(current, operation, c.operator, value)?;
+                (*self.environment)
+                    .borrow_mut()
+                    .assign(&c.identifier, value.clone())?;
+                Ok(value)
+            }
+            Expression::Update(u) => {
+                let current = (*self.environment)
+                    .borrow()
+                    .get_value(&u.identifier.clone().lexeme())?;
+                let delta = match u.operator.discriminant() {
+                    TokenDiscriminant::PlusPlus => 1,
+                    TokenDiscriminant::MinusMinus => -1,
+                    _ => {
+                        return Err(RuntimeError::new(
+                            u.operator,
+                            "`++` and `--` are the only valid update operators",
+                        )
+                        .into())
+                    }
+                };
+                let updated = match current {
+                    LoxValue::Integer(n) => LoxValue::Integer(
+                        n.checked_add(delta)
+                            .ok_or_else(|| RuntimeError::integer_overflow(u.operator))?,
+                    ),
+                    LoxValue::Number(n) => LoxValue::Number(n + delta as f64),
+                    _ => {
+                        return Err(RuntimeError::new(
+                            u.operator,
+                            "Operand of `++` and `--` must be a number",
+                        )
+                        .into())
+                    }
+                };
+                (*self.environment)
+                    .borrow_mut()
+                    .assign(&u.identifier, updated.clone())?;
+                if u.prefix {
+                    Ok(updated)
+                } else {
+                    Ok(current)
+                }
+            }
+            Expression::Conditional(c) => {
+                if self.eval(*c.condition)?.is_truthy() {
+                    self.eval(*c.then_branch)
+                } else {
+                    self.eval(*c.else_branch)
+                }
+            }
             Expression::Call(c) => {
                 let callee = self.eval(*c.callee)?;
                 let arguments = c
@@ -267,28 +479,132 @@ impl<'a> Interpreter<'a> {
                     .into_iter()
                     .map(|a| self.eval(a))
                     .collect::<Result<Vec<_>, _>>()?;
-                match callee {
-                    LoxValue::Function(callee) => {
-                        // This is fine since the parser will reject functions with more than 255 arguments
-                        let n_arguments = arguments.len() as u8;
-                        if callee.arity() != n_arguments {
-                            return Err(
-                                RuntimeError::arity_mismatch(callee.arity(), n_arguments).into()
-                            );
-                        }
-                        Ok(callee.call(self, arguments)?)
-                    }
-                    LoxValue::Boolean(_)
-                    | LoxValue::Null
-                    | LoxValue::String(_)
-                    | LoxValue::Number(_) => Err(RuntimeError::not_callable(&callee).into()),
+                Ok(self.call(callee, arguments, &c.closing_parenthesis)?)
+            }
+            Expression::Get(g) => {
+                let object = self.eval(*g.object)?;
+                match object {
+                    LoxValue::Error(error) => match g.name.clone().lexeme().as_str() {
+                        "message" => Ok(LoxValue::String(error.message.clone())),
+                        "line" => Ok(error
+                            .line
+                            .map(|l| LoxValue::Integer(l as i64))
+                            .unwrap_or(LoxValue::Null)),
+                        _ => Err(RuntimeError::undefined_property(g.name).into()),
+                    },
+                    LoxValue::Module(module) => Ok(module.get(g.name)?),
+                    LoxValue::Generator(generator) => match g.name.clone().lexeme().as_str() {
+                        "next" => Ok(next_method(generator)),
+                        _ => Err(RuntimeError::undefined_property(g.name).into()),
+                    },
+                    LoxValue::List(elements) => match g.name.clone().lexeme().as_str() {
+                        "length" => Ok(LoxValue::Integer(elements.borrow().len() as i64)),
+                        _ => Err(RuntimeError::undefined_property(g.name).into()),
+                    },
+                    _ => Err(RuntimeError::new(
+                        g.name,
+                        "Only errors, modules, lists and generators have properties",
+                    )
+                    .into()),
                 }
             }
+            // Programs with syntax errors are never executed
+            Expression::Error(_) => unreachable!(),
         }
     }
 }
 
+impl<'a> Interpreter<'a> {
+    /// Call `callee` with arguments that have already been evaluated.
+    ///
+    /// `location` is attached to the errors that are not already attributed to a token
+    /// (e.g. an arity mismatch).
+    pub(in crate::interpreter) fn call(
+        &self,
+        callee: LoxValue,
+        arguments: Vec<LoxValue>,
+        location: &Token,
+    ) -> Result<LoxValue, RuntimeError> {
+        match callee {
+            LoxValue::Function(callee) => self.checked_call(callee, arguments, location),
+            LoxValue::NativeFunction(callee) => self
+                .checked_call(callee, arguments, location)
+                .map_err(|e| e.or_at(location.to_owned())),
+            LoxValue::Boolean(_)
+            | LoxValue::Null
+            | LoxValue::String(_)
+            | LoxValue::Number(_)
+            | LoxValue::Integer(_)
+            | LoxValue::Error(_)
+            | LoxValue::Module(_)
+            | LoxValue::List(_)
+            | LoxValue::Range(_)
+            | LoxValue::Generator(_) => {
+                Err(RuntimeError::not_callable().or_at(location.to_owned()))
+            }
+        }
+    }
+
+    fn checked_call<C: LoxCallable>(
+        &self,
+        callee: C,
+        arguments: Vec<LoxValue>,
+        location: &Token,
+    ) -> Result<LoxValue, RuntimeError> {
+        // This is fine since the parser will reject functions with more than 255 arguments
+        let n_arguments = arguments.len() as u8;
+        if !callee.arity().accepts(n_arguments) {
+            return Err(RuntimeError::arity_mismatch(callee.arity(), n_arguments)
+                .or_at(location.to_owned()));
+        }
+        callee.call(self, arguments)
+    }
+}
+
+/// Apply a (non short-circuiting) binary operator to two operands that have already been
+/// evaluated.
+///
+/// `kind` is passed separately from `operator` to allow compound assignments (e.g. `+=`) to
+/// reuse the logic of the underlying binary operator (e.g. `+`).
+fn binary_operation(
+    left: LoxValue,
+    kind: TokenDiscriminant,
+    operator: Token,
+    right: LoxValue,
+) -> Result<LoxValue, RuntimeErrorOrReturn> {
+    match kind {
+        TokenDiscriminant::Minus => {
+            arithmetic_op(left, right, operator, i64::checked_sub, |l, r| l - r)
+        }
+        TokenDiscriminant::Plus => match (left, right) {
+            (LoxValue::String(l), LoxValue::String(r)) => Ok(LoxValue::String(l + &r)),
+            (l, r) if l.as_f64().is_some() && r.as_f64().is_some() => {
+                arithmetic_op(l, r, operator, i64::checked_add, |l, r| l + r)
+            }
+            (_, _) => Err(RuntimeError::new(
+                operator,
+                "Operands must be two numbers or two strings.",
+            )
+            .into()),
+        },
+        // Dividing two integers does not truncate: the result is always a float.
+        TokenDiscriminant::Slash => num_op(left, right, operator, |l, r| LoxValue::Number(l / r)),
+        TokenDiscriminant::Star => {
+            arithmetic_op(left, right, operator, i64::checked_mul, |l, r| l * r)
+        }
+        TokenDiscriminant::Greater => compare(left, right, operator, Ordering::is_gt),
+        TokenDiscriminant::GreaterEqual => compare(left, right, operator, Ordering::is_ge),
+        TokenDiscriminant::Less => compare(left, right, operator, Ordering::is_lt),
+        TokenDiscriminant::LessEqual => compare(left, right, operator, Ordering::is_le),
+        TokenDiscriminant::EqualEqual => Ok(LoxValue::Boolean(left.is_equal(&right))),
+        TokenDiscriminant::BangEqual => Ok(LoxValue::Boolean(!left.is_equal(&right))),
+        _ => Err(RuntimeError::new(operator, "It is not a valid binary operator").into()),
+    }
+}
+
 /// Short-hand for evaluating numerical operations.
+///
+/// Integers are promoted to floats.
 fn num_op<F>(
     left: LoxValue,
     right: LoxValue,
@@ -298,20 +614,76 @@ fn num_op<F>(
 where
     F: Fn(f64, f64) -> LoxValue,
 {
-    match (left, right) {
-        (LoxValue::Number(l), LoxValue::Number(r)) => Ok(operation(l, r)),
+    match (left.as_f64(), right.as_f64()) {
+        (Some(l), Some(r)) => Ok(operation(l, r)),
         (_, _) => Err(RuntimeError::operands_must_be_numbers(operator).into()),
     }
 }
 
+/// An arithmetic operation that stays in the integers if both operands are integers,
+/// failing if the result overflows.
+/// Otherwise the operation is carried out on floats, see [`num_op`].
+fn arithmetic_op(
+    left: LoxValue,
+    right: LoxValue,
+    operator: Token,
+    integer_operation: fn(i64, i64) -> Option<i64>,
+    float_operation: fn(f64, f64) -> f64,
+) -> Result<LoxValue, RuntimeErrorOrReturn> {
+    match (left, right) {
+        (LoxValue::Integer(l), LoxValue::Integer(r)) => integer_operation(l, r)
+            .map(LoxValue::Integer)
+            .ok_or_else(|| RuntimeError::integer_overflow(operator).into()),
+        (l, r) => num_op(l, r, operator, |l, r| {
+            LoxValue::Number(float_operation(l, r))
+        }),
+    }
+}
+
+/// Compare two numbers. Integers are compared exactly, without going through floats.
+fn compare(
+    left: LoxValue,
+    right: LoxValue,
+    operator: Token,
+    predicate: fn(Ordering) -> bool,
+) -> Result<LoxValue, RuntimeErrorOrReturn> {
+    let ordering = match (left, right) {
+        (LoxValue::Integer(l), LoxValue::Integer(r)) => Some(l.cmp(&r)),
+        (l, r) => match (l.as_f64(), r.as_f64()) {
+            (Some(l), Some(r)) => l.partial_cmp(&r),
+            (_, _) => return Err(RuntimeError::operands_must_be_numbers(operator).into()),
+        },
+    };
+    // `None` if one of the operands is NaN, which is not ordered.
+    Ok(LoxValue::Boolean(ordering.map(predicate).unwrap_or(false)))
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ExecuteRawError {
-    #[error("Failed to parse the source code")]
-    ParserError(Vec<Statement>),
+    #[error("{}", display_errors(.0))]
+    ParserError(Vec<SyntaxError>),
+    #[error("{}", display_errors(.0))]
+    ResolverError(Vec<ResolverError>),
     #[error(transparent)]
     RuntimeError(RuntimeError),
 }
 
+pub(crate) fn display_errors(errors: &[impl std::fmt::Display]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ExecuteModuleError {
+    #[error("Failed to load the module")]
+    LoaderError(#[from] ModuleLoaderError),
+    #[error(transparent)]
+    ExecuteRawError(#[from] ExecuteRawError),
+}
+
 #[derive(Debug, thiserror::Error)]
 pub(in crate::interpreter) enum RuntimeErrorOrReturn {
     #[error(transparent)]
@@ -329,6 +701,10 @@ pub(in crate::interpreter) struct Return(pub(in crate::interpreter) LoxValue);
 pub struct RuntimeError {
     t: Option<Token>,
     msg: String,
+    /// The value passed to `throw`, if the error was raised by a Lox program.
+    thrown: Option<Box<LoxValue>>,
+    /// The status passed to `exit`, if the error was raised by it.
+    exit_code: Option<i32>,
 }
 
 impl RuntimeError {
@@ -336,23 +712,100 @@ impl RuntimeError {
         Self {
             t: Some(t),
             msg: msg.into(),
+            thrown: None,
+            exit_code: None,
+        }
+    }
+
+    fn thrown(keyword: Token, value: LoxValue) -> Self {
+        let msg = match &value {
+            // Re-throwing a caught error should look the same as the original error.
+            LoxValue::Error(error) => error.message.clone(),
+            value => format!("Uncaught exception: {value}"),
+        };
+        Self {
+            t: Some(keyword),
+            msg,
+            thrown: Some(Box::new(value)),
+            exit_code: None,
+        }
+    }
+
+    /// The error raised by `exit(code)`.
+    /// It cannot be caught: it unwinds the whole program, running `finally` blocks on the way.
+    pub(in crate::interpreter) fn exit(code: i32) -> Self {
+        Self {
+            t: None,
+            msg: format!("The program exited with status {code}."),
+            thrown: None,
+            exit_code: Some(code),
+        }
+    }
+
+    /// The status the program asked to exit with, if the error was raised by `exit`.
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code
+    }
+
+    /// The error message, without any location information.
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+
+    /// Attach a location to the error, if it doesn't have one already.
+    pub(in crate::interpreter) fn or_at(mut self, t: Token) -> Self {
+        if self.t.is_none() {
+            self.t = Some(t);
+        }
+        self
+    }
+
+    /// Convert the error into the value that gets bound to the variable of a `catch` clause.
+    pub(in crate::interpreter) fn into_value(self) -> LoxValue {
+        match self.thrown {
+            Some(value) => *value,
+            None => LoxValue::Error(Rc::new(ErrorValue {
+                message: self.msg,
+                line: self.t.map(|t| t.line()),
+            })),
         }
     }
 
+    /// The line the error was raised at, if known.
+    pub fn line(&self) -> Option<u64> {
+        self.t.as_ref().map(|t| t.line())
+    }
+
     pub fn operands_must_be_numbers(operator: Token) -> Self {
-        Self::new(operator, "Operands must be numbers")
+        Self::new(operator, "Operands must be numbers.")
+    }
+
+    pub fn integer_overflow(operator: Token) -> Self {
+        Self::new(operator, "Integer overflow")
+    }
+
+    pub fn assignment_to_constant(variable: Token) -> Self {
+        let msg = format!(
+            "Cannot assign to {}, it was declared as a constant",
+            variable.clone().lexeme()
+        );
+        Self::new(variable, msg)
     }
 
     pub fn undefined_variable(variable_name: &str) -> Self {
         Self {
             t: None,
-            msg: format!("Undefined variable named {}", variable_name),
+            thrown: None,
+            exit_code: None,
+            msg: format!("Undefined variable '{variable_name}'."),
         }
     }
 
     pub fn failed_to_print(e: std::io::Error) -> Self {
         Self {
             t: None,
+            thrown: None,
+            exit_code: None,
             msg: format!("Failed to execute a print statement.\n{}", e),
         }
     }
@@ -360,27 +813,59 @@ impl RuntimeError {
     pub fn failed_to_flush(e: std::io::Error) -> Self {
         Self {
             t: None,
+            thrown: None,
+            exit_code: None,
             msg: format!("Failed to flush the output stream.\n{}", e),
         }
     }
 
-    pub fn arity_mismatch(expected: u8, found: u8) -> Self {
+    pub fn arity_mismatch(expected: Arity, found: u8) -> Self {
+        Self {
+            t: None,
+            thrown: None,
+            exit_code: None,
+            msg: format!("Expected {expected} arguments but got {found}."),
+        }
+    }
+
+    fn not_callable() -> Self {
+        Self {
+            t: None,
+            thrown: None,
+            exit_code: None,
+            msg: "Can only call functions and classes.".into(),
+        }
+    }
+
+    pub(in crate::interpreter) fn not_iterable(v: &LoxValue) -> Self {
         Self {
             t: None,
-            msg: format!("Expect {expected} arguments, but got {found} arguments."),
+            thrown: None,
+            exit_code: None,
+            msg: format!("`{v}` is not iterable."),
         }
     }
 
-    fn not_callable(v: &LoxValue) -> Self {
+    /// An error raised by a native function, located at the call site by the interpreter.
+    pub(in crate::interpreter) fn native(msg: impl Into<String>) -> Self {
         Self {
             t: None,
-            msg: format!("`{v}` is not callable."),
+            thrown: None,
+            exit_code: None,
+            msg: msg.into(),
         }
     }
 
+    fn undefined_property(name: Token) -> Self {
+        let msg = format!("Undefined property '{}'", name.clone().lexeme());
+        Self::new(name, msg)
+    }
+
     fn unexpected_return() -> Self {
         Self {
             t: None,
+            thrown: None,
+            exit_code: None,
             msg: "`return` was used in an illegal position".into(),
         }
     }
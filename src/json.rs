@@ -0,0 +1,932 @@
+//! Tokens and syntax trees as JSON, for other tools: to analyse programs, or to build
+//! syntax trees and execute them.
+//!
+//! A token is an object with its kind, its lexeme, its line and its span, e.g.
+//! `{"kind": "Integer", "lexeme": "1", "line": 1, "span": {"start": 6, "end": 7}, "value": 1}`.
+//! `value` is only there for literals.
+//!
+//! A syntax tree is an array of statements. Each node is an object with its type, its
+//! children and its span, e.g. `print 1;` is
+//! `[{"type": "Print", "expression": {"type": "Number", "token": {...}, "span": ...}, "span": ...}]`.
+//! The span of a node goes from the start of its first token to the end of its last one:
+//! it leaves out the tokens the syntax tree does not keep, e.g. `print` or `;`.
+//! Like the values of tokens, spans are derived from the tokens: they are ignored when
+//! importing a syntax tree.
+use crate::interpreter::display_errors;
+use crate::parser::ast::{
+    BlockStatement, CatchClause, ExportStatement, Expression, ExpressionStatement, ForInStatement,
+    FunctionDeclarationStatement, IfElseStatement, ImportStatement, LiteralExpression, Parameter,
+    PrintStatement, ReturnStatement, Statement, TestStatement, ThrowStatement, TryStatement,
+    VariableDeclarationStatement, WhileStatement, YieldStatement,
+};
+use crate::parser::Parser;
+use crate::resolver::resolve;
+use crate::scanner::{Scanner, Token, TokenDiscriminant};
+use crate::{ExecuteRawError, ResolverError};
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SyntaxTreeJsonError {
+    /// The JSON does not follow the schema of syntax trees.
+    #[error("Malformed syntax tree: {0}")]
+    Malformed(#[from] serde_json::Error),
+    /// The syntax tree follows the schema, but the parser would never produce it,
+    /// e.g. because it contains a `print` statement in an `export` statement.
+    #[error("Invalid syntax tree: {0}.")]
+    Invalid(String),
+    #[error("{}", display_errors(.0))]
+    ResolverError(Vec<ResolverError>),
+}
+
+/// The tokens in `source`, trivia included, as a JSON array.
+pub fn tokens_to_json(source: &str) -> String {
+    let tokens: Vec<Token> = Scanner::new(source).collect();
+    // Safe because tokens always serialize to JSON
+    serde_json::to_string_pretty(&tokens).unwrap()
+}
+
+/// The syntax tree of `source`, as a JSON array of statements.
+/// It returns `Err` if `source` cannot be parsed.
+pub fn syntax_tree_to_json(source: &str) -> Result<String, ExecuteRawError> {
+    let statements = Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+    let nodes: Vec<Node<StatementJson>> = statements.iter().map(statement_to_json).collect();
+    // Safe because syntax trees always serialize to JSON
+    Ok(serde_json::to_string_pretty(&nodes).unwrap())
+}
+
+/// Read a syntax tree, either exported by [`syntax_tree_to_json`] or built by another tool.
+/// It is checked like the parser and the resolver check source code, so it can be executed
+/// with [`Interpreter::batch_execute`](crate::Interpreter::batch_execute).
+pub fn syntax_tree_from_json(json: &str) -> Result<Vec<Statement>, SyntaxTreeJsonError> {
+    let nodes: Vec<Node<StatementJson>> = serde_json::from_str(json)?;
+    let statements = nodes
+        .into_iter()
+        .map(statement_from_json)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(SyntaxTreeJsonError::Invalid)?;
+    resolve(&statements).map_err(SyntaxTreeJsonError::ResolverError)?;
+    Ok(statements)
+}
+
+/// The most parameters, or arguments, a function call can have.
+const MAX_ARGUMENTS: usize = 255;
+
+/// A node of the syntax tree, along with the part of the source code it covers.
+#[derive(Serialize, Deserialize)]
+struct Node<T> {
+    #[serde(flatten)]
+    node: T,
+    #[serde(skip_deserializing)]
+    span: Range<usize>,
+}
+
+impl<T> Node<T> {
+    /// `spans` are the ones of the tokens and of the child nodes of `node`.
+    fn new(node: T, spans: impl IntoIterator<Item = Range<usize>>) -> Self {
+        let span = spans
+            .into_iter()
+            .reduce(|a, b| a.start.min(b.start)..a.end.max(b.end))
+            // Safe because every node has at least a token
+            .unwrap();
+        Self { node, span }
+    }
+}
+
+/// The syntax trees of valid programs have no error nodes: there are none in JSON.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum StatementJson {
+    Expression {
+        expression: Node<ExpressionJson>,
+    },
+    Print {
+        expression: Node<ExpressionJson>,
+    },
+    VariableDeclaration {
+        identifier: Token,
+        initializer: Option<Node<ExpressionJson>>,
+        constant: bool,
+    },
+    FunctionDeclaration {
+        name: Token,
+        parameters: Vec<ParameterJson>,
+        rest: Option<Token>,
+        body: Vec<Node<StatementJson>>,
+        generator: bool,
+    },
+    Block(BlockJson),
+    IfElse {
+        condition: Node<ExpressionJson>,
+        if_branch: Box<Node<StatementJson>>,
+        else_branch: Option<Box<Node<StatementJson>>>,
+    },
+    While {
+        condition: Node<ExpressionJson>,
+        body: Box<Node<StatementJson>>,
+    },
+    ForIn {
+        variable: Token,
+        keyword: Token,
+        iterable: Node<ExpressionJson>,
+        body: Box<Node<StatementJson>>,
+    },
+    Return {
+        keyword: Token,
+        value: Node<ExpressionJson>,
+    },
+    Throw {
+        keyword: Token,
+        value: Node<ExpressionJson>,
+    },
+    Yield {
+        keyword: Token,
+        value: Node<ExpressionJson>,
+    },
+    Try {
+        body: Node<BlockJson>,
+        catch: Option<CatchJson>,
+        finally: Option<Node<BlockJson>>,
+    },
+    Import {
+        keyword: Token,
+        path: Token,
+        alias: Option<Token>,
+    },
+    Export {
+        keyword: Token,
+        declaration: Box<Node<StatementJson>>,
+    },
+    Test {
+        keyword: Token,
+        name: Token,
+        body: Node<BlockJson>,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+struct BlockJson {
+    brace: Token,
+    statements: Vec<Node<StatementJson>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ParameterJson {
+    name: Token,
+    default: Option<Node<ExpressionJson>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CatchJson {
+    variable: Token,
+    body: Node<BlockJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type")]
+enum ExpressionJson {
+    Binary {
+        left: Box<Node<ExpressionJson>>,
+        operator: Token,
+        right: Box<Node<ExpressionJson>>,
+    },
+    Unary {
+        operator: Token,
+        operand: Box<Node<ExpressionJson>>,
+    },
+    Boolean {
+        value: bool,
+        token: Token,
+    },
+    Nil {
+        token: Token,
+    },
+    String {
+        token: Token,
+    },
+    Number {
+        token: Token,
+    },
+    Grouping {
+        expression: Box<Node<ExpressionJson>>,
+    },
+    VariableReference {
+        identifier: Token,
+    },
+    VariableAssignment {
+        identifier: Token,
+        value: Box<Node<ExpressionJson>>,
+    },
+    CompoundAssignment {
+        identifier: Token,
+        operator: Token,
+        value: Box<Node<ExpressionJson>>,
+    },
+    Update {
+        identifier: Token,
+        operator: Token,
+        prefix: bool,
+    },
+    Conditional {
+        condition: Box<Node<ExpressionJson>>,
+        then_branch: Box<Node<ExpressionJson>>,
+        else_branch: Box<Node<ExpressionJson>>,
+    },
+    Call {
+        callee: Box<Node<ExpressionJson>>,
+        closing_parenthesis: Token,
+        arguments: Vec<Node<ExpressionJson>>,
+    },
+    Get {
+        object: Box<Node<ExpressionJson>>,
+        name: Token,
+    },
+}
+
+fn statement_to_json(statement: &Statement) -> Node<StatementJson> {
+    let boxed = |s: &Statement| Box::new(statement_to_json(s));
+    match statement {
+        Statement::Expression(ExpressionStatement(e)) => {
+            let expression = expression_to_json(e);
+            let span = expression.span.clone();
+            Node::new(StatementJson::Expression { expression }, [span])
+        }
+        Statement::Print(PrintStatement(e)) => {
+            let expression = expression_to_json(e);
+            let span = expression.span.clone();
+            Node::new(StatementJson::Print { expression }, [span])
+        }
+        Statement::VariableDeclaration(d) => {
+            let initializer = d.initializer.as_ref().map(expression_to_json);
+            let spans = [d.identifier.span()]
+                .into_iter()
+                .chain(initializer.iter().map(|i| i.span.clone()))
+                .collect::<Vec<_>>();
+            let node = StatementJson::VariableDeclaration {
+                identifier: d.identifier.clone(),
+                initializer,
+                constant: d.constant,
+            };
+            Node::new(node, spans)
+        }
+        Statement::FunctionDeclaration(f) => {
+            let parameters: Vec<ParameterJson> = f
+                .parameters
+                .iter()
+                .map(|p| ParameterJson {
+                    name: p.name.clone(),
+                    default: p.default.as_ref().map(expression_to_json),
+                })
+                .collect();
+            let body: Vec<Node<StatementJson>> = f.body.iter().map(statement_to_json).collect();
+            let spans = [f.name.span()]
+                .into_iter()
+                .chain(parameters.iter().flat_map(|p| {
+                    [p.name.span()]
+                        .into_iter()
+                        .chain(p.default.iter().map(|d| d.span.clone()))
+                }))
+                .chain(f.rest.iter().map(Token::span))
+                .chain(body.iter().map(|s| s.span.clone()))
+                .collect::<Vec<_>>();
+            let node = StatementJson::FunctionDeclaration {
+                name: f.name.clone(),
+                parameters,
+                rest: f.rest.clone(),
+                body,
+                generator: f.generator,
+            };
+            Node::new(node, spans)
+        }
+        Statement::Block(b) => {
+            let block = block_to_json(b);
+            Node::new(StatementJson::Block(block.node), [block.span])
+        }
+        Statement::IfElse(i) => {
+            let condition = expression_to_json(&i.condition);
+            let if_branch = boxed(&i.if_branch);
+            let else_branch = i.else_branch.as_deref().map(boxed);
+            let spans = [condition.span.clone(), if_branch.span.clone()]
+                .into_iter()
+                .chain(else_branch.iter().map(|e| e.span.clone()))
+                .collect::<Vec<_>>();
+            let node = StatementJson::IfElse {
+                condition,
+                if_branch,
+                else_branch,
+            };
+            Node::new(node, spans)
+        }
+        Statement::While(w) => {
+            let condition = expression_to_json(&w.condition);
+            let body = boxed(&w.body);
+            let spans = [condition.span.clone(), body.span.clone()];
+            Node::new(StatementJson::While { condition, body }, spans)
+        }
+        Statement::ForIn(f) => {
+            let iterable = expression_to_json(&f.iterable);
+            let body = boxed(&f.body);
+            let spans = [
+                f.variable.span(),
+                f.keyword.span(),
+                iterable.span.clone(),
+                body.span.clone(),
+            ];
+            let node = StatementJson::ForIn {
+                variable: f.variable.clone(),
+                keyword: f.keyword.clone(),
+                iterable,
+                body,
+            };
+            Node::new(node, spans)
+        }
+        Statement::Return(ReturnStatement { keyword, value })
+        | Statement::Throw(ThrowStatement { keyword, value })
+        | Statement::Yield(YieldStatement { keyword, value }) => {
+            let value = expression_to_json(value);
+            let spans = [keyword.span(), value.span.clone()];
+            let keyword = keyword.clone();
+            let node = match statement {
+                Statement::Return(_) => StatementJson::Return { keyword, value },
+                Statement::Throw(_) => StatementJson::Throw { keyword, value },
+                _ => StatementJson::Yield { keyword, value },
+            };
+            Node::new(node, spans)
+        }
+        Statement::Try(t) => {
+            let body = block_to_json(&t.body);
+            let catch = t.catch.as_ref().map(|c| CatchJson {
+                variable: c.variable.clone(),
+                body: block_to_json(&c.body),
+            });
+            let finally = t.finally.as_ref().map(block_to_json);
+            let spans = [body.span.clone()]
+                .into_iter()
+                .chain(
+                    catch
+                        .iter()
+                        .flat_map(|c| [c.variable.span(), c.body.span.clone()]),
+                )
+                .chain(finally.iter().map(|f| f.span.clone()))
+                .collect::<Vec<_>>();
+            let node = StatementJson::Try {
+                body,
+                catch,
+                finally,
+            };
+            Node::new(node, spans)
+        }
+        Statement::Import(i) => {
+            let spans = [i.keyword.span(), i.path.span()]
+                .into_iter()
+                .chain(i.alias.iter().map(Token::span))
+                .collect::<Vec<_>>();
+            let node = StatementJson::Import {
+                keyword: i.keyword.clone(),
+                path: i.path.clone(),
+                alias: i.alias.clone(),
+            };
+            Node::new(node, spans)
+        }
+        Statement::Export(e) => {
+            let declaration = boxed(&e.declaration);
+            let spans = [e.keyword.span(), declaration.span.clone()];
+            let node = StatementJson::Export {
+                keyword: e.keyword.clone(),
+                declaration,
+            };
+            Node::new(node, spans)
+        }
+        Statement::Test(t) => {
+            let body = block_to_json(&t.body);
+            let spans = [t.keyword.span(), t.name.span(), body.span.clone()];
+            let node = StatementJson::Test {
+                keyword: t.keyword.clone(),
+                name: t.name.clone(),
+                body,
+            };
+            Node::new(node, spans)
+        }
+        Statement::Error(_) => unreachable!("Only invalid programs have error nodes"),
+    }
+}
+
+fn block_to_json(block: &BlockStatement) -> Node<BlockJson> {
+    let statements: Vec<Node<StatementJson>> =
+        block.statements.iter().map(statement_to_json).collect();
+    let spans = [block.brace.span()]
+        .into_iter()
+        .chain(statements.iter().map(|s| s.span.clone()))
+        .collect::<Vec<_>>();
+    let node = BlockJson {
+        brace: block.brace.clone(),
+        statements,
+    };
+    Node::new(node, spans)
+}
+
+fn expression_to_json(expression: &Expression) -> Node<ExpressionJson> {
+    let boxed = |e: &Expression| Box::new(expression_to_json(e));
+    match expression {
+        Expression::Binary(b) => {
+            let left = boxed(&b.left);
+            let right = boxed(&b.right);
+            let spans = [left.span.clone(), b.operator.span(), right.span.clone()];
+            let node = ExpressionJson::Binary {
+                left,
+                operator: b.operator.clone(),
+                right,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Unary(u) => {
+            let operand = boxed(&u.operand);
+            let spans = [u.operator.span(), operand.span.clone()];
+            let node = ExpressionJson::Unary {
+                operator: u.operator.clone(),
+                operand,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Literal(l) => {
+            let (node, token) = match l {
+                LiteralExpression::Boolean(value, t) => (
+                    ExpressionJson::Boolean {
+                        value: *value,
+                        token: t.clone(),
+                    },
+                    t,
+                ),
+                LiteralExpression::Null(t) => (ExpressionJson::Nil { token: t.clone() }, t),
+                LiteralExpression::String(t) => (ExpressionJson::String { token: t.clone() }, t),
+                LiteralExpression::Number(t) => (ExpressionJson::Number { token: t.clone() }, t),
+            };
+            Node::new(node, [token.span()])
+        }
+        Expression::Grouping(g) => {
+            let expression = boxed(&g.0);
+            let span = expression.span.clone();
+            Node::new(ExpressionJson::Grouping { expression }, [span])
+        }
+        Expression::VariableReference(v) => {
+            let node = ExpressionJson::VariableReference {
+                identifier: v.identifier.clone(),
+            };
+            Node::new(node, [v.identifier.span()])
+        }
+        Expression::VariableAssignment(v) => {
+            let value = boxed(&v.value);
+            let spans = [v.identifier.span(), value.span.clone()];
+            let node = ExpressionJson::VariableAssignment {
+                identifier: v.identifier.clone(),
+                value,
+            };
+            Node::new(node, spans)
+        }
+        Expression::CompoundAssignment(c) => {
+            let value = boxed(&c.value);
+            let spans = [c.identifier.span(), c.operator.span(), value.span.clone()];
+            let node = ExpressionJson::CompoundAssignment {
+                identifier: c.identifier.clone(),
+                operator: c.operator.clone(),
+                value,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Update(u) => {
+            let spans = [u.identifier.span(), u.operator.span()];
+            let node = ExpressionJson::Update {
+                identifier: u.identifier.clone(),
+                operator: u.operator.clone(),
+                prefix: u.prefix,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Conditional(c) => {
+            let condition = boxed(&c.condition);
+            let then_branch = boxed(&c.then_branch);
+            let else_branch = boxed(&c.else_branch);
+            let spans = [
+                condition.span.clone(),
+                then_branch.span.clone(),
+                else_branch.span.clone(),
+            ];
+            let node = ExpressionJson::Conditional {
+                condition,
+                then_branch,
+                else_branch,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Call(c) => {
+            let callee = boxed(&c.callee);
+            let arguments: Vec<Node<ExpressionJson>> =
+                c.arguments.iter().map(expression_to_json).collect();
+            let spans = [callee.span.clone(), c.closing_parenthesis.span()]
+                .into_iter()
+                .chain(arguments.iter().map(|a| a.span.clone()))
+                .collect::<Vec<_>>();
+            let node = ExpressionJson::Call {
+                callee,
+                closing_parenthesis: c.closing_parenthesis.clone(),
+                arguments,
+            };
+            Node::new(node, spans)
+        }
+        Expression::Get(g) => {
+            let object = boxed(&g.object);
+            let spans = [object.span.clone(), g.name.span()];
+            let node = ExpressionJson::Get {
+                object,
+                name: g.name.clone(),
+            };
+            Node::new(node, spans)
+        }
+        Expression::Error(_) => unreachable!("Only invalid programs have error nodes"),
+    }
+}
+
+/// Build the statement, checking the guarantees that the parser gives on the syntax trees
+/// it builds, and that the interpreter relies on.
+fn statement_from_json(Node { node, .. }: Node<StatementJson>) -> Result<Statement, String> {
+    let boxed = |s: Box<Node<StatementJson>>| statement_from_json(*s).map(Box::new);
+    Ok(match node {
+        StatementJson::Expression { expression } => {
+            Statement::Expression(ExpressionStatement(expression_from_json(expression)?))
+        }
+        StatementJson::Print { expression } => {
+            Statement::Print(PrintStatement(expression_from_json(expression)?))
+        }
+        StatementJson::VariableDeclaration {
+            identifier,
+            initializer,
+            constant,
+        } => Statement::VariableDeclaration(VariableDeclarationStatement {
+            initializer: initializer.map(expression_from_json).transpose()?,
+            identifier,
+            constant,
+        }),
+        StatementJson::FunctionDeclaration {
+            name,
+            parameters,
+            rest,
+            body,
+            generator,
+        } => {
+            if parameters.len() + rest.iter().len() > MAX_ARGUMENTS {
+                return Err(format!(
+                    "`{}` has more than {MAX_ARGUMENTS} parameters",
+                    name.clone().lexeme()
+                ));
+            }
+            let mut defaults = false;
+            let parameters = parameters
+                .into_iter()
+                .map(|ParameterJson { name, default }| {
+                    if default.is_none() && defaults {
+                        let name = name.clone().lexeme();
+                        return Err(format!(
+                            "parameter `{name}` has no default, but follows a parameter with a default"
+                        ));
+                    }
+                    defaults |= default.is_some();
+                    Ok(Parameter {
+                        name,
+                        default: default.map(expression_from_json).transpose()?,
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+            Statement::FunctionDeclaration(FunctionDeclarationStatement {
+                name,
+                parameters,
+                rest,
+                body: body
+                    .into_iter()
+                    .map(statement_from_json)
+                    .collect::<Result<_, _>>()?,
+                generator,
+            })
+        }
+        StatementJson::Block(block) => Statement::Block(block_from_json(block)?),
+        StatementJson::IfElse {
+            condition,
+            if_branch,
+            else_branch,
+        } => Statement::IfElse(IfElseStatement {
+            condition: expression_from_json(condition)?,
+            if_branch: boxed(if_branch)?,
+            else_branch: else_branch.map(boxed).transpose()?,
+        }),
+        StatementJson::While { condition, body } => Statement::While(WhileStatement {
+            condition: expression_from_json(condition)?,
+            body: boxed(body)?,
+        }),
+        StatementJson::ForIn {
+            variable,
+            keyword,
+            iterable,
+            body,
+        } => Statement::ForIn(ForInStatement {
+            variable,
+            keyword,
+            iterable: expression_from_json(iterable)?,
+            body: boxed(body)?,
+        }),
+        StatementJson::Return { keyword, value } => Statement::Return(ReturnStatement {
+            keyword,
+            value: expression_from_json(value)?,
+        }),
+        StatementJson::Throw { keyword, value } => Statement::Throw(ThrowStatement {
+            keyword,
+            value: expression_from_json(value)?,
+        }),
+        StatementJson::Yield { keyword, value } => Statement::Yield(YieldStatement {
+            keyword,
+            value: expression_from_json(value)?,
+        }),
+        StatementJson::Try {
+            body,
+            catch,
+            finally,
+        } => {
+            if catch.is_none() && finally.is_none() {
+                return Err("a `try` statement has neither `catch` nor `finally`".to_owned());
+            }
+            Statement::Try(TryStatement {
+                body: block_from_json(body.node)?,
+                catch: catch
+                    .map(|CatchJson { variable, body }| {
+                        Ok::<_, String>(CatchClause {
+                            variable,
+                            body: block_from_json(body.node)?,
+                        })
+                    })
+                    .transpose()?,
+                finally: finally.map(|f| block_from_json(f.node)).transpose()?,
+            })
+        }
+        StatementJson::Import {
+            keyword,
+            path,
+            alias,
+        } => {
+            string_literal(&path)?;
+            Statement::Import(ImportStatement {
+                keyword,
+                path,
+                alias,
+            })
+        }
+        StatementJson::Export {
+            keyword,
+            declaration,
+        } => match declaration.node {
+            StatementJson::VariableDeclaration { .. }
+            | StatementJson::FunctionDeclaration { .. } => Statement::Export(ExportStatement {
+                keyword,
+                declaration: boxed(declaration)?,
+            }),
+            _ => return Err("only declarations can be exported".to_owned()),
+        },
+        StatementJson::Test {
+            keyword,
+            name,
+            body,
+        } => {
+            string_literal(&name)?;
+            Statement::Test(TestStatement {
+                keyword,
+                name,
+                body: block_from_json(body.node)?,
+            })
+        }
+    })
+}
+
+fn block_from_json(BlockJson { brace, statements }: BlockJson) -> Result<BlockStatement, String> {
+    Ok(BlockStatement {
+        brace,
+        statements: statements
+            .into_iter()
+            .map(statement_from_json)
+            .collect::<Result<_, _>>()?,
+    })
+}
+
+fn expression_from_json(Node { node, .. }: Node<ExpressionJson>) -> Result<Expression, String> {
+    let boxed = |e: Box<Node<ExpressionJson>>| expression_from_json(*e);
+    Ok(match node {
+        ExpressionJson::Binary {
+            left,
+            operator,
+            right,
+        } => Expression::binary(boxed(left)?, operator, boxed(right)?),
+        ExpressionJson::Unary { operator, operand } => Expression::unary(operator, boxed(operand)?),
+        ExpressionJson::Boolean { value, token } => Expression::boolean(value, token),
+        ExpressionJson::Nil { token } => Expression::null(token),
+        ExpressionJson::String { token } => {
+            string_literal(&token)?;
+            Expression::string(token)
+        }
+        ExpressionJson::Number { token } => match token.discriminant() {
+            TokenDiscriminant::Number | TokenDiscriminant::Integer => Expression::number(token),
+            _ => return Err(format!("`{}` is not a number", token.clone().lexeme())),
+        },
+        ExpressionJson::Grouping { expression } => Expression::grouping(boxed(expression)?),
+        ExpressionJson::VariableReference { identifier } => {
+            Expression::variable_reference(identifier)
+        }
+        ExpressionJson::VariableAssignment { identifier, value } => {
+            Expression::variable_assignment(identifier, boxed(value)?)
+        }
+        ExpressionJson::CompoundAssignment {
+            identifier,
+            operator,
+            value,
+        } => Expression::compound_assignment(identifier, operator, boxed(value)?),
+        ExpressionJson::Update {
+            identifier,
+            operator,
+            prefix,
+        } => Expression::update(identifier, operator, prefix),
+        ExpressionJson::Conditional {
+            condition,
+            then_branch,
+            else_branch,
+        } => Expression::conditional(boxed(condition)?, boxed(then_branch)?, boxed(else_branch)?),
+        ExpressionJson::Call {
+            callee,
+            closing_parenthesis,
+            arguments,
+        } => {
+            if arguments.len() > MAX_ARGUMENTS {
+                return Err(format!("a call has more than {MAX_ARGUMENTS} arguments"));
+            }
+            let arguments = arguments
+                .into_iter()
+                .map(expression_from_json)
+                .collect::<Result<_, _>>()?;
+            Expression::call(boxed(callee)?, closing_parenthesis, arguments)
+        }
+        ExpressionJson::Get { object, name } => Expression::get(boxed(object)?, name),
+    })
+}
+
+fn string_literal(token: &Token) -> Result<(), String> {
+    match token.discriminant() {
+        TokenDiscriminant::String => Ok(()),
+        _ => Err(format!("`{}` is not a string", token.clone().lexeme())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::Parser;
+    use crate::scanner::Scanner;
+    use crate::{
+        syntax_tree_from_json, syntax_tree_to_json, tokens_to_json, Environment, InMemoryLoader,
+        Interpreter,
+    };
+    use insta::assert_snapshot;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn tokens_have_a_kind_a_lexeme_a_line_and_a_span() {
+        assert_snapshot!(tokens_to_json("1\n\"a\""), @r###"
+        [
+          {
+            "kind": "Integer",
+            "lexeme": "1",
+            "line": 1,
+            "span": {
+              "start": 0,
+              "end": 1
+            },
+            "value": 1
+          },
+          {
+            "kind": "Trivia",
+            "lexeme": "\n",
+            "line": 2,
+            "span": {
+              "start": 1,
+              "end": 2
+            }
+          },
+          {
+            "kind": "String",
+            "lexeme": "\"a\"",
+            "line": 2,
+            "span": {
+              "start": 2,
+              "end": 5
+            },
+            "value": "a"
+          }
+        ]
+        "###);
+    }
+
+    #[test]
+    fn every_node_survives_a_round_trip() {
+        let source = r#"import "lib.lox" as lib;
+export const a = -1 + 2.5;
+var b;
+fun* f(x, y = nil, ...rest) {
+    yield x ? y : !true;
+    return f(x).next;
+}
+test "t" {
+    b = "s";
+    b += 1;
+    b++;
+    --b;
+}
+try {
+    throw (a);
+} catch (e) {
+    print e;
+} finally {
+    if (false) {} else while (b) for (c in b) {}
+}
+for (var i = 0; ; i++) {}
+"#;
+        let parsed = Parser::parse(Scanner::new(source)).unwrap();
+        let json = syntax_tree_to_json(source).unwrap();
+        let imported = syntax_tree_from_json(&json).unwrap();
+        assert_eq!(format!("{imported:?}"), format!("{parsed:?}"));
+    }
+
+    fn execute(json: &str) -> String {
+        let statements = syntax_tree_from_json(json).unwrap();
+        let mut output = Vec::new();
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        let mut interpreter = Interpreter::new(&mut output, environment, InMemoryLoader::new());
+        interpreter.batch_execute(statements).unwrap();
+        drop(interpreter);
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn exported_syntax_trees_can_be_executed() {
+        let source = "fun add(a, b) { return a + b; }\nprint add(1, 2) * 3;";
+        let json = syntax_tree_to_json(source).unwrap();
+        assert_snapshot!(execute(&json), @"9");
+    }
+
+    #[test]
+    fn every_node_has_a_span() {
+        // The span of `print -a;` leaves out `print` and `;`.
+        let json = syntax_tree_to_json("var a = 1;\nprint -a;").unwrap();
+        let tree: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let print = &tree[1];
+        assert_snapshot!(print["span"], @r###"
+        {"end":19,"start":17}
+        "###);
+        assert_snapshot!(print["expression"]["span"], @r###"
+        {"end":19,"start":17}
+        "###);
+        assert_snapshot!(print["expression"]["operand"]["span"], @r###"
+        {"end":19,"start":18}
+        "###);
+    }
+
+    #[test]
+    fn syntax_trees_can_be_built_by_hand() {
+        let json = r#"[{"type": "Print", "expression": {"type": "String", "token": {
+            "kind": "String", "lexeme": "\"hi\"", "line": 1, "span": {"start": 0, "end": 4}
+        }}}]"#;
+        assert_snapshot!(execute(json), @"hi");
+    }
+
+    #[test]
+    fn invalid_syntax_trees_are_rejected() {
+        let token = |kind: &str, lexeme: &str| {
+            format!(
+                r#"{{"kind": "{kind}", "lexeme": {lexeme:?}, "line": 1, "span": {{"start": 0, "end": {}}}}}"#,
+                lexeme.len()
+            )
+        };
+        let error = |json: String| syntax_tree_from_json(&json).unwrap_err().to_string();
+
+        assert_snapshot!(error(r#"[{"type": "Print", "expression": 1}]"#.to_owned()), @"Malformed syntax tree: invalid type: integer `1`, expected struct Node at line 1 column 35");
+        let print = |literal: String| format!(r#"[{{"type": "Print", "expression": {literal}}}]"#);
+        assert_snapshot!(error(print(format!(r#"{{"type": "Number", "token": {}}}"#, token("Identifier", "one")))), @"Invalid syntax tree: `one` is not a number.");
+        assert_snapshot!(error(print(format!(r#"{{"type": "Number", "token": {}}}"#, token("Integer", "1.5")))), @"Malformed syntax tree: `1.5` is a token of kind Number, not Integer at line 1 column 142");
+        assert_snapshot!(error(print(format!(r#"{{"type": "Number", "token": {}}}"#, token("Integer", "1 2")))), @"Malformed syntax tree: `1 2` is not a single token at line 1 column 142");
+        assert_snapshot!(error(r#"[{"type": "Error", "token": null}]"#.to_owned()), @"Malformed syntax tree: unknown variant `Error`, expected one of `Expression`, `Print`, `VariableDeclaration`, `FunctionDeclaration`, `Block`, `IfElse`, `While`, `ForIn`, `Return`, `Throw`, `Yield`, `Try`, `Import`, `Export`, `Test` at line 1 column 33");
+        let export = format!(
+            r#"[{{"type": "Export", "keyword": {}, "declaration": {{"type": "Block", "brace": {}, "statements": []}}}}]"#,
+            token("Export", "export"),
+            token("LeftBrace", "{")
+        );
+        assert_snapshot!(error(export), @"Invalid syntax tree: only declarations can be exported.");
+        let yield_ = format!(
+            r#"[{{"type": "Yield", "keyword": {}, "value": {{"type": "Nil", "token": {}}}}}]"#,
+            token("Yield", "yield"),
+            token("Nil", "nil")
+        );
+        assert_snapshot!(error(yield_), @"[line 1] Error at 'yield': Can only yield from a generator.");
+    }
+}
@@ -1,7 +1,24 @@
+mod cst;
+mod formatter;
+mod inspect;
 mod interpreter;
+mod json;
+mod linter;
+mod lsp;
 mod parser;
 mod repl;
+mod resolver;
 mod scanner;
 
-pub use interpreter::{Environment, ExecuteRawError, Interpreter, RuntimeError};
+pub use formatter::format_source;
+pub use inspect::{check, display_syntax_tree, display_tokens};
+pub use interpreter::{
+    Environment, ExecuteModuleError, ExecuteRawError, FileSystemLoader, InMemoryLoader,
+    Interpreter, ModuleLoader, ModuleLoaderError, RuntimeError, TestReport, TestResult,
+};
+pub use json::{syntax_tree_from_json, syntax_tree_to_json, tokens_to_json, SyntaxTreeJsonError};
+pub use linter::{lint, Diagnostic, LintConfig, LintConfigError, Rule, Severity};
+pub use lsp::{language_server, serve_language_server, LanguageServerError};
+pub use parser::SyntaxError;
 pub use repl::repl;
+pub use resolver::ResolverError;
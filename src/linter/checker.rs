@@ -0,0 +1,415 @@
+use crate::interpreter::{module_name, Arity};
+use crate::linter::{Diagnostic, Rule};
+use crate::parser::ast::{
+    BlockStatement, Expression, ExpressionStatement, FunctionDeclarationStatement,
+    LiteralExpression, PrintStatement, Statement,
+};
+use crate::scanner::{Token, TokenDiscriminant};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Run the rules that work on the AST, with their default severity.
+pub(super) fn check(program: &[Statement]) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        scopes: vec![Scope::default()],
+        diagnostics: vec![],
+    };
+    checker.statements(program);
+    checker.diagnostics
+}
+
+/// Like in the resolver, the global scope is the first one: unused globals are not
+/// reported, since they might be used by other modules or by tests.
+struct Checker {
+    scopes: Vec<Scope>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+type Scope = HashMap<String, Binding>;
+
+struct Binding {
+    kind: BindingKind,
+    line: u64,
+    used: bool,
+    /// The arity of the function bound to the name, if it is known statically.
+    arity: Option<Arity>,
+    /// Whether the value bound to the name can never be nil, e.g. a constant
+    /// initialized with a number.
+    never_nil: bool,
+}
+
+#[derive(PartialEq)]
+enum BindingKind {
+    Variable,
+    Parameter,
+    /// Functions, imports, loop and `catch` variables: they are not required to be used.
+    Other,
+}
+
+impl Checker {
+    fn report(&mut self, rule: Rule, line: u64, message: String) {
+        self.diagnostics.push(Diagnostic {
+            rule,
+            severity: rule.default_severity(),
+            line,
+            message,
+        });
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(Scope::default());
+    }
+
+    fn end_scope(&mut self) {
+        let scope = self.scopes.pop().unwrap();
+        for (name, binding) in scope {
+            if binding.used || binding.kind == BindingKind::Other || name.starts_with('_') {
+                continue;
+            }
+            let kind = match binding.kind {
+                BindingKind::Parameter => "Parameter",
+                _ => "Variable",
+            };
+            self.report(
+                Rule::UnusedVariable,
+                binding.line,
+                format!("{kind} `{name}` is never used."),
+            );
+        }
+    }
+
+    fn declare(&mut self, identifier: &Token, kind: BindingKind) -> &mut Binding {
+        let name = identifier.clone().lexeme();
+        self.declare_name(name, identifier.line(), kind)
+    }
+
+    fn declare_name(&mut self, name: String, line: u64, kind: BindingKind) -> &mut Binding {
+        let enclosing = &self.scopes[..self.scopes.len() - 1];
+        if let Some(shadowed) = enclosing.iter().rev().find_map(|scope| scope.get(&name)) {
+            let message = format!(
+                "`{name}` shadows the variable declared on line {}.",
+                shadowed.line
+            );
+            self.report(Rule::Shadowing, line, message);
+        }
+        let binding = Binding {
+            kind,
+            line,
+            used: false,
+            arity: None,
+            never_nil: false,
+        };
+        let current = self.scopes.last_mut().unwrap();
+        current.insert(name.clone(), binding);
+        current.get_mut(&name).unwrap()
+    }
+
+    fn lookup(&mut self, identifier: &Token) -> Option<&mut Binding> {
+        let name = identifier.clone().lexeme();
+        self.scopes
+            .iter_mut()
+            .rev()
+            .find_map(|scope| scope.get_mut(&name))
+    }
+
+    fn statements(&mut self, statements: &[Statement]) {
+        let terminator = statements.iter().enumerate().find_map(|(i, s)| match s {
+            Statement::Return(r) => Some((i, &r.keyword, "return")),
+            Statement::Throw(t) => Some((i, &t.keyword, "throw")),
+            _ => None,
+        });
+        if let Some((i, keyword, name)) = terminator {
+            // Reported on the first unreachable statement, where it can be allowed.
+            if let Some(unreachable) = statements.get(i + 1) {
+                let line = statement_line(unreachable).unwrap_or(keyword.line());
+                let message = format!("Unreachable code after `{name}`.");
+                self.report(Rule::UnreachableCode, line, message);
+            }
+        }
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    fn block(&mut self, BlockStatement { statements, .. }: &BlockStatement) {
+        self.begin_scope();
+        self.statements(statements);
+        self.end_scope();
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(s) => self.expression(&s.0),
+            Statement::Print(s) => self.expression(&s.0),
+            Statement::VariableDeclaration(s) => {
+                if let Some(initializer) = &s.initializer {
+                    self.expression(initializer);
+                }
+                let never_nil = s.constant
+                    && s.initializer
+                        .as_ref()
+                        .map(|initializer| self.never_nil(initializer))
+                        .unwrap_or(false);
+                self.declare(&s.identifier, BindingKind::Variable).never_nil = never_nil;
+            }
+            Statement::FunctionDeclaration(s) => self.function(s),
+            Statement::Block(s) => self.block(s),
+            Statement::IfElse(s) => {
+                self.condition(&s.condition);
+                self.statement(&s.if_branch);
+                if let Some(else_branch) = &s.else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While(s) => {
+                self.condition(&s.condition);
+                self.statement(&s.body);
+            }
+            Statement::ForIn(s) => {
+                self.expression(&s.iterable);
+                self.begin_scope();
+                self.declare(&s.variable, BindingKind::Other);
+                self.statement(&s.body);
+                self.end_scope();
+            }
+            Statement::Return(s) => self.expression(&s.value),
+            Statement::Throw(s) => self.expression(&s.value),
+            Statement::Yield(s) => self.expression(&s.value),
+            Statement::Try(s) => {
+                self.block(&s.body);
+                if let Some(catch) = &s.catch {
+                    self.begin_scope();
+                    self.declare(&catch.variable, BindingKind::Other);
+                    self.block(&catch.body);
+                    self.end_scope();
+                }
+                if let Some(finally) = &s.finally {
+                    self.block(finally);
+                }
+            }
+            Statement::Import(s) => match &s.alias {
+                Some(alias) => {
+                    self.declare(alias, BindingKind::Other);
+                }
+                None => {
+                    // Safe because the parser only accepts string literals as paths
+                    let path = s.path.ty().to_owned().string().unwrap();
+                    if let Some(name) = module_name(Path::new(&path)) {
+                        self.declare_name(name, s.path.line(), BindingKind::Other);
+                    }
+                }
+            },
+            Statement::Export(s) => self.statement(&s.declaration),
+            Statement::Test(s) => self.block(&s.body),
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn function(&mut self, declaration: &FunctionDeclarationStatement) {
+        // Declared before the body, so that recursive calls are checked too.
+        self.declare(&declaration.name, BindingKind::Other).arity = Some(Arity::of(declaration));
+        self.begin_scope();
+        for parameter in &declaration.parameters {
+            if let Some(default) = &parameter.default {
+                self.expression(default);
+            }
+            self.declare(&parameter.name, BindingKind::Parameter);
+        }
+        if let Some(rest) = &declaration.rest {
+            self.declare(rest, BindingKind::Parameter);
+        }
+        self.statements(&declaration.body);
+        self.end_scope();
+    }
+
+    fn condition(&mut self, condition: &Expression) {
+        let mut inner = condition;
+        while let Expression::Grouping(grouping) = inner {
+            inner = &grouping.0;
+        }
+        if let Expression::VariableAssignment(assignment) = inner {
+            self.report(
+                Rule::AssignmentInCondition,
+                assignment.identifier.line(),
+                "Assignment used as a condition. Did you mean `==`?".to_owned(),
+            );
+        }
+        self.expression(condition);
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Binary(e) => {
+                self.nil_comparison(&e.left, &e.operator, &e.right);
+                self.expression(&e.left);
+                self.expression(&e.right);
+            }
+            Expression::Unary(e) => self.expression(&e.operand),
+            Expression::Literal(_) => {}
+            Expression::Grouping(e) => self.expression(&e.0),
+            Expression::VariableReference(e) => {
+                if let Some(binding) = self.lookup(&e.identifier) {
+                    binding.used = true;
+                }
+            }
+            Expression::VariableAssignment(e) => {
+                self.expression(&e.value);
+                if let Some(binding) = self.lookup(&e.identifier) {
+                    binding.arity = None;
+                    binding.never_nil = false;
+                }
+            }
+            Expression::CompoundAssignment(e) => {
+                self.expression(&e.value);
+                if let Some(binding) = self.lookup(&e.identifier) {
+                    binding.used = true;
+                }
+            }
+            Expression::Update(e) => {
+                if let Some(binding) = self.lookup(&e.identifier) {
+                    binding.used = true;
+                }
+            }
+            Expression::Conditional(e) => {
+                self.expression(&e.condition);
+                self.expression(&e.then_branch);
+                self.expression(&e.else_branch);
+            }
+            Expression::Call(e) => {
+                self.expression(&e.callee);
+                for argument in &e.arguments {
+                    self.expression(argument);
+                }
+                if let Expression::VariableReference(callee) = e.callee.as_ref() {
+                    let arity = self.lookup(&callee.identifier).and_then(|b| b.arity);
+                    // Safe because the parser enforces that we do not have more than 255 arguments
+                    let n_arguments = e.arguments.len() as u8;
+                    if let Some(arity) = arity.filter(|arity| !arity.accepts(n_arguments)) {
+                        let name = callee.identifier.clone().lexeme();
+                        let arguments = if arity.is_singular() {
+                            "argument"
+                        } else {
+                            "arguments"
+                        };
+                        let were = if n_arguments == 1 { "was" } else { "were" };
+                        let message = format!(
+                            "`{name}` expects {arity} {arguments}, but {n_arguments} {were} given."
+                        );
+                        self.report(Rule::ArityMismatch, e.closing_parenthesis.line(), message);
+                    }
+                }
+            }
+            Expression::Get(e) => self.expression(&e.object),
+            Expression::Error(e) => {
+                for child in &e.children {
+                    self.expression(child);
+                }
+            }
+        }
+    }
+
+    fn nil_comparison(&mut self, left: &Expression, operator: &Token, right: &Expression) {
+        let always = match operator.discriminant() {
+            TokenDiscriminant::EqualEqual => "false",
+            TokenDiscriminant::BangEqual => "true",
+            _ => return,
+        };
+        let operand = match (is_nil(left), is_nil(right)) {
+            (true, false) => right,
+            (false, true) => left,
+            _ => return,
+        };
+        if self.never_nil(operand) {
+            let message =
+                format!("This comparison is always {always}: the operand can never be nil.");
+            self.report(Rule::NilComparison, operator.line(), message);
+        }
+    }
+
+    /// Whether `expression` is guaranteed not to evaluate to nil.
+    fn never_nil(&mut self, expression: &Expression) -> bool {
+        match expression {
+            Expression::Literal(LiteralExpression::Null(_)) => false,
+            Expression::Literal(_) => true,
+            // `and` and `or` evaluate to one of their operands.
+            Expression::Binary(e) => !matches!(
+                e.operator.discriminant(),
+                TokenDiscriminant::And | TokenDiscriminant::Or
+            ),
+            Expression::Unary(_) | Expression::CompoundAssignment(_) | Expression::Update(_) => {
+                true
+            }
+            Expression::Grouping(e) => self.never_nil(&e.0),
+            Expression::VariableAssignment(e) => self.never_nil(&e.value),
+            Expression::Conditional(e) => {
+                self.never_nil(&e.then_branch) && self.never_nil(&e.else_branch)
+            }
+            Expression::VariableReference(e) => self
+                .lookup(&e.identifier)
+                .map(|binding| binding.never_nil || binding.arity.is_some())
+                .unwrap_or(false),
+            Expression::Call(_) | Expression::Get(_) | Expression::Error(_) => false,
+        }
+    }
+}
+
+fn is_nil(expression: &Expression) -> bool {
+    match expression {
+        Expression::Literal(LiteralExpression::Null(_)) => true,
+        Expression::Grouping(e) => is_nil(&e.0),
+        _ => false,
+    }
+}
+
+/// The line where `statement` starts, i.e. the line of its leftmost token.
+/// `None` if it has no token to tell, e.g. an empty block.
+fn statement_line(statement: &Statement) -> Option<u64> {
+    match statement {
+        Statement::Expression(ExpressionStatement(e)) | Statement::Print(PrintStatement(e)) => {
+            expression_line(e)
+        }
+        Statement::VariableDeclaration(s) => Some(s.identifier.line()),
+        Statement::FunctionDeclaration(s) => Some(s.name.line()),
+        Statement::Block(BlockStatement { statements, .. }) => {
+            statements.first().and_then(statement_line)
+        }
+        Statement::IfElse(s) => expression_line(&s.condition),
+        Statement::While(s) => expression_line(&s.condition),
+        Statement::ForIn(s) => Some(s.variable.line()),
+        Statement::Return(s) => Some(s.keyword.line()),
+        Statement::Throw(s) => Some(s.keyword.line()),
+        Statement::Yield(s) => Some(s.keyword.line()),
+        Statement::Try(s) => s.body.statements.first().and_then(statement_line),
+        Statement::Import(s) => Some(s.keyword.line()),
+        Statement::Export(s) => Some(s.keyword.line()),
+        Statement::Test(s) => Some(s.keyword.line()),
+        Statement::Error(s) => s.token.as_ref().map(Token::line),
+    }
+}
+
+/// The line of the leftmost token of `expression`, if it has one.
+fn expression_line(expression: &Expression) -> Option<u64> {
+    match expression {
+        Expression::Binary(e) => expression_line(&e.left),
+        Expression::Unary(e) => Some(e.operator.line()),
+        Expression::Literal(
+            LiteralExpression::Boolean(_, t)
+            | LiteralExpression::Null(t)
+            | LiteralExpression::String(t)
+            | LiteralExpression::Number(t),
+        ) => Some(t.line()),
+        Expression::Grouping(e) => expression_line(&e.0),
+        Expression::VariableReference(e) => Some(e.identifier.line()),
+        Expression::VariableAssignment(e) => Some(e.identifier.line()),
+        Expression::CompoundAssignment(e) => Some(e.identifier.line()),
+        Expression::Update(e) => Some(e.identifier.line()),
+        Expression::Conditional(e) => expression_line(&e.condition),
+        Expression::Call(e) => expression_line(&e.callee),
+        Expression::Get(e) => expression_line(&e.object),
+        Expression::Error(e) => e
+            .children
+            .first()
+            .and_then(expression_line)
+            .or_else(|| e.token.as_ref().map(Token::line)),
+    }
+}
@@ -0,0 +1,61 @@
+use crate::linter::{Rule, Severity};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
+use toml::Spanned;
+
+/// The severity of each rule, or `None` if the rule is turned off.
+/// Rules that are not configured explicitly use their [`Rule::default_severity`].
+#[derive(Debug, Clone, Default)]
+pub struct LintConfig {
+    overrides: HashMap<Rule, Option<Severity>>,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("[line {line}] Invalid lint configuration: {msg}")]
+pub struct LintConfigError {
+    line: usize,
+    msg: String,
+}
+
+impl LintConfig {
+    /// Parse a TOML document mapping rule ids to `"off"`, `"warning"` or `"error"`, e.g.
+    /// `unused-variable = "off"`.
+    pub fn parse(text: &str) -> Result<Self, LintConfigError> {
+        let error = |span: Option<Range<usize>>, msg: String| LintConfigError {
+            line: span.map_or(1, |span| text[..span.start].matches('\n').count() + 1),
+            msg,
+        };
+        let entries: BTreeMap<Spanned<String>, Spanned<String>> = toml::from_str(text)
+            .map_err(|e| error(e.span(), e.message().trim_end().replace('\n', ", ")))?;
+        let mut overrides = HashMap::new();
+        for (rule, severity) in entries {
+            let rule_id = rule.get_ref();
+            let rule_id: Rule = rule_id
+                .parse()
+                .map_err(|_| error(Some(rule.span()), format!("unknown rule `{rule_id}`.")))?;
+            let severity = match severity.get_ref().as_str() {
+                "off" => None,
+                name => Some(name.parse().map_err(|_| {
+                    error(
+                        Some(severity.span()),
+                        format!("unknown severity `{name}`, expect `off`, `warning` or `error`."),
+                    )
+                })?),
+            };
+            overrides.insert(rule_id, severity);
+        }
+        Ok(Self { overrides })
+    }
+
+    /// `None` if `rule` is turned off.
+    pub fn severity(&self, rule: Rule) -> Option<Severity> {
+        self.overrides
+            .get(&rule)
+            .copied()
+            .unwrap_or(Some(rule.default_severity()))
+    }
+
+    pub fn set(&mut self, rule: Rule, severity: Option<Severity>) {
+        self.overrides.insert(rule, severity);
+    }
+}
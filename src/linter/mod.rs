@@ -0,0 +1,358 @@
+//! Static checks over the syntax tree of a program.
+//!
+//! Each [`Rule`] can be turned off, or have its [`Severity`] changed, by a [`LintConfig`].
+//! A single occurrence can be silenced with a comment, either at the end of the offending
+//! line or on the line before it:
+//!
+//! ```lox
+//! // lox-lint: allow(unused-variable, shadowing)
+//! var a = 1;
+//! ```
+mod checker;
+mod config;
+
+use crate::cst::{SyntaxElement, SyntaxKind, SyntaxNode};
+use crate::parser::Parser;
+use crate::scanner::{Scanner, TokenDiscriminant};
+use crate::ExecuteRawError;
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+
+pub use config::{LintConfig, LintConfigError};
+
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, strum_macros::Display, strum_macros::EnumString,
+)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Rule {
+    /// A local variable, or a parameter, that is never read.
+    /// Names starting with `_` are exempt.
+    UnusedVariable,
+    /// Statements after a `return` or a `throw` in the same block.
+    UnreachableCode,
+    /// A declaration with the same name as a variable of an enclosing scope.
+    Shadowing,
+    /// `if (a = b)`, which is usually a typo for `if (a == b)`.
+    AssignmentInCondition,
+    /// `a == nil` where `a` can never be nil.
+    NilComparison,
+    /// `{}`, unless it is the body of a function or it contains a comment.
+    EmptyBlock,
+    /// A call to a function declared in the program with the wrong number of arguments.
+    ArityMismatch,
+}
+
+impl Rule {
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Rule::ArityMismatch => Severity::Error,
+            _ => Severity::Warning,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum_macros::Display, strum_macros::EnumString)]
+#[strum(serialize_all = "kebab-case")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub line: u64,
+    pub message: String,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "[line {}] {}[{}]: {}",
+            self.line, self.severity, self.rule, self.message
+        )
+    }
+}
+
+/// Check `source` against the rules enabled in `config`.
+/// The diagnostics are sorted by line. Programs that do not parse are rejected.
+pub fn lint(source: &str, config: &LintConfig) -> Result<Vec<Diagnostic>, ExecuteRawError> {
+    let program = Parser::parse(Scanner::new(source)).map_err(ExecuteRawError::ParserError)?;
+    let mut diagnostics = checker::check(&program);
+    empty_blocks(&SyntaxNode::parse(source), &mut diagnostics);
+
+    let allowed = allow_comments(source);
+    let is_allowed = |d: &Diagnostic| {
+        allowed
+            .get(&d.line)
+            .map(|rules| rules.contains(&d.rule))
+            .unwrap_or(false)
+    };
+    let mut diagnostics: Vec<Diagnostic> = diagnostics
+        .into_iter()
+        .filter(|d| !is_allowed(d))
+        .filter_map(|d| {
+            let severity = config.severity(d.rule)?;
+            Some(Diagnostic { severity, ..d })
+        })
+        .collect();
+    diagnostics.sort_by(|a, b| (a.line, &a.message).cmp(&(b.line, &b.message)));
+    Ok(diagnostics)
+}
+
+/// The AST does not keep track of where blocks are, so empty blocks are found in the CST.
+fn empty_blocks(node: &SyntaxNode, diagnostics: &mut Vec<Diagnostic>) {
+    for child in &node.children {
+        let SyntaxElement::Node(child) = child else {
+            continue;
+        };
+        let is_empty = child.kind == SyntaxKind::Block
+            && node.kind != SyntaxKind::Function
+            && child.children.iter().all(|element| match element {
+                SyntaxElement::Node(_) => false,
+                SyntaxElement::Token(token) => !token.is_comment(),
+            });
+        if is_empty {
+            let brace = child
+                .tokens()
+                .find(|t| t.discriminant() == TokenDiscriminant::LeftBrace);
+            if let Some(brace) = brace {
+                diagnostics.push(Diagnostic {
+                    rule: Rule::EmptyBlock,
+                    severity: Rule::EmptyBlock.default_severity(),
+                    line: brace.line(),
+                    message: "Empty block.".to_owned(),
+                });
+            }
+        }
+        empty_blocks(child, diagnostics);
+    }
+}
+
+/// The rules silenced on each line by `// lox-lint: allow(...)` comments.
+/// A comment at the end of a line applies to that line, a comment on a line of its own
+/// applies to the next one.
+fn allow_comments(source: &str) -> HashMap<u64, HashSet<Rule>> {
+    let mut allowed: HashMap<u64, HashSet<Rule>> = HashMap::new();
+    let mut last_code_line = None;
+    for token in Scanner::new(source) {
+        if !token.is_comment() {
+            if token.discriminant() != TokenDiscriminant::Trivia {
+                last_code_line = Some(token.line());
+            }
+            continue;
+        }
+        let line = token.line();
+        let text = token.lexeme();
+        let Some(rules) = text
+            .trim_start_matches('/')
+            .trim()
+            .strip_prefix("lox-lint:")
+            .and_then(|directive| directive.trim().strip_prefix("allow("))
+            .and_then(|list| list.trim_end().strip_suffix(')'))
+        else {
+            continue;
+        };
+        let target = if last_code_line == Some(line) {
+            line
+        } else {
+            line + 1
+        };
+        allowed.entry(target).or_default().extend(
+            rules
+                .split(',')
+                .filter_map(|rule| rule.trim().parse::<Rule>().ok()),
+        );
+    }
+    allowed
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::linter::{lint, LintConfig};
+
+    fn diagnostics(source: &str) -> String {
+        diagnostics_with(source, &LintConfig::default())
+    }
+
+    fn diagnostics_with(source: &str, config: &LintConfig) -> String {
+        lint(source, config)
+            .unwrap()
+            .iter()
+            .map(|d| format!("{d}\n"))
+            .collect()
+    }
+
+    #[test]
+    fn unused_variables_and_parameters() {
+        let source = r#"
+var global = 1;
+fun f(used, unused, _ignored) {
+    var local = used;
+    var counter = 0;
+    counter += 1;
+    return 1;
+}
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 3] warning[unused-variable]: Parameter `unused` is never used.
+        [line 4] warning[unused-variable]: Variable `local` is never used.
+        "###);
+    }
+
+    #[test]
+    fn unreachable_code() {
+        let source = r#"
+fun f() {
+    return 1;
+    print "unreachable";
+}
+fun g() {
+    if (true) {
+        throw "error";
+    }
+    print "reachable";
+}
+fun h() {
+    throw "error";
+    // lox-lint: allow(unreachable-code)
+    print "unreachable, on purpose";
+}
+"#;
+        insta::assert_snapshot!(diagnostics(source), @"[line 4] warning[unreachable-code]: Unreachable code after `return`.");
+    }
+
+    #[test]
+    fn shadowing() {
+        let source = r#"
+var a = 1;
+fun f(a) {
+    {
+        var b = a;
+        {
+            var b = 2;
+            print b;
+        }
+        print b;
+    }
+}
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 3] warning[shadowing]: `a` shadows the variable declared on line 2.
+        [line 7] warning[shadowing]: `b` shadows the variable declared on line 5.
+        "###);
+    }
+
+    #[test]
+    fn assignment_in_condition() {
+        let source = r#"
+var a = 1;
+if (a = 2) print a;
+while ((a = 3)) print a;
+if (a == 2) print a;
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 3] warning[assignment-in-condition]: Assignment used as a condition. Did you mean `==`?
+        [line 4] warning[assignment-in-condition]: Assignment used as a condition. Did you mean `==`?
+        "###);
+    }
+
+    #[test]
+    fn nil_comparison() {
+        let source = r#"
+const one = 1;
+var maybe = nil;
+print 1 + 2 == nil;
+print nil != "a";
+print one == nil;
+print maybe == nil;
+print (maybe or 1) == nil;
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 4] warning[nil-comparison]: This comparison is always false: the operand can never be nil.
+        [line 5] warning[nil-comparison]: This comparison is always true: the operand can never be nil.
+        [line 6] warning[nil-comparison]: This comparison is always false: the operand can never be nil.
+        "###);
+    }
+
+    #[test]
+    fn empty_blocks() {
+        let source = r#"
+fun noop() {}
+if (true) {} else {
+    // Nothing to do
+}
+while (false) {
+}
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 3] warning[empty-block]: Empty block.
+        [line 6] warning[empty-block]: Empty block.
+        "###);
+    }
+
+    #[test]
+    fn arity_mismatch() {
+        let source = r#"
+fun f(a, b = 1) { return a + b; }
+fun g(a, ...rest) { print a; return rest; }
+fun h(a) { return a; }
+f();
+f(1);
+f(1, 2, 3);
+g(1, 2, 3);
+g();
+h(1, 2);
+h();
+"#;
+        insta::assert_snapshot!(diagnostics(source), @r###"
+        [line 5] error[arity-mismatch]: `f` expects 1 to 2 arguments, but 0 were given.
+        [line 7] error[arity-mismatch]: `f` expects 1 to 2 arguments, but 3 were given.
+        [line 9] error[arity-mismatch]: `g` expects at least 1 argument, but 0 were given.
+        [line 10] error[arity-mismatch]: `h` expects 1 argument, but 2 were given.
+        [line 11] error[arity-mismatch]: `h` expects 1 argument, but 0 were given.
+        "###);
+    }
+
+    #[test]
+    fn rules_can_be_allowed_inline() {
+        let source = r#"
+fun f() {
+    // lox-lint: allow(unused-variable)
+    var a = 1;
+    var b = 2; // lox-lint: allow(unused-variable, shadowing)
+    var c = 3;
+}
+"#;
+        insta::assert_snapshot!(diagnostics(source), @"[line 6] warning[unused-variable]: Variable `c` is never used.
+");
+    }
+
+    #[test]
+    fn rules_can_be_configured() {
+        let config = LintConfig::parse(
+            "# Local overrides\nunused-variable = \"off\"\nshadowing = \"error\"\n",
+        )
+        .unwrap();
+        let source = "var a = 1;\nfun f(a) { var b; }\n";
+        insta::assert_snapshot!(diagnostics_with(source, &config), @"[line 2] error[shadowing]: `a` shadows the variable declared on line 1.
+");
+    }
+
+    #[test]
+    fn invalid_configurations_are_reported_with_their_line() {
+        let errors = [
+            "shadowing = \"error\"\nunused-variable = off\n",
+            "shadowing = \"error\"\n\nunknown-rule = \"off\"\n",
+            "shadowing = \"fatal\"\n",
+        ]
+        .map(|text| LintConfig::parse(text).unwrap_err().to_string());
+        insta::assert_snapshot!(errors.join("\n"), @r###"
+        [line 2] Invalid lint configuration: invalid string, expected `"`, `'`
+        [line 3] Invalid lint configuration: unknown rule `unknown-rule`.
+        [line 1] Invalid lint configuration: unknown severity `fatal`, expect `off`, `warning` or `error`.
+        "###);
+    }
+}
@@ -0,0 +1,575 @@
+//! What the language server knows about a document: its errors, the symbols it declares
+//! and where they are referenced.
+use crate::interpreter::module_name;
+use crate::parser::ast::{BlockStatement, Expression, FunctionDeclarationStatement, Statement};
+use crate::parser::IncrementalParser;
+use crate::resolver::resolve;
+use crate::scanner::{Token, TokenDiscriminant, TokenType};
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+
+pub(super) struct Analysis {
+    /// The significant tokens of the document, i.e. without trivia.
+    tokens: Vec<Token>,
+    pub(super) problems: Vec<Problem>,
+    pub(super) symbols: Vec<Symbol>,
+    pub(super) references: Vec<Reference>,
+}
+
+/// An error reported by the scanner, the parser or the resolver.
+pub(super) struct Problem {
+    pub(super) span: Range<usize>,
+    pub(super) message: String,
+}
+
+pub(super) struct Symbol {
+    pub(super) name: String,
+    pub(super) kind: SymbolKind,
+    /// The name in the declaration.
+    pub(super) span: Range<usize>,
+    /// The whole declaration, e.g. a function including its body.
+    pub(super) extent: Range<usize>,
+    /// How the symbol is declared, e.g. the signature of a function.
+    pub(super) detail: String,
+    /// The function the symbol is declared in, `None` for globals.
+    pub(super) parent: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum SymbolKind {
+    Function,
+    Variable,
+    Constant,
+    Parameter,
+    Module,
+}
+
+/// A name in scope, for completion.
+type Name = (String, SymbolKind);
+
+/// An occurrence of a symbol outside of its declaration.
+pub(super) struct Reference {
+    pub(super) span: Range<usize>,
+    pub(super) symbol: usize,
+}
+
+impl Analysis {
+    pub(super) fn new(parser: &IncrementalParser) -> Self {
+        let source = parser.source();
+        let statements = parser.statements();
+        let mut problems: Vec<Problem> = parser
+            .tokens()
+            .iter()
+            .filter_map(|t| match t.ty() {
+                TokenType::SyntaxError { error_msg } => Some(Problem {
+                    span: t.span(),
+                    message: match error_msg {
+                        Some(msg) => format!("{msg}."),
+                        None => format!("Unexpected character `{}`.", t.clone().lexeme()),
+                    },
+                }),
+                _ => None,
+            })
+            .collect();
+
+        for error in parser.errors() {
+            let span = match &error.token {
+                Some(t) => t.span(),
+                None => source.len()..source.len(),
+            };
+            // Scanner errors are already reported.
+            if !problems.iter().any(|p| p.span == span) {
+                let message = error.message();
+                problems.push(Problem { span, message });
+            }
+        }
+        // The partial syntax tree is still worth checking: error nodes stand in for the
+        // malformed code.
+        if let Err(errors) = resolve(statements) {
+            problems.extend(errors.iter().map(|e| Problem {
+                span: e.token().span(),
+                message: e.message().to_owned(),
+            }));
+        }
+
+        let tokens: Vec<Token> = parser
+            .tokens()
+            .iter()
+            .filter(|t| t.discriminant() != TokenDiscriminant::Trivia)
+            .cloned()
+            .collect();
+        let mut collector = Collector {
+            source,
+            tokens: &tokens,
+            scopes: vec![HashMap::new()],
+            functions: vec![],
+            symbols: vec![],
+            references: vec![],
+        };
+        collector.program(statements);
+        let Collector {
+            symbols,
+            references,
+            ..
+        } = collector;
+        Self {
+            tokens,
+            problems,
+            symbols,
+            references,
+        }
+    }
+
+    /// The symbol declared or referenced at `offset`, including the position right after
+    /// its name, where the cursor usually is.
+    pub(super) fn symbol_at(&self, offset: usize) -> Option<usize> {
+        let contains = |span: &Range<usize>| span.start <= offset && offset <= span.end;
+        self.symbols
+            .iter()
+            .position(|s| contains(&s.span))
+            .or_else(|| {
+                self.references
+                    .iter()
+                    .find(|r| contains(&r.span))
+                    .map(|r| r.symbol)
+            })
+    }
+
+    /// Where `symbol` is referenced, its declaration excluded.
+    pub(super) fn references_to(&self, symbol: usize) -> impl Iterator<Item = &Range<usize>> {
+        self.references
+            .iter()
+            .filter(move |r| r.symbol == symbol)
+            .map(|r| &r.span)
+    }
+
+    /// The names declared in the document that are visible at `offset`, innermost first.
+    ///
+    /// It only looks at tokens, rather than at the syntax tree, because completion is
+    /// mostly requested while typing, when the code around the cursor does not parse.
+    /// Globals are visible everywhere, even before their declaration: functions can refer
+    /// to globals declared after them.
+    pub(super) fn names_in_scope(&self, offset: usize) -> Vec<Name> {
+        let mut scopes: Vec<Vec<Name>> = vec![vec![]];
+        // Parameters, and `catch` or loop variables, belong to the block that follows them.
+        let mut pending = vec![];
+        let before_cursor = self.tokens.iter().take_while(|t| t.span().end <= offset);
+        for (i, token) in before_cursor.enumerate() {
+            match token.discriminant() {
+                TokenDiscriminant::LeftBrace => scopes.push(std::mem::take(&mut pending)),
+                TokenDiscriminant::RightBrace if scopes.len() > 1 => {
+                    scopes.pop();
+                }
+                TokenDiscriminant::Semicolon => pending.clear(),
+                _ => {
+                    let (declared, parameters) = self.declaration_at(i);
+                    scopes.last_mut().unwrap().extend(declared);
+                    pending.extend(parameters);
+                }
+            }
+        }
+
+        let mut globals = vec![];
+        let mut depth = 0;
+        for (i, token) in self.tokens.iter().enumerate() {
+            match token.discriminant() {
+                TokenDiscriminant::LeftBrace => depth += 1,
+                TokenDiscriminant::RightBrace => depth -= 1,
+                _ if depth == 0 => globals.extend(self.declaration_at(i).0),
+                _ => {}
+            }
+        }
+        scopes[0] = globals;
+
+        let mut names: Vec<Name> = vec![];
+        for (name, kind) in scopes.into_iter().rev().flatten() {
+            if !names.iter().any(|(n, _)| *n == name) {
+                names.push((name, kind));
+            }
+        }
+        names
+    }
+
+    /// The name declared by the token at `index`, if it is `var`, `const` or `fun`, and
+    /// the names that are bound in the following block: parameters, `catch` and loop
+    /// variables.
+    fn declaration_at(&self, index: usize) -> (Option<Name>, Vec<Name>) {
+        let kind_at = |i: usize| self.tokens.get(i).map(|t| t.discriminant());
+        let name_at = |i: usize| {
+            self.tokens
+                .get(i)
+                .filter(|t| t.discriminant() == TokenDiscriminant::Identifier)
+                .map(|t| t.clone().lexeme())
+        };
+        match self.tokens[index].discriminant() {
+            TokenDiscriminant::Var => (
+                name_at(index + 1).map(|n| (n, SymbolKind::Variable)),
+                vec![],
+            ),
+            TokenDiscriminant::Const => (
+                name_at(index + 1).map(|n| (n, SymbolKind::Constant)),
+                vec![],
+            ),
+            TokenDiscriminant::Fun => {
+                let mut i = index + 1;
+                if kind_at(i) == Some(TokenDiscriminant::Star) {
+                    i += 1;
+                }
+                let name = name_at(i).map(|n| (n, SymbolKind::Function));
+                let mut parameters = vec![];
+                let mut depth = 0;
+                for (j, token) in self.tokens.iter().enumerate().skip(i + 1) {
+                    match token.discriminant() {
+                        TokenDiscriminant::LeftParen => depth += 1,
+                        TokenDiscriminant::RightParen => depth -= 1,
+                        TokenDiscriminant::Identifier if depth == 1 => {
+                            let follows_separator = matches!(
+                                kind_at(j - 1),
+                                Some(
+                                    TokenDiscriminant::LeftParen
+                                        | TokenDiscriminant::Comma
+                                        | TokenDiscriminant::Ellipsis
+                                )
+                            );
+                            if follows_separator {
+                                parameters.push((token.clone().lexeme(), SymbolKind::Parameter));
+                            }
+                        }
+                        _ => {}
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                (name, parameters)
+            }
+            TokenDiscriminant::Catch | TokenDiscriminant::For
+                if kind_at(index + 1) == Some(TokenDiscriminant::LeftParen) =>
+            {
+                let is_bound = match self.tokens[index].discriminant() {
+                    TokenDiscriminant::Catch => true,
+                    _ => kind_at(index + 3) == Some(TokenDiscriminant::In),
+                };
+                let variable = name_at(index + 2)
+                    .filter(|_| is_bound)
+                    .map(|n| (n, SymbolKind::Variable));
+                (None, variable.into_iter().collect())
+            }
+            _ => (None, vec![]),
+        }
+    }
+}
+
+/// Walks the syntax tree, like the resolver, to bind each reference to its declaration.
+struct Collector<'a> {
+    source: &'a str,
+    tokens: &'a [Token],
+    scopes: Vec<HashMap<String, usize>>,
+    /// The functions enclosing the current node, innermost last.
+    functions: Vec<usize>,
+    symbols: Vec<Symbol>,
+    references: Vec<Reference>,
+}
+
+impl<'a> Collector<'a> {
+    fn program(&mut self, statements: &[Statement]) {
+        // Globals are declared upfront, since functions can refer to globals declared
+        // after them.
+        for statement in statements {
+            let statement = match statement {
+                Statement::Export(export) => export.declaration.as_ref(),
+                statement => statement,
+            };
+            match statement {
+                Statement::VariableDeclaration(s) => {
+                    let (kind, keyword) = if s.constant {
+                        (SymbolKind::Constant, "const")
+                    } else {
+                        (SymbolKind::Variable, "var")
+                    };
+                    let detail = format!("{keyword} {}", s.identifier.clone().lexeme());
+                    self.declare(&s.identifier, kind, detail);
+                }
+                Statement::FunctionDeclaration(s) => {
+                    self.declare_function(s);
+                }
+                _ => {}
+            }
+        }
+        for statement in statements {
+            self.statement(statement);
+        }
+    }
+
+    /// Declare `identifier` in the current scope. Globals declared upfront are not
+    /// declared again.
+    fn declare(&mut self, identifier: &Token, kind: SymbolKind, detail: String) -> usize {
+        self.declare_as(identifier.clone().lexeme(), identifier, kind, detail)
+    }
+
+    /// Like [`Collector::declare`], for a symbol whose name is not the lexeme of the token
+    /// that declares it.
+    fn declare_as(
+        &mut self,
+        name: String,
+        identifier: &Token,
+        kind: SymbolKind,
+        detail: String,
+    ) -> usize {
+        let span = identifier.span();
+        if let Some(&existing) = self.scopes.last().unwrap().get(&name) {
+            if self.symbols[existing].span == span {
+                return existing;
+            }
+        }
+        let extent = match kind {
+            SymbolKind::Function => self.function_extent(identifier),
+            _ => span.clone(),
+        };
+        self.symbols.push(Symbol {
+            name: name.clone(),
+            kind,
+            span,
+            extent,
+            detail,
+            parent: self.functions.last().copied(),
+        });
+        let index = self.symbols.len() - 1;
+        self.scopes.last_mut().unwrap().insert(name, index);
+        index
+    }
+
+    fn declare_function(&mut self, declaration: &FunctionDeclarationStatement) -> usize {
+        let detail = self.signature(declaration);
+        self.declare(&declaration.name, SymbolKind::Function, detail)
+    }
+
+    fn reference(&mut self, identifier: &Token) {
+        let name = identifier.clone().lexeme();
+        let symbol = self.scopes.iter().rev().find_map(|scope| scope.get(&name));
+        // Natives are not declared in the document.
+        if let Some(&symbol) = symbol {
+            self.references.push(Reference {
+                span: identifier.span(),
+                symbol,
+            });
+        }
+    }
+
+    /// The index of the significant token starting at the same offset as `token`.
+    fn index_of(&self, token: &Token) -> Option<usize> {
+        self.tokens
+            .binary_search_by_key(&token.span().start, |t| t.span().start)
+            .ok()
+    }
+
+    /// The index of the token that closes the parenthesis or the brace at `open`.
+    fn matching(&self, open: usize) -> Option<usize> {
+        let mut depth = 0;
+        for (i, token) in self.tokens.iter().enumerate().skip(open) {
+            match token.discriminant() {
+                TokenDiscriminant::LeftParen | TokenDiscriminant::LeftBrace => depth += 1,
+                TokenDiscriminant::RightParen | TokenDiscriminant::RightBrace => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Some(i);
+            }
+        }
+        None
+    }
+
+    /// From the `fun` keyword to the end of the body of the function named `name`.
+    fn function_extent(&self, name: &Token) -> Range<usize> {
+        let Some(index) = self.index_of(name) else {
+            return name.span();
+        };
+        let start = self.tokens[..index]
+            .iter()
+            .rev()
+            .take(2)
+            .find(|t| t.discriminant() == TokenDiscriminant::Fun)
+            .map(|t| t.span().start)
+            .unwrap_or(name.span().start);
+        let end = self
+            .matching(index + 1)
+            .and_then(|close| self.matching(close + 1))
+            .map(|close| self.tokens[close].span().end)
+            .unwrap_or(name.span().end);
+        start..end
+    }
+
+    /// `fun name(parameters)`, as written in the source, but on a single line.
+    fn signature(&self, declaration: &FunctionDeclarationStatement) -> String {
+        let keyword = if declaration.generator { "fun*" } else { "fun" };
+        let mut signature = format!("{keyword} {}", declaration.name.clone().lexeme());
+        let Some(index) = self.index_of(&declaration.name) else {
+            return signature;
+        };
+        let Some(close) = self.matching(index + 1) else {
+            return signature;
+        };
+        for i in index + 1..=close {
+            let token = &self.tokens[i];
+            let separated = self.tokens[i - 1].span().end < token.span().start;
+            let after_name = i == index + 1;
+            if separated && !after_name {
+                signature.push(' ');
+            }
+            signature.push_str(&self.source[token.span()]);
+        }
+        signature
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn block(&mut self, BlockStatement { statements, .. }: &BlockStatement) {
+        self.begin_scope();
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.end_scope();
+    }
+
+    fn statement(&mut self, statement: &Statement) {
+        match statement {
+            Statement::Expression(s) => self.expression(&s.0),
+            Statement::Print(s) => self.expression(&s.0),
+            Statement::VariableDeclaration(s) => {
+                if let Some(initializer) = &s.initializer {
+                    self.expression(initializer);
+                }
+                let (kind, keyword) = if s.constant {
+                    (SymbolKind::Constant, "const")
+                } else {
+                    (SymbolKind::Variable, "var")
+                };
+                let detail = format!("{keyword} {}", s.identifier.clone().lexeme());
+                self.declare(&s.identifier, kind, detail);
+            }
+            Statement::FunctionDeclaration(s) => self.function(s),
+            Statement::Block(s) => self.block(s),
+            Statement::IfElse(s) => {
+                self.expression(&s.condition);
+                self.statement(&s.if_branch);
+                if let Some(else_branch) = &s.else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While(s) => {
+                self.expression(&s.condition);
+                self.statement(&s.body);
+            }
+            Statement::ForIn(s) => {
+                self.expression(&s.iterable);
+                self.begin_scope();
+                let detail = format!("(variable) {}", s.variable.clone().lexeme());
+                self.declare(&s.variable, SymbolKind::Variable, detail);
+                self.statement(&s.body);
+                self.end_scope();
+            }
+            Statement::Return(s) => self.expression(&s.value),
+            Statement::Throw(s) => self.expression(&s.value),
+            Statement::Yield(s) => self.expression(&s.value),
+            Statement::Try(s) => {
+                self.block(&s.body);
+                if let Some(catch) = &s.catch {
+                    self.begin_scope();
+                    let detail = format!("(variable) {}", catch.variable.clone().lexeme());
+                    self.declare(&catch.variable, SymbolKind::Variable, detail);
+                    self.block(&catch.body);
+                    self.end_scope();
+                }
+                if let Some(finally) = &s.finally {
+                    self.block(finally);
+                }
+            }
+            Statement::Import(s) => {
+                let detail = format!("import {}", s.path.clone().lexeme());
+                match &s.alias {
+                    Some(alias) => {
+                        self.declare(alias, SymbolKind::Module, detail);
+                    }
+                    None => {
+                        // Safe because the parser only accepts string literals as paths
+                        let path = s.path.ty().to_owned().string().unwrap();
+                        if let Some(name) = module_name(Path::new(&path)) {
+                            self.declare_as(name, &s.path, SymbolKind::Module, detail);
+                        }
+                    }
+                }
+            }
+            Statement::Export(s) => self.statement(&s.declaration),
+            Statement::Test(s) => self.block(&s.body),
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn function(&mut self, declaration: &FunctionDeclarationStatement) {
+        let symbol = self.declare_function(declaration);
+        self.functions.push(symbol);
+        self.begin_scope();
+        for parameter in &declaration.parameters {
+            if let Some(default) = &parameter.default {
+                self.expression(default);
+            }
+            let detail = format!("(parameter) {}", parameter.name.clone().lexeme());
+            self.declare(&parameter.name, SymbolKind::Parameter, detail);
+        }
+        if let Some(rest) = &declaration.rest {
+            let detail = format!("(parameter) ...{}", rest.clone().lexeme());
+            self.declare(rest, SymbolKind::Parameter, detail);
+        }
+        for statement in &declaration.body {
+            self.statement(statement);
+        }
+        self.end_scope();
+        self.functions.pop();
+    }
+
+    fn expression(&mut self, expression: &Expression) {
+        match expression {
+            Expression::Binary(e) => {
+                self.expression(&e.left);
+                self.expression(&e.right);
+            }
+            Expression::Unary(e) => self.expression(&e.operand),
+            Expression::Literal(_) => {}
+            Expression::Grouping(e) => self.expression(&e.0),
+            Expression::VariableReference(e) => self.reference(&e.identifier),
+            Expression::VariableAssignment(e) => {
+                self.expression(&e.value);
+                self.reference(&e.identifier);
+            }
+            Expression::CompoundAssignment(e) => {
+                self.expression(&e.value);
+                self.reference(&e.identifier);
+            }
+            Expression::Update(e) => self.reference(&e.identifier),
+            Expression::Conditional(e) => {
+                self.expression(&e.condition);
+                self.expression(&e.then_branch);
+                self.expression(&e.else_branch);
+            }
+            Expression::Call(e) => {
+                self.expression(&e.callee);
+                for argument in &e.arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::Get(e) => self.expression(&e.object),
+            Expression::Error(e) => {
+                for child in &e.children {
+                    self.expression(child);
+                }
+            }
+        }
+    }
+}
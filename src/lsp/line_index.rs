@@ -0,0 +1,68 @@
+use lsp_types::Position;
+use std::ops::Range;
+
+/// Converts byte offsets in a document to LSP positions and back.
+/// LSP positions count characters in UTF-16 code units, the default encoding of the protocol.
+pub(super) struct LineIndex<'a> {
+    source: &'a str,
+    /// The byte offset where each line starts.
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(super) fn new(source: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+        Self {
+            source,
+            line_starts,
+        }
+    }
+
+    pub(super) fn position(&self, offset: usize) -> Position {
+        let offset = offset.min(self.source.len());
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let start = self.line_starts[line];
+        let character = self.source[start..offset].encode_utf16().count();
+        Position::new(line as u32, character as u32)
+    }
+
+    pub(super) fn range(&self, span: Range<usize>) -> lsp_types::Range {
+        lsp_types::Range::new(self.position(span.start), self.position(span.end))
+    }
+
+    /// Positions past the end of a line are clamped to the end of the line.
+    pub(super) fn offset(&self, position: Position) -> usize {
+        let Some(start) = self.line_starts.get(position.line as usize).copied() else {
+            return self.source.len();
+        };
+        let line = self.source[start..].split('\n').next().unwrap_or_default();
+        let mut units = 0;
+        for (i, c) in line.char_indices() {
+            if units >= position.character as usize {
+                return start + i;
+            }
+            units += c.len_utf16();
+        }
+        start + line.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lsp::line_index::LineIndex;
+    use lsp_types::Position;
+
+    #[test]
+    fn positions_count_utf16_code_units() {
+        let source = "var a = 1;\nprint \"😀\" + a;\n";
+        let index = LineIndex::new(source);
+        let offset = source.rfind('a').unwrap();
+        let position = index.position(offset);
+        assert_eq!(position, Position::new(1, 13));
+        assert_eq!(index.offset(position), offset);
+        assert_eq!(index.offset(Position::new(0, 99)), 10);
+        assert_eq!(index.offset(Position::new(9, 0)), source.len());
+    }
+}
@@ -0,0 +1,409 @@
+//! A language server for Lox, speaking the Language Server Protocol.
+//!
+//! It keeps the documents opened by the client in memory and analyses them on every
+//! change. The client sends the edits rather than whole documents, so only the
+//! declarations around an edit are parsed again.
+mod analysis;
+mod line_index;
+
+use crate::lsp::analysis::{Analysis, SymbolKind};
+use crate::lsp::line_index::LineIndex;
+use crate::parser::IncrementalParser;
+use crate::scanner::keywords;
+use crate::scanner::TextEdit;
+use crate::Environment;
+use lsp_server::{Connection, ErrorCode, ExtractError, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{
+    Completion, DocumentSymbolRequest, GotoDefinition, HoverRequest, References,
+    Request as LspRequest,
+};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionParams, CompletionResponse,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse,
+    GotoDefinitionParams, GotoDefinitionResponse, Hover, HoverContents, HoverParams,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, OneOf, PublishDiagnosticsParams,
+    ReferenceParams, ServerCapabilities, TextDocumentContentChangeEvent,
+    TextDocumentPositionParams, TextDocumentSyncCapability, TextDocumentSyncKind, Uri,
+};
+use std::collections::HashMap;
+
+#[derive(Debug, thiserror::Error)]
+pub enum LanguageServerError {
+    #[error(transparent)]
+    Protocol(#[from] lsp_server::ProtocolError),
+    #[error("The connection with the client was closed.")]
+    Disconnected,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Run the language server over stdin and stdout, until the client asks it to exit.
+pub fn language_server() -> Result<(), LanguageServerError> {
+    let (connection, io_threads) = Connection::stdio();
+    serve_language_server(connection)?;
+    io_threads.join()?;
+    Ok(())
+}
+
+/// Run the language server over `connection`, until the client asks it to exit.
+/// Use [`Connection::memory`] to talk to the server from the same process, e.g. in tests.
+pub fn serve_language_server(connection: Connection) -> Result<(), LanguageServerError> {
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(
+            TextDocumentSyncKind::INCREMENTAL,
+        )),
+        definition_provider: Some(OneOf::Left(true)),
+        references_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        document_symbol_provider: Some(OneOf::Left(true)),
+        completion_provider: Some(CompletionOptions::default()),
+        ..Default::default()
+    };
+    // Safe because `ServerCapabilities` always serializes to JSON
+    connection.initialize(serde_json::to_value(capabilities).unwrap())?;
+
+    let mut server = Server {
+        connection: &connection,
+        documents: HashMap::new(),
+        natives: Environment::new()
+            .global_names()
+            .map(str::to_owned)
+            .collect(),
+    };
+    for message in &connection.receiver {
+        match message {
+            Message::Request(request) => {
+                if connection.handle_shutdown(&request)? {
+                    return Ok(());
+                }
+                server.request(request)?;
+            }
+            Message::Notification(notification) => server.notification(notification)?,
+            Message::Response(_) => {}
+        }
+    }
+    Ok(())
+}
+
+struct Server<'a> {
+    connection: &'a Connection,
+    documents: HashMap<Uri, Document>,
+    /// The names of the native functions, always in scope.
+    natives: Vec<String>,
+}
+
+struct Document {
+    parser: IncrementalParser,
+    analysis: Analysis,
+}
+
+impl Document {
+    fn new(source: String) -> Self {
+        let parser = IncrementalParser::new(source);
+        let analysis = Analysis::new(&parser);
+        Self { parser, analysis }
+    }
+
+    /// Apply the changes in order, then analyse the result.
+    fn change(&mut self, changes: Vec<TextDocumentContentChangeEvent>) {
+        for change in changes {
+            match change.range {
+                Some(range) => {
+                    let index = self.line_index();
+                    let (start, end) = (index.offset(range.start), index.offset(range.end));
+                    let edit = TextEdit {
+                        range: start.min(end)..end.max(start),
+                        text: change.text,
+                    };
+                    self.parser.edit(&edit);
+                }
+                None => self.parser = IncrementalParser::new(change.text),
+            }
+        }
+        self.analysis = Analysis::new(&self.parser);
+    }
+
+    fn line_index(&self) -> LineIndex<'_> {
+        LineIndex::new(self.parser.source())
+    }
+
+    /// The symbol at `position`, if any.
+    fn symbol_at(&self, position: lsp_types::Position) -> Option<usize> {
+        self.analysis.symbol_at(self.line_index().offset(position))
+    }
+}
+
+impl<'a> Server<'a> {
+    fn send(&self, message: impl Into<Message>) -> Result<(), LanguageServerError> {
+        self.connection
+            .sender
+            .send(message.into())
+            .map_err(|_| LanguageServerError::Disconnected)
+    }
+
+    fn request(&mut self, request: Request) -> Result<(), LanguageServerError> {
+        let response = match request.method.as_str() {
+            GotoDefinition::METHOD => self.respond::<GotoDefinition>(request, Self::definition),
+            References::METHOD => self.respond::<References>(request, Self::references),
+            HoverRequest::METHOD => self.respond::<HoverRequest>(request, Self::hover),
+            DocumentSymbolRequest::METHOD => {
+                self.respond::<DocumentSymbolRequest>(request, Self::document_symbols)
+            }
+            Completion::METHOD => self.respond::<Completion>(request, Self::completion),
+            method => Response::new_err(
+                request.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("Unsupported request `{method}`."),
+            ),
+        };
+        self.send(response)
+    }
+
+    fn respond<R: LspRequest>(
+        &self,
+        request: Request,
+        handler: fn(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        let id = request.id.clone();
+        match request.extract::<R::Params>(R::METHOD) {
+            Ok((id, params)) => Response::new_ok(id, handler(self, params)),
+            Err(e) => {
+                let message = match e {
+                    ExtractError::JsonError { error, .. } => error.to_string(),
+                    ExtractError::MethodMismatch(r) => format!("Unexpected `{}`.", r.method),
+                };
+                Response::new_err(id, ErrorCode::InvalidParams as i32, message)
+            }
+        }
+    }
+
+    /// Malformed notifications are ignored: there is no way to report an error.
+    fn notification(&mut self, notification: Notification) -> Result<(), LanguageServerError> {
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Ok(params) =
+                    notification.extract::<DidOpenTextDocumentParams>(DidOpenTextDocument::METHOD)
+                else {
+                    return Ok(());
+                };
+                let document = params.text_document;
+                self.documents
+                    .insert(document.uri.clone(), Document::new(document.text));
+                self.publish_diagnostics(document.uri, Some(document.version))
+            }
+            DidChangeTextDocument::METHOD => {
+                let Ok(params) = notification
+                    .extract::<DidChangeTextDocumentParams>(DidChangeTextDocument::METHOD)
+                else {
+                    return Ok(());
+                };
+                let document = params.text_document;
+                let Some(open) = self.documents.get_mut(&document.uri) else {
+                    return Ok(());
+                };
+                open.change(params.content_changes);
+                self.publish_diagnostics(document.uri, Some(document.version))
+            }
+            DidCloseTextDocument::METHOD => {
+                let Ok(params) = notification
+                    .extract::<DidCloseTextDocumentParams>(DidCloseTextDocument::METHOD)
+                else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                self.publish_diagnostics(params.text_document.uri, None)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Send the problems in the document at `uri`: none if the document is closed.
+    fn publish_diagnostics(
+        &self,
+        uri: Uri,
+        version: Option<i32>,
+    ) -> Result<(), LanguageServerError> {
+        let diagnostics = match self.documents.get(&uri) {
+            None => vec![],
+            Some(document) => {
+                let index = document.line_index();
+                document
+                    .analysis
+                    .problems
+                    .iter()
+                    .map(|problem| Diagnostic {
+                        range: index.range(problem.span.clone()),
+                        severity: Some(DiagnosticSeverity::ERROR),
+                        source: Some("jlox".to_owned()),
+                        message: problem.message.clone(),
+                        ..Default::default()
+                    })
+                    .collect()
+            }
+        };
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version,
+        };
+        self.send(Notification::new(
+            PublishDiagnostics::METHOD.to_owned(),
+            params,
+        ))
+    }
+
+    fn document(&self, position: &TextDocumentPositionParams) -> Option<&Document> {
+        self.documents.get(&position.text_document.uri)
+    }
+
+    fn definition(&self, params: GotoDefinitionParams) -> Option<GotoDefinitionResponse> {
+        let position = params.text_document_position_params;
+        let document = self.document(&position)?;
+        let symbol = document.symbol_at(position.position)?;
+        let span = document.analysis.symbols[symbol].span.clone();
+        Some(GotoDefinitionResponse::Scalar(Location::new(
+            position.text_document.uri,
+            document.line_index().range(span),
+        )))
+    }
+
+    fn references(&self, params: ReferenceParams) -> Option<Vec<Location>> {
+        let position = params.text_document_position;
+        let document = self.document(&position)?;
+        let symbol = document.symbol_at(position.position)?;
+        let index = document.line_index();
+        let declaration = params
+            .context
+            .include_declaration
+            .then_some(&document.analysis.symbols[symbol].span);
+        let locations = declaration
+            .into_iter()
+            .chain(document.analysis.references_to(symbol))
+            .map(|span| {
+                Location::new(
+                    position.text_document.uri.clone(),
+                    index.range(span.clone()),
+                )
+            })
+            .collect();
+        Some(locations)
+    }
+
+    fn hover(&self, params: HoverParams) -> Option<Hover> {
+        let position = params.text_document_position_params;
+        let document = self.document(&position)?;
+        let symbol = &document.analysis.symbols[document.symbol_at(position.position)?];
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: format!("```lox\n{}\n```", symbol.detail),
+            }),
+            range: None,
+        })
+    }
+
+    fn document_symbols(&self, params: DocumentSymbolParams) -> Option<DocumentSymbolResponse> {
+        let document = self.documents.get(&params.text_document.uri)?;
+        let index = document.line_index();
+        let symbols = &document.analysis.symbols;
+
+        // Symbols are declared before the symbols nested in them, so children can be
+        // attached to their parent by going backwards.
+        let mut nodes: Vec<Option<DocumentSymbol>> = symbols
+            .iter()
+            .map(|symbol| {
+                let kind = match symbol.kind {
+                    SymbolKind::Function => lsp_types::SymbolKind::FUNCTION,
+                    SymbolKind::Variable => lsp_types::SymbolKind::VARIABLE,
+                    SymbolKind::Constant => lsp_types::SymbolKind::CONSTANT,
+                    SymbolKind::Module => lsp_types::SymbolKind::MODULE,
+                    SymbolKind::Parameter => return None,
+                };
+                #[allow(deprecated)]
+                Some(DocumentSymbol {
+                    name: symbol.name.clone(),
+                    detail: Some(symbol.detail.clone()),
+                    kind,
+                    tags: None,
+                    deprecated: None,
+                    range: index.range(symbol.extent.clone()),
+                    selection_range: index.range(symbol.span.clone()),
+                    children: None,
+                })
+            })
+            .collect();
+        for i in (0..nodes.len()).rev() {
+            let Some(parent) = symbols[i].parent else {
+                continue;
+            };
+            if let Some(node) = nodes[i].take() {
+                if let Some(parent) = &mut nodes[parent] {
+                    parent.children.get_or_insert_with(Vec::new).insert(0, node);
+                }
+            }
+        }
+        // Globals are declared upfront, before the other symbols.
+        let mut roots: Vec<DocumentSymbol> = symbols
+            .iter()
+            .zip(nodes)
+            .filter(|(symbol, _)| symbol.parent.is_none())
+            .filter_map(|(_, node)| node)
+            .collect();
+        roots.sort_by_key(|node| (node.range.start.line, node.range.start.character));
+        Some(DocumentSymbolResponse::Nested(roots))
+    }
+
+    fn completion(&self, params: CompletionParams) -> Option<CompletionResponse> {
+        let position = params.text_document_position;
+        let document = self.document(&position)?;
+        let offset = document.line_index().offset(position.position);
+        let analysis = &document.analysis;
+
+        let mut items: Vec<CompletionItem> = analysis
+            .names_in_scope(offset)
+            .into_iter()
+            .map(|(name, kind)| {
+                let detail = analysis
+                    .symbols
+                    .iter()
+                    .find(|s| s.name == name && s.kind == kind)
+                    .map(|s| s.detail.clone());
+                let kind = match kind {
+                    SymbolKind::Function => CompletionItemKind::FUNCTION,
+                    SymbolKind::Constant => CompletionItemKind::CONSTANT,
+                    SymbolKind::Module => CompletionItemKind::MODULE,
+                    SymbolKind::Variable | SymbolKind::Parameter => CompletionItemKind::VARIABLE,
+                };
+                CompletionItem {
+                    label: name,
+                    kind: Some(kind),
+                    detail,
+                    ..Default::default()
+                }
+            })
+            .collect();
+        let mut natives: Vec<&String> = self.natives.iter().collect();
+        natives.sort();
+        for native in natives {
+            if !items.iter().any(|item| item.label == *native) {
+                items.push(CompletionItem {
+                    label: native.clone(),
+                    kind: Some(CompletionItemKind::FUNCTION),
+                    detail: Some("(native)".to_owned()),
+                    ..Default::default()
+                });
+            }
+        }
+        items.extend(keywords().map(|keyword| CompletionItem {
+            label: keyword.to_owned(),
+            kind: Some(CompletionItemKind::KEYWORD),
+            ..Default::default()
+        }));
+        Some(CompletionResponse::Array(items))
+    }
+}
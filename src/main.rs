@@ -1,25 +1,124 @@
-use jlox::{repl, Environment, Interpreter};
+use jlox::{
+    check, display_syntax_tree, display_tokens, format_source, language_server, lint, repl,
+    syntax_tree_from_json, syntax_tree_to_json, tokens_to_json, Environment, ExecuteModuleError,
+    ExecuteRawError, FileSystemLoader, Interpreter, LintConfig, Severity,
+};
 use std::cell::RefCell;
-use std::io::stdout;
-use std::path::PathBuf;
+use std::io::{stdout, Read};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
+const USAGE: &str = "Usage: jlox [command] [input]
+
+Commands:
+  run <input>     Execute the program (the default command)
+  run --ast <input>
+                  Execute a syntax tree in JSON, as printed by `ast --json`
+  check <input>   Report the errors in the program, without executing it
+  tokens <input>  Print the tokens of the program
+  tokens --json <input>
+                  Print the tokens of the program as JSON
+  ast <input>     Print the syntax tree of the program
+  ast --json <input>
+                  Print the syntax tree of the program as JSON
+  test <input>    Run the tests declared in the program
+  fmt <input>     Format the program: files are rewritten, other inputs printed
+  fmt --check <input>
+                  Exit with status 1 if the program is not formatted
+  lint <input>    Report suspicious code, using the rules in `lox-lint.toml`, if present
+  lint --config <file> <input>
+                  Use the rules in <file>
+  repl            Start an interactive session (the default without arguments)
+  lsp             Start a language server, speaking the Language Server Protocol over stdio
+
+Input:
+  <file>          Read the program from a file
+  -               Read the program from stdin
+  -e <code>       Pass the program as an argument";
+
+enum Command {
+    Run,
+    /// Execute a syntax tree in JSON rather than source code.
+    RunAst,
+    Check,
+    Tokens {
+        json: bool,
+    },
+    Ast {
+        json: bool,
+    },
+    Test,
+    Format {
+        check: bool,
+    },
+    Lint {
+        config: Option<PathBuf>,
+    },
+}
+
+/// The configuration file used by `lint`, if it exists in the working directory.
+const LINT_CONFIG: &str = "lox-lint.toml";
+
+/// Where the source code of the program comes from.
+enum Input {
+    File(PathBuf),
+    Stdin,
+    Inline(String),
+}
+
 fn main() -> Result<(), std::io::Error> {
     let args: Vec<String> = std::env::args().collect();
     // The first element in the arguments list is the name of the binary.
     // Then the actual binary arguments, flags and options.
-    if args.len() == 1 {
-        repl()?;
-    } else if args.len() == 2 {
-        let filepath = PathBuf::from(&args[1]);
-        let file = std::fs::read_to_string(filepath)?;
-        let environment = Rc::new(RefCell::new(Environment::new()));
-        if let Err(e) = Interpreter::new(stdout(), environment).execute_raw(&file) {
-            eprintln!("{}", e);
-            std::process::exit(65);
+    let (command, input) = match &args[1..] {
+        [] => return repl(),
+        [command] if command == "repl" => return repl(),
+        [command] if command == "lsp" => {
+            if let Err(e) = language_server() {
+                eprintln!("{e}");
+                std::process::exit(1);
+            }
+            return Ok(());
         }
-    } else {
-        println!("Usage: jlox [script]");
+        [command, input @ ..] => match command.as_str() {
+            "run" => match input {
+                [flag, input @ ..] if flag == "--ast" => (Command::RunAst, parse_input(input)),
+                input => (Command::Run, parse_input(input)),
+            },
+            "check" => (Command::Check, parse_input(input)),
+            "tokens" => match input {
+                [flag, input @ ..] if flag == "--json" => {
+                    (Command::Tokens { json: true }, parse_input(input))
+                }
+                input => (Command::Tokens { json: false }, parse_input(input)),
+            },
+            "ast" => match input {
+                [flag, input @ ..] if flag == "--json" => {
+                    (Command::Ast { json: true }, parse_input(input))
+                }
+                input => (Command::Ast { json: false }, parse_input(input)),
+            },
+            "test" => (Command::Test, parse_input(input)),
+            "fmt" => match input {
+                [flag, input @ ..] if flag == "--check" => {
+                    (Command::Format { check: true }, parse_input(input))
+                }
+                input => (Command::Format { check: false }, parse_input(input)),
+            },
+            "lint" => match input {
+                [flag, config, input @ ..] if flag == "--config" => (
+                    Command::Lint {
+                        config: Some(config.into()),
+                    },
+                    parse_input(input),
+                ),
+                input => (Command::Lint { config: None }, parse_input(input)),
+            },
+            _ => (Command::Run, parse_input(&args[1..])),
+        },
+    };
+    let Some(input) = input else {
+        eprintln!("{USAGE}");
         // Why 64, you ask?
         //
         // If you run (on a Linux machine):
@@ -33,6 +132,189 @@ fn main() -> Result<(), std::io::Error> {
         // #define EX_USAGE        64      /* command line usage error */
         // ```
         std::process::exit(64);
+    };
+
+    match command {
+        Command::Run => run(input),
+        Command::RunAst => run_syntax_tree(input),
+        Command::Check => {
+            if let Err(e) = check(&input.read()) {
+                exit_with_error(e);
+            }
+        }
+        Command::Tokens { json: false } => print!("{}", display_tokens(&input.read())),
+        Command::Tokens { json: true } => println!("{}", tokens_to_json(&input.read())),
+        Command::Ast { json: false } => match display_syntax_tree(&input.read()) {
+            Ok(tree) => print!("{tree}"),
+            Err(e) => exit_with_error(e),
+        },
+        Command::Ast { json: true } => match syntax_tree_to_json(&input.read()) {
+            Ok(tree) => println!("{tree}"),
+            Err(e) => exit_with_error(e),
+        },
+        Command::Test => test(input),
+        Command::Format { check } => format(input, check)?,
+        Command::Lint { config } => run_linter(input, config)?,
+    }
+    Ok(())
+}
+
+fn parse_input(args: &[String]) -> Option<Input> {
+    match args {
+        [dash] if dash == "-" => Some(Input::Stdin),
+        [flag, code] if flag == "-e" => Some(Input::Inline(code.to_owned())),
+        [path] if !path.starts_with('-') => Some(Input::File(path.into())),
+        _ => None,
+    }
+}
+
+impl Input {
+    /// The source code of the program.
+    /// It terminates the process if the input cannot be read.
+    fn read(&self) -> String {
+        let outcome = match self {
+            Input::File(path) => std::fs::read_to_string(path),
+            Input::Stdin => {
+                let mut source = String::new();
+                std::io::stdin().read_to_string(&mut source).map(|_| source)
+            }
+            Input::Inline(code) => Ok(code.clone()),
+        };
+        outcome.unwrap_or_else(|e| exit_with_unreadable_input(self, e))
+    }
+
+    /// An interpreter that writes to stdout.
+    /// Imports are resolved relative to the location of the input file or, if the program
+    /// does not come from a file, to the working directory.
+    fn interpreter(&self) -> Interpreter<'static> {
+        let root = match self {
+            Input::File(path) => path.parent().unwrap_or_else(|| Path::new("")),
+            Input::Stdin | Input::Inline(_) => Path::new("."),
+        };
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        Interpreter::new(stdout(), environment, FileSystemLoader::new(root))
+    }
+}
+
+/// The specifier of the module at `path`, relative to its own directory.
+fn specifier(path: &Path) -> String {
+    path.file_name()
+        .map(|f| f.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+fn run(input: Input) {
+    let mut interpreter = input.interpreter();
+    let outcome = match &input {
+        Input::File(path) => interpreter.execute_module(&specifier(path)),
+        input => interpreter
+            .execute_raw(&input.read())
+            .map_err(ExecuteModuleError::from),
+    };
+    match outcome {
+        Ok(()) => {}
+        Err(ExecuteModuleError::LoaderError(e)) => exit_with_unreadable_input(&input, e),
+        Err(ExecuteModuleError::ExecuteRawError(e)) => exit_with_error(e),
+    }
+}
+
+fn run_syntax_tree(input: Input) {
+    let statements = syntax_tree_from_json(&input.read()).unwrap_or_else(|e| {
+        eprintln!("{e}");
+        // `EX_DATAERR`, like invalid programs
+        std::process::exit(65);
+    });
+    if let Err(e) = input.interpreter().batch_execute(statements) {
+        exit_with_error(ExecuteRawError::RuntimeError(e));
+    }
+}
+
+fn test(input: Input) {
+    let mut interpreter = input.interpreter();
+    let outcome = match &input {
+        Input::File(path) => interpreter.test_module(&specifier(path)),
+        input => interpreter
+            .test_raw(&input.read())
+            .map_err(ExecuteModuleError::from),
+    };
+    let report = match outcome {
+        Ok(report) => report,
+        Err(ExecuteModuleError::LoaderError(e)) => exit_with_unreadable_input(&input, e),
+        Err(ExecuteModuleError::ExecuteRawError(e)) => exit_with_error(e),
+    };
+    println!("{}", report);
+    if report.n_failed() > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn format(input: Input, check: bool) -> Result<(), std::io::Error> {
+    let source = input.read();
+    let formatted = format_source(&source).unwrap_or_else(|e| exit_with_error(e));
+    if check {
+        if formatted != source {
+            match &input {
+                Input::File(path) => eprintln!("`{}` is not formatted.", path.display()),
+                Input::Stdin | Input::Inline(_) => eprintln!("The input is not formatted."),
+            }
+            std::process::exit(1);
+        }
+    } else {
+        match &input {
+            Input::File(path) if formatted != source => std::fs::write(path, formatted)?,
+            Input::File(_) => {}
+            Input::Stdin | Input::Inline(_) => print!("{formatted}"),
+        }
+    }
+    Ok(())
+}
+
+fn run_linter(input: Input, config: Option<PathBuf>) -> Result<(), std::io::Error> {
+    let config_path = config.or_else(|| {
+        let default = PathBuf::from(LINT_CONFIG);
+        default.exists().then_some(default)
+    });
+    let config = match config_path {
+        Some(path) => {
+            let text = std::fs::read_to_string(&path)?;
+            LintConfig::parse(&text).unwrap_or_else(|e| {
+                eprintln!("{}: {e}", path.display());
+                // `EX_CONFIG`
+                std::process::exit(78);
+            })
+        }
+        None => LintConfig::default(),
+    };
+    let diagnostics = lint(&input.read(), &config).unwrap_or_else(|e| exit_with_error(e));
+    for diagnostic in &diagnostics {
+        println!("{diagnostic}");
+    }
+    if diagnostics.iter().any(|d| d.severity == Severity::Error) {
+        std::process::exit(1);
     }
     Ok(())
 }
+
+/// Report `e` and terminate the process with the matching `sysexits.h` status:
+/// 65 (`EX_DATAERR`) if the program is invalid, 70 (`EX_SOFTWARE`) if it failed at runtime.
+/// Programs that call `exit` terminate with the status they asked for.
+fn exit_with_error(e: ExecuteRawError) -> ! {
+    let status = match &e {
+        ExecuteRawError::ParserError(_) | ExecuteRawError::ResolverError(_) => 65,
+        ExecuteRawError::RuntimeError(e) => match e.exit_code() {
+            Some(code) => std::process::exit(code),
+            None => 70,
+        },
+    };
+    eprintln!("{}", e);
+    std::process::exit(status);
+}
+
+/// Terminate the process with status 66 (`EX_NOINPUT`).
+fn exit_with_unreadable_input(input: &Input, e: impl std::fmt::Display) -> ! {
+    match input {
+        Input::File(path) => eprintln!("Cannot read `{}`: {e}", path.display()),
+        Input::Stdin | Input::Inline(_) => eprintln!("Cannot read from stdin: {e}"),
+    }
+    std::process::exit(66);
+}
@@ -9,7 +9,15 @@ pub enum Statement {
     Block(BlockStatement),
     IfElse(IfElseStatement),
     While(WhileStatement),
+    ForIn(ForInStatement),
     Return(ReturnStatement),
+    Throw(ThrowStatement),
+    Yield(YieldStatement),
+    Try(TryStatement),
+    Import(ImportStatement),
+    Export(ExportStatement),
+    Test(TestStatement),
+    Error(ErrorStatement),
 }
 
 #[derive(Debug, Clone)]
@@ -19,19 +27,40 @@ pub struct ExpressionStatement(pub Expression);
 pub struct PrintStatement(pub Expression);
 
 #[derive(Debug, Clone)]
-pub struct BlockStatement(pub Vec<Statement>);
+pub struct BlockStatement {
+    /// The `{` that opens the block or, for the blocks a `for` loop is desugared into,
+    /// the `for` keyword.
+    pub brace: Token,
+    pub statements: Vec<Statement>,
+}
 
 #[derive(Debug, Clone)]
 pub struct VariableDeclarationStatement {
     pub initializer: Option<Expression>,
     pub identifier: Token,
+    /// `true` for `const` declarations, `false` for `var` declarations.
+    pub constant: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct FunctionDeclarationStatement {
     pub name: Token,
-    pub parameters: Vec<Token>,
+    pub parameters: Vec<Parameter>,
+    /// `...rest`: the parameter that collects all arguments in excess, as a list.
+    pub rest: Option<Token>,
     pub body: Vec<Statement>,
+    /// `fun*`: calling the function returns a generator instead of executing the body.
+    pub generator: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct Parameter {
+    pub name: Token,
+    /// Evaluated, in the scope of the function, when the caller does not provide
+    /// a value for the parameter.
+    /// The parser guarantees that parameters without a default never follow parameters
+    /// with a default.
+    pub default: Option<Expression>,
 }
 
 #[derive(Debug, Clone)]
@@ -47,12 +76,83 @@ pub struct WhileStatement {
     pub body: Box<Statement>,
 }
 
+/// `for (variable in iterable) body`
+#[derive(Debug, Clone)]
+pub struct ForInStatement {
+    pub variable: Token,
+    /// The `in` keyword, used to locate the errors raised while iterating.
+    pub keyword: Token,
+    pub iterable: Expression,
+    pub body: Box<Statement>,
+}
+
 #[derive(Debug, Clone)]
 pub struct ReturnStatement {
     pub keyword: Token,
     pub value: Expression,
 }
 
+/// `test "name" { ... }`: a unit test, only executed by the test runner.
+#[derive(Debug, Clone)]
+pub struct TestStatement {
+    pub keyword: Token,
+    /// A string literal.
+    pub name: Token,
+    pub body: BlockStatement,
+}
+
+#[derive(Debug, Clone)]
+pub struct YieldStatement {
+    pub keyword: Token,
+    pub value: Expression,
+}
+
+#[derive(Debug, Clone)]
+pub struct ThrowStatement {
+    pub keyword: Token,
+    pub value: Expression,
+}
+
+/// `try { ... } catch (e) { ... } finally { ... }`.
+/// The parser guarantees that at least one between `catch` and `finally` is present.
+#[derive(Debug, Clone)]
+pub struct TryStatement {
+    pub body: BlockStatement,
+    pub catch: Option<CatchClause>,
+    pub finally: Option<BlockStatement>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CatchClause {
+    pub variable: Token,
+    pub body: BlockStatement,
+}
+
+/// `import "path/to/module.lox" as name;`
+#[derive(Debug, Clone)]
+pub struct ImportStatement {
+    pub keyword: Token,
+    /// A string literal.
+    pub path: Token,
+    /// If missing, the module is bound to the name of the imported file, without extension.
+    pub alias: Option<Token>,
+}
+
+/// `export` followed by a variable, constant or function declaration.
+#[derive(Debug, Clone)]
+pub struct ExportStatement {
+    pub keyword: Token,
+    pub declaration: Box<Statement>,
+}
+
+/// Stands in for a statement the parser could not make sense of.
+/// Only the ASTs of invalid programs contain error nodes.
+#[derive(Debug, Clone)]
+pub struct ErrorStatement {
+    /// The token the parser choked on, `None` if the input ended too early.
+    pub token: Option<Token>,
+}
+
 #[derive(Debug, Clone)]
 pub enum Expression {
     Binary(BinaryExpression),
@@ -61,7 +161,12 @@ pub enum Expression {
     Grouping(GroupingExpression),
     VariableReference(VariableReferenceExpression),
     VariableAssignment(VariableAssignmentExpression),
+    CompoundAssignment(CompoundAssignmentExpression),
+    Update(UpdateExpression),
+    Conditional(ConditionalExpression),
     Call(CallExpression),
+    Get(GetExpression),
+    Error(ErrorExpression),
 }
 
 impl Expression {
@@ -80,8 +185,8 @@ impl Expression {
         })
     }
 
-    pub fn boolean(b: bool) -> Self {
-        Self::Literal(LiteralExpression::Boolean(b))
+    pub fn boolean(b: bool, t: Token) -> Self {
+        Self::Literal(LiteralExpression::Boolean(b, t))
     }
 
     pub fn string(t: Token) -> Self {
@@ -111,6 +216,34 @@ impl Expression {
         })
     }
 
+    pub fn compound_assignment(identifier: Token, operator: Token, value: Expression) -> Self {
+        Self::CompoundAssignment(CompoundAssignmentExpression {
+            identifier,
+            operator,
+            value: Box::new(value),
+        })
+    }
+
+    pub fn update(identifier: Token, operator: Token, prefix: bool) -> Self {
+        Self::Update(UpdateExpression {
+            identifier,
+            operator,
+            prefix,
+        })
+    }
+
+    pub fn conditional(
+        condition: Expression,
+        then_branch: Expression,
+        else_branch: Expression,
+    ) -> Self {
+        Self::Conditional(ConditionalExpression {
+            condition: Box::new(condition),
+            then_branch: Box::new(then_branch),
+            else_branch: Box::new(else_branch),
+        })
+    }
+
     pub fn call(
         callee: Expression,
         closing_parenthesis: Token,
@@ -122,6 +255,17 @@ impl Expression {
             arguments,
         })
     }
+
+    pub fn get(object: Expression, name: Token) -> Self {
+        Self::Get(GetExpression {
+            object: Box::new(object),
+            name,
+        })
+    }
+
+    pub fn error(token: Option<Token>, children: Vec<Expression>) -> Self {
+        Self::Error(ErrorExpression { token, children })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -152,11 +296,40 @@ pub struct VariableAssignmentExpression {
     pub value: Box<Expression>,
 }
 
+/// `a += b`, `a -= b`, `a *= b` and `a /= b`.
+#[derive(Debug, Clone)]
+pub struct CompoundAssignmentExpression {
+    pub identifier: Token,
+    pub operator: Token,
+    pub value: Box<Expression>,
+}
+
+/// Increment (`++`) or decrement (`--`) of a variable, either prefix (`++a`) or postfix (`a++`).
+/// Prefix updates evaluate to the new value, postfix updates to the old one.
+#[derive(Debug, Clone)]
+pub struct UpdateExpression {
+    pub identifier: Token,
+    pub operator: Token,
+    pub prefix: bool,
+}
+
+/// `condition ? then_branch : else_branch`.
+/// Only the branch selected by `condition` is evaluated.
+#[derive(Debug, Clone)]
+pub struct ConditionalExpression {
+    pub condition: Box<Expression>,
+    pub then_branch: Box<Expression>,
+    pub else_branch: Box<Expression>,
+}
+
 #[derive(Debug, Clone)]
 pub enum LiteralExpression {
-    Boolean(bool),
+    /// The token is `true` or `false` or, for the condition of a `for` loop without one,
+    /// the `for` keyword.
+    Boolean(bool, Token),
     Null(Token),
     String(Token),
+    /// Either a `Number` or an `Integer` token.
     Number(Token),
 }
 
@@ -166,6 +339,25 @@ pub struct GroupingExpression(pub Box<Expression>);
 #[derive(Debug, Clone)]
 pub struct CallExpression {
     pub callee: Box<Expression>,
+    /// In the AST of an invalid program, the opening parenthesis stands in for a missing
+    /// closing one.
     pub closing_parenthesis: Token,
     pub arguments: Vec<Expression>,
 }
+
+/// Property access, e.g. `error.message`.
+#[derive(Debug, Clone)]
+pub struct GetExpression {
+    pub object: Box<Expression>,
+    pub name: Token,
+}
+
+/// Stands in for an expression the parser could not make sense of, e.g. `1 = 2` or `a.`.
+/// Only the ASTs of invalid programs contain error nodes.
+#[derive(Debug, Clone)]
+pub struct ErrorExpression {
+    /// The token the parser choked on, `None` if the input ended too early.
+    pub token: Option<Token>,
+    /// The well-formed sub-expressions that were parsed before the error, e.g. `a` in `a.`.
+    pub children: Vec<Expression>,
+}
@@ -0,0 +1,512 @@
+//! Incremental parsing, for editors: after an edit, only the tokens and the top-level
+//! declarations around it are scanned and parsed again.
+use crate::parser::ast::{
+    BlockStatement, CallExpression, CatchClause, CompoundAssignmentExpression,
+    ConditionalExpression, ErrorExpression, ErrorStatement, ExportStatement, Expression,
+    ExpressionStatement, ForInStatement, FunctionDeclarationStatement, GetExpression,
+    IfElseStatement, ImportStatement, LiteralExpression, PrintStatement, ReturnStatement,
+    Statement, TestStatement, ThrowStatement, TryStatement, UpdateExpression,
+    VariableAssignmentExpression, VariableDeclarationStatement, VariableReferenceExpression,
+    WhileStatement, YieldStatement,
+};
+use crate::parser::{Parser, SyntaxError};
+use crate::scanner::{Scanner, TextEdit, Token};
+use std::ops::Range;
+
+/// A source code with its tokens and its AST, kept up to date as the source code is edited.
+///
+/// The parser is always in the same state at the start of a top-level declaration, and it
+/// looks a single token ahead, or two to tell a test block from the identifier `test`:
+/// a declaration can be reused as long as its tokens, and the two tokens that follow them,
+/// did not change.
+pub struct IncrementalParser {
+    source: String,
+    /// All the tokens of `source`, trivia included.
+    tokens: Vec<Token>,
+    statements: Vec<Statement>,
+    /// One for each top-level statement.
+    declarations: Vec<Declaration>,
+}
+
+struct Declaration {
+    /// The offset of the first token of the declaration.
+    start: usize,
+    /// The syntax errors in the declaration, in order.
+    errors: Vec<SyntaxError>,
+}
+
+impl IncrementalParser {
+    pub fn new(source: String) -> Self {
+        let tokens: Vec<Token> = Scanner::new(&source).collect();
+        let (statements, declarations) = parse_declarations(&tokens, |_| false);
+        Self {
+            source,
+            tokens,
+            statements,
+            declarations,
+        }
+    }
+
+    /// Apply `edit` to the source code, then update the tokens and the AST to match.
+    /// It returns the indices of the top-level statements that were parsed again.
+    ///
+    /// It panics if `edit.range` is out of bounds or does not lie on `char` boundaries.
+    pub fn edit(&mut self, edit: &TextEdit) -> Range<usize> {
+        let removed_lines = self.source[edit.range.clone()].matches('\n').count();
+        let moved_lines = edit.text.matches('\n').count() as i64 - removed_lines as i64;
+        self.source.replace_range(edit.range.clone(), &edit.text);
+        let rescanned = Scanner::rescan(&mut self.tokens, &self.source, edit);
+
+        // The last declaration that starts before the rescanned tokens might depend on them,
+        // through the tokens that follow it. So might the one before it, if the last one
+        // is a test block: the parser looked at its name to tell it from an identifier.
+        // The ones before them can be kept as they are.
+        let first = self
+            .declarations
+            .partition_point(|d| d.start < rescanned.start)
+            .saturating_sub(2);
+        let start = match first {
+            // Anything before the first declaration might have become a declaration.
+            0 => 0,
+            _ => self.declarations[first].start,
+        };
+        let from = self.tokens.partition_point(|t| t.span().start < start);
+        let mut kept = self.declarations.len();
+        let (statements, declarations) = parse_declarations(&self.tokens[from..], |offset| {
+            // Past the rescanned tokens, a declaration that starts where an old one started
+            // is the old one, moved.
+            if offset < rescanned.end {
+                return false;
+            }
+            let old_offset = (offset as isize - edit.shift()) as usize;
+            match self
+                .declarations
+                .binary_search_by_key(&old_offset, |d| d.start)
+            {
+                Ok(index) => {
+                    kept = index;
+                    true
+                }
+                Err(_) => false,
+            }
+        });
+
+        let count = statements.len();
+        self.statements.splice(first..kept, statements);
+        self.declarations.splice(first..kept, declarations);
+        let mut shift = |token: &mut Token| token.shift(edit.shift(), moved_lines);
+        for (statement, declaration) in self.statements[first + count..]
+            .iter_mut()
+            .zip(&mut self.declarations[first + count..])
+        {
+            declaration.start = (declaration.start as isize + edit.shift()) as usize;
+            for error in &mut declaration.errors {
+                error.line = (error.line as i64 + moved_lines) as u64;
+                if let Some(token) = &mut error.token {
+                    shift(token);
+                }
+            }
+            statement_tokens(statement, &mut shift);
+        }
+        first..first + count
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// All the tokens of the source code, trivia included.
+    pub fn tokens(&self) -> &[Token] {
+        &self.tokens
+    }
+
+    /// The top-level statements, with error nodes in place of malformed code.
+    pub fn statements(&self) -> &[Statement] {
+        &self.statements
+    }
+
+    /// The syntax errors in the source code, the same as parsing it all at once.
+    pub fn errors(&self) -> impl Iterator<Item = &SyntaxError> {
+        self.declarations.iter().flat_map(|d| &d.errors)
+    }
+}
+
+/// Parse top-level declarations until the end of `tokens`, or until `stop` returns `true`
+/// for the offset where the next declaration starts.
+fn parse_declarations(
+    tokens: &[Token],
+    mut stop: impl FnMut(usize) -> bool,
+) -> (Vec<Statement>, Vec<Declaration>) {
+    let mut parser = Parser::new(tokens.iter().cloned());
+    let mut statements = vec![];
+    let mut declarations = vec![];
+    while let Some(start) = parser.tokens.peek().map(|t| t.span().start) {
+        if stop(start) {
+            break;
+        }
+        statements.push(parser.top_level_declaration());
+        declarations.push(Declaration {
+            start,
+            errors: std::mem::take(&mut parser.errors),
+        });
+    }
+    (statements, declarations)
+}
+
+/// Call `f` on every token in `statement`.
+fn statement_tokens(statement: &mut Statement, f: &mut impl FnMut(&mut Token)) {
+    match statement {
+        Statement::Expression(ExpressionStatement(e)) | Statement::Print(PrintStatement(e)) => {
+            expression_tokens(e, f)
+        }
+        Statement::VariableDeclaration(VariableDeclarationStatement {
+            initializer,
+            identifier,
+            ..
+        }) => {
+            f(identifier);
+            if let Some(initializer) = initializer {
+                expression_tokens(initializer, f);
+            }
+        }
+        Statement::FunctionDeclaration(FunctionDeclarationStatement {
+            name,
+            parameters,
+            rest,
+            body,
+            ..
+        }) => {
+            f(name);
+            for parameter in parameters {
+                f(&mut parameter.name);
+                if let Some(default) = &mut parameter.default {
+                    expression_tokens(default, f);
+                }
+            }
+            if let Some(rest) = rest {
+                f(rest);
+            }
+            for statement in body {
+                statement_tokens(statement, f);
+            }
+        }
+        Statement::Block(block) => block_tokens(block, f),
+        Statement::IfElse(IfElseStatement {
+            condition,
+            if_branch,
+            else_branch,
+        }) => {
+            expression_tokens(condition, f);
+            statement_tokens(if_branch, f);
+            if let Some(else_branch) = else_branch {
+                statement_tokens(else_branch, f);
+            }
+        }
+        Statement::While(WhileStatement { condition, body }) => {
+            expression_tokens(condition, f);
+            statement_tokens(body, f);
+        }
+        Statement::ForIn(ForInStatement {
+            variable,
+            keyword,
+            iterable,
+            body,
+        }) => {
+            f(variable);
+            f(keyword);
+            expression_tokens(iterable, f);
+            statement_tokens(body, f);
+        }
+        Statement::Return(ReturnStatement { keyword, value })
+        | Statement::Throw(ThrowStatement { keyword, value })
+        | Statement::Yield(YieldStatement { keyword, value }) => {
+            f(keyword);
+            expression_tokens(value, f);
+        }
+        Statement::Try(TryStatement {
+            body,
+            catch,
+            finally,
+        }) => {
+            block_tokens(body, f);
+            if let Some(CatchClause { variable, body }) = catch {
+                f(variable);
+                block_tokens(body, f);
+            }
+            if let Some(finally) = finally {
+                block_tokens(finally, f);
+            }
+        }
+        Statement::Import(ImportStatement {
+            keyword,
+            path,
+            alias,
+        }) => {
+            f(keyword);
+            f(path);
+            if let Some(alias) = alias {
+                f(alias);
+            }
+        }
+        Statement::Export(ExportStatement {
+            keyword,
+            declaration,
+        }) => {
+            f(keyword);
+            statement_tokens(declaration, f);
+        }
+        Statement::Test(TestStatement {
+            keyword,
+            name,
+            body,
+        }) => {
+            f(keyword);
+            f(name);
+            block_tokens(body, f);
+        }
+        Statement::Error(ErrorStatement { token }) => {
+            if let Some(token) = token {
+                f(token);
+            }
+        }
+    }
+}
+
+fn block_tokens(block: &mut BlockStatement, f: &mut impl FnMut(&mut Token)) {
+    f(&mut block.brace);
+    for statement in &mut block.statements {
+        statement_tokens(statement, f);
+    }
+}
+
+fn expression_tokens(expression: &mut Expression, f: &mut impl FnMut(&mut Token)) {
+    match expression {
+        Expression::Binary(b) => {
+            expression_tokens(&mut b.left, f);
+            f(&mut b.operator);
+            expression_tokens(&mut b.right, f);
+        }
+        Expression::Unary(u) => {
+            f(&mut u.operator);
+            expression_tokens(&mut u.operand, f);
+        }
+        Expression::Literal(l) => match l {
+            LiteralExpression::Boolean(_, t)
+            | LiteralExpression::Null(t)
+            | LiteralExpression::String(t)
+            | LiteralExpression::Number(t) => f(t),
+        },
+        Expression::Grouping(g) => expression_tokens(&mut g.0, f),
+        Expression::VariableReference(VariableReferenceExpression { identifier }) => f(identifier),
+        Expression::VariableAssignment(VariableAssignmentExpression { identifier, value }) => {
+            f(identifier);
+            expression_tokens(value, f);
+        }
+        Expression::CompoundAssignment(CompoundAssignmentExpression {
+            identifier,
+            operator,
+            value,
+        }) => {
+            f(identifier);
+            f(operator);
+            expression_tokens(value, f);
+        }
+        Expression::Update(UpdateExpression {
+            identifier,
+            operator,
+            ..
+        }) => {
+            f(identifier);
+            f(operator);
+        }
+        Expression::Conditional(ConditionalExpression {
+            condition,
+            then_branch,
+            else_branch,
+        }) => {
+            expression_tokens(condition, f);
+            expression_tokens(then_branch, f);
+            expression_tokens(else_branch, f);
+        }
+        Expression::Call(CallExpression {
+            callee,
+            closing_parenthesis,
+            arguments,
+        }) => {
+            expression_tokens(callee, f);
+            f(closing_parenthesis);
+            for argument in arguments {
+                expression_tokens(argument, f);
+            }
+        }
+        Expression::Get(GetExpression { object, name }) => {
+            expression_tokens(object, f);
+            f(name);
+        }
+        Expression::Error(ErrorExpression { token, children }) => {
+            if let Some(token) = token {
+                f(token);
+            }
+            for child in children {
+                expression_tokens(child, f);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::{IncrementalParser, Parser};
+    use crate::scanner::{Scanner, TextEdit, Token};
+
+    const PROGRAM: &str = r#"// Numbers, and more numbers.
+var total = 0;
+const limit = 10.5;
+
+fun add(a, b = 1, ...rest) {
+    var sum = a + b;
+    for (n in rest) sum += n;
+    return sum;
+}
+
+fun* counter() {
+    var i = 0;
+    while (i < limit) { yield i++; }
+}
+
+try {
+    print add(1, 2, 3) > 5 ? "big" : "small";
+} catch (error) {
+    print error.message;
+}
+
+test "addition" {
+    assert(add(1) == 2);
+}
+print "done: é";
+"#;
+
+    /// Pieces of code that, inserted anywhere, make for interesting edits.
+    const FRAGMENTS: [&str; 22] = [
+        "", "a", "1", ".", "..", "5", "\"", "//", "/", "\n", " ", "(", ")", "{", "}", ";", "=",
+        "var x = ", "fun f(", "if (a) ", "print", "} else {",
+    ];
+
+    /// A small, deterministic, pseudo-random number generator (xorshift).
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        /// A random offset in `source`, on a `char` boundary.
+        fn offset(&mut self, source: &str) -> usize {
+            let mut offset = self.below(source.len() + 1);
+            while !source.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            offset
+        }
+    }
+
+    fn assert_matches_full_parse(parser: &IncrementalParser, edits: &[TextEdit]) {
+        let tokens: Vec<Token> = Scanner::new(parser.source()).collect();
+        assert_eq!(
+            format!("{:?}", parser.tokens()),
+            format!("{tokens:?}"),
+            "Tokens differ after {edits:?}"
+        );
+        let (statements, errors) = Parser::parse_reporting_errors(tokens.into_iter());
+        assert_eq!(
+            format!("{:?}", parser.statements()),
+            format!("{statements:?}"),
+            "Statements differ after {edits:?}"
+        );
+        assert_eq!(
+            format!("{:?}", parser.errors().collect::<Vec<_>>()),
+            format!("{:?}", errors.iter().collect::<Vec<_>>()),
+            "Errors differ after {edits:?}"
+        );
+    }
+
+    #[test]
+    fn incremental_parsing_matches_full_parsing_on_random_edits() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for _ in 0..100 {
+            let mut parser = IncrementalParser::new(PROGRAM.to_owned());
+            let mut edits = vec![];
+            for _ in 0..20 {
+                let source = parser.source();
+                let start = random.offset(source);
+                let end = (start + random.below(12)).min(source.len());
+                let end = (end..=source.len())
+                    .find(|&i| source.is_char_boundary(i))
+                    .unwrap();
+                let text = if random.below(4) == 0 {
+                    // Moving code around
+                    let from = random.offset(source);
+                    let to = (from + random.below(30)).min(source.len());
+                    let to = (to..=source.len())
+                        .find(|&i| source.is_char_boundary(i))
+                        .unwrap();
+                    source[from..to].to_owned()
+                } else {
+                    FRAGMENTS[random.below(FRAGMENTS.len())].to_owned()
+                };
+                let edit = TextEdit {
+                    range: start..end,
+                    text,
+                };
+                parser.edit(&edit);
+                edits.push(edit);
+                assert_matches_full_parse(&parser, &edits);
+            }
+        }
+    }
+
+    #[test]
+    fn edits_only_reparse_the_declarations_around_them() {
+        let mut parser = IncrementalParser::new(PROGRAM.to_owned());
+        assert_eq!(parser.statements().len(), 7);
+
+        // Inside the body of `add`, along with the declaration before it.
+        let offset = PROGRAM.find("sum;").unwrap();
+        let edit = TextEdit {
+            range: offset..offset + 3,
+            text: "total".to_owned(),
+        };
+        assert_eq!(parser.edit(&edit), 1..3);
+
+        // The declaration before the edited one looked at its first token.
+        let offset = PROGRAM.find("const").unwrap();
+        let edit = TextEdit {
+            range: offset..offset + 5,
+            text: "cons".to_owned(),
+        };
+        assert_eq!(parser.edit(&edit), 0..2);
+        assert_eq!(parser.errors().count(), 1);
+        assert_matches_full_parse(&parser, &[]);
+    }
+
+    #[test]
+    fn the_name_of_a_test_block_is_looked_at_by_the_declaration_before_it() {
+        // The parser recovers from the missing semicolon right before the test block.
+        let source = "var a = 1 test // The name is on the next line.\n\"name\" {}\nprint a;\n";
+        let mut parser = IncrementalParser::new(source.to_owned());
+        assert_eq!(parser.statements().len(), 3);
+
+        // Without a name, `test` is an identifier: the parser skips it as well.
+        let offset = source.find('"').unwrap();
+        let edit = TextEdit {
+            range: offset..offset + 6,
+            text: "name".to_owned(),
+        };
+        assert_eq!(parser.edit(&edit), 0..1);
+        assert_eq!(parser.statements().len(), 2);
+        assert_matches_full_parse(&parser, &[edit]);
+    }
+}
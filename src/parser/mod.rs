@@ -1,15 +1,24 @@
 pub mod ast;
+mod incremental;
 
 use crate::parser::ast::{
-    BlockStatement, CallExpression, ExpressionStatement, FunctionDeclarationStatement,
-    IfElseStatement, PrintStatement, ReturnStatement, Statement, VariableAssignmentExpression,
-    VariableDeclarationStatement, VariableReferenceExpression, WhileStatement,
+    BlockStatement, CallExpression, CatchClause, CompoundAssignmentExpression,
+    ConditionalExpression, ErrorExpression, ErrorStatement, ExportStatement, ExpressionStatement,
+    ForInStatement, FunctionDeclarationStatement, GetExpression, IfElseStatement, ImportStatement,
+    Parameter, PrintStatement, ReturnStatement, Statement, TestStatement, ThrowStatement,
+    TryStatement, UpdateExpression, VariableAssignmentExpression, VariableDeclarationStatement,
+    VariableReferenceExpression, WhileStatement, YieldStatement,
 };
 use crate::scanner::{Token, TokenDiscriminant, TokenType};
 use ast::{Expression, LiteralExpression};
+use std::collections::VecDeque;
 use std::fmt::Write;
-use std::iter::Peekable;
+use std::iter::Fuse;
 
+pub use incremental::IncrementalParser;
+
+/// In error recovery mode the parser does not consume any token: the rest of the malformed
+/// statement collapses into error nodes, then the parser skips to the start of the next one.
 #[derive(PartialEq, Eq, Copy, Clone)]
 enum ParsingMode {
     ErrorRecovery,
@@ -20,137 +29,379 @@ pub struct Parser<TokenIter>
 where
     TokenIter: Iterator<Item = Token>,
 {
-    tokens: Peekable<Source<TokenIter>>,
+    tokens: Source<TokenIter>,
     mode: ParsingMode,
+    errors: Vec<SyntaxError>,
+}
+
+/// A syntax error in a program: the parser recovers from it, and goes on to report
+/// the following ones.
+#[derive(Debug, Clone)]
+pub struct SyntaxError {
+    /// The token the parser could not make sense of, `None` if the input ended too early.
+    pub token: Option<Token>,
+    /// The line of `token` or, if the input ended too early, the last line.
+    pub line: u64,
+}
+
+impl SyntaxError {
+    /// Whether the error was caused by the input ending too early, e.g. in the middle of
+    /// a block or of a string literal.
+    pub fn ended_early(&self) -> bool {
+        match &self.token {
+            None => true,
+            Some(t) => t.is_unterminated_string(),
+        }
+    }
+
+    /// What went wrong, without the location.
+    pub fn message(&self) -> String {
+        match self.token.as_ref().map(|t| (t.ty(), t)) {
+            None => "Unexpected end of input.".to_owned(),
+            Some((
+                TokenType::SyntaxError {
+                    error_msg: Some(msg),
+                },
+                _,
+            )) => format!("{msg}."),
+            Some((_, t)) => format!("Unexpected `{}`.", t.clone().lexeme()),
+        }
+    }
+}
+
+impl std::fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.token.as_ref().map(|t| (t.ty(), t)) {
+            None => write!(f, "[line {}] Error at end: {}", self.line, self.message()),
+            // Errors of the scanner, e.g. an unterminated string, are not about a lexeme.
+            Some((TokenType::SyntaxError { .. }, _)) => {
+                write!(f, "[line {}] Error: {}", self.line, self.message())
+            }
+            Some((_, t)) => write!(
+                f,
+                "[line {}] Error at '{}': {}",
+                self.line,
+                t.clone().lexeme(),
+                self.message()
+            ),
+        }
+    }
 }
 
 impl<TokenIter> Parser<TokenIter>
 where
     TokenIter: Iterator<Item = Token>,
 {
-    pub fn parse(tokens: TokenIter) -> Result<Vec<Statement>, Vec<Statement>> {
-        let mut parser = Self {
-            tokens: Source(tokens).peekable(),
+    /// It returns the syntax errors, if the program has any.
+    /// The errors are in the order they appear in the source code.
+    pub fn parse(tokens: TokenIter) -> Result<Vec<Statement>, Vec<SyntaxError>> {
+        match Self::parse_reporting_errors(tokens) {
+            (statements, errors) if errors.is_empty() => Ok(statements),
+            (_, errors) => Err(errors),
+        }
+    }
+
+    /// Whether parsing `tokens` fails only because the input ended too early, e.g. in the
+    /// middle of a block or of a string literal.
+    /// More input might turn `tokens` into a valid program.
+    pub fn is_incomplete(tokens: TokenIter) -> bool {
+        let mut parser = Self::new(tokens);
+        parser.program();
+        parser
+            .errors
+            .first()
+            .map(|error| error.ended_early())
+            .unwrap_or(false)
+    }
+
+    /// Like [`Parser::parse`], but it returns the statements even if there are syntax errors:
+    /// malformed statements and expressions are replaced by error nodes.
+    pub fn parse_reporting_errors(tokens: TokenIter) -> (Vec<Statement>, Vec<SyntaxError>) {
+        let mut parser = Self::new(tokens);
+        let statements = parser.program();
+        (statements, parser.errors)
+    }
+
+    /// Parse `tokens` as a single expression, without a trailing semicolon.
+    /// It returns `None` if `tokens` are not exactly one expression.
+    pub fn parse_expression(tokens: TokenIter) -> Option<Expression> {
+        let mut parser = Self::new(tokens);
+        let expression = parser.expression();
+        (parser.errors.is_empty() && parser.is_at_end()).then_some(expression)
+    }
+
+    fn new(tokens: TokenIter) -> Self {
+        Self {
+            tokens: Source {
+                tokens: tokens.fuse(),
+                peeked: VecDeque::new(),
+                line: 1,
+            },
             mode: ParsingMode::Normal,
-        };
+            errors: vec![],
+        }
+    }
 
-        let mut has_errored = false;
+    fn program(&mut self) -> Vec<Statement> {
         let mut statements = vec![];
-        while !parser.is_at_end() {
-            let statement = parser.declaration();
-            match statement {
-                Some(statement) => {
-                    statements.push(statement);
-                }
-                None => {
-                    parser.advance_until_recovery_point();
-                    has_errored = true;
-                }
-            }
+        while !self.is_at_end() {
+            statements.push(self.top_level_declaration());
         }
-        if has_errored {
-            Err(statements)
-        } else {
-            Ok(statements)
+        statements
+    }
+
+    /// After a top-level declaration the parser is always in normal mode, ready to parse
+    /// the next one.
+    fn top_level_declaration(&mut self) -> Statement {
+        let declaration = self.declaration();
+        if self.mode == ParsingMode::ErrorRecovery {
+            self.advance_until_recovery_point();
+            // Unlike in a block, a closing brace can't be the end of the statements.
+            self.advance_on_match(&[TokenDiscriminant::RightBrace]);
         }
+        declaration
     }
 
-    fn declaration(&mut self) -> Option<Statement> {
-        if self.advance_on_match(&[TokenDiscriminant::Fun]).is_some() {
+    fn declaration(&mut self) -> Statement {
+        let declaration = if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::Import])
+        {
+            self.import_statement(keyword).map(Statement::Import)
+        } else if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::Export]) {
+            self.export_statement(keyword).map(Statement::Export)
+        } else if self.mode == ParsingMode::Normal && starts_test_block(&mut self.tokens) {
+            self.advance()
+                .and_then(|keyword| self.test_statement(keyword))
+                .map(Statement::Test)
+        } else if self.advance_on_match(&[TokenDiscriminant::Fun]).is_some() {
             self.function().map(Statement::FunctionDeclaration)
         } else if self.advance_on_match(&[TokenDiscriminant::Var]).is_some() {
-            let identifier = self.expect(TokenDiscriminant::Identifier)?;
-            let mut initializer = None;
-            if self.advance_on_match(&[TokenDiscriminant::Equal]).is_some() {
-                initializer = Some(self.expression()?);
-            }
-            self.expect(TokenDiscriminant::Semicolon)?;
-            Some(Statement::VariableDeclaration(
-                VariableDeclarationStatement {
-                    initializer,
-                    identifier,
-                },
-            ))
+            self.variable_declaration(false)
+                .map(Statement::VariableDeclaration)
+        } else if self.advance_on_match(&[TokenDiscriminant::Const]).is_some() {
+            self.variable_declaration(true)
+                .map(Statement::VariableDeclaration)
+        } else {
+            Some(self.statement())
+        };
+        declaration.unwrap_or_else(|| self.error_statement())
+    }
+
+    /// Parse the rest of a `var` or `const` declaration, starting right after the keyword.
+    fn variable_declaration(&mut self, constant: bool) -> Option<VariableDeclarationStatement> {
+        let identifier = self.expect(TokenDiscriminant::Identifier)?;
+        let initializer = if constant {
+            // Constants can't be assigned after their declaration, therefore
+            // an initializer is mandatory.
+            self.expect(TokenDiscriminant::Equal);
+            Some(self.expression())
+        } else if self.advance_on_match(&[TokenDiscriminant::Equal]).is_some() {
+            Some(self.expression())
         } else {
-            self.statement()
+            None
+        };
+        self.expect(TokenDiscriminant::Semicolon);
+        Some(VariableDeclarationStatement {
+            initializer,
+            identifier,
+            constant,
+        })
+    }
+
+    fn import_statement(&mut self, keyword: Token) -> Option<ImportStatement> {
+        let path = self.expect(TokenDiscriminant::String)?;
+        let mut alias = None;
+        if self.advance_on_match(&[TokenDiscriminant::As]).is_some() {
+            alias = Some(self.expect(TokenDiscriminant::Identifier)?);
+        }
+        self.expect(TokenDiscriminant::Semicolon);
+        Some(ImportStatement {
+            keyword,
+            path,
+            alias,
+        })
+    }
+
+    fn export_statement(&mut self, keyword: Token) -> Option<ExportStatement> {
+        let is_declaration = self
+            .peek()
+            .map(|t| {
+                matches!(
+                    t.discriminant(),
+                    TokenDiscriminant::Fun | TokenDiscriminant::Var | TokenDiscriminant::Const
+                )
+            })
+            .unwrap_or(false);
+        if !is_declaration {
+            // Only declarations can be exported.
+            self.error();
+            return None;
         }
+        Some(ExportStatement {
+            keyword,
+            declaration: Box::new(self.declaration()),
+        })
+    }
+
+    fn test_statement(&mut self, keyword: Token) -> Option<TestStatement> {
+        let name = self.expect(TokenDiscriminant::String)?;
+        let brace = self.expect(TokenDiscriminant::LeftBrace)?;
+        let body = self.block_statement(brace);
+        Some(TestStatement {
+            keyword,
+            name,
+            body,
+        })
     }
 
     fn function(&mut self) -> Option<FunctionDeclarationStatement> {
+        let generator = self.advance_on_match(&[TokenDiscriminant::Star]).is_some();
         let name = self.expect(TokenDiscriminant::Identifier)?;
         self.expect(TokenDiscriminant::LeftParen)?;
 
         // Function parameters
-        let mut parameters = vec![];
+        let mut parameters: Vec<Parameter> = vec![];
+        let mut rest = None;
         if self.peek()?.discriminant() != TokenDiscriminant::RightParen {
             loop {
-                parameters.push(self.expect(TokenDiscriminant::Identifier)?);
+                if self
+                    .advance_on_match(&[TokenDiscriminant::Ellipsis])
+                    .is_some()
+                {
+                    // The rest parameter must be the last one - if it isn't, we'll fail
+                    // to find the closing parenthesis.
+                    rest = Some(self.expect(TokenDiscriminant::Identifier)?);
+                    break;
+                }
+                let name = self.expect(TokenDiscriminant::Identifier)?;
+                let mut default = None;
+                if self.advance_on_match(&[TokenDiscriminant::Equal]).is_some() {
+                    default = Some(self.expression());
+                } else if parameters.iter().any(|p| p.default.is_some()) {
+                    // Parameters without a default value can't follow parameters with
+                    // a default value.
+                    self.error();
+                    return None;
+                }
+                parameters.push(Parameter { name, default });
                 if self.advance_on_match(&[TokenDiscriminant::Comma]).is_none() {
                     break;
                 }
             }
         }
-        self.expect(TokenDiscriminant::RightParen)?;
-        if parameters.len() >= 255 {
-            // Ugly, we should set `has_errored` here.
+        let closing_parenthesis = self.expect(TokenDiscriminant::RightParen)?;
+        if parameters.len() + rest.iter().len() >= 255 {
             println!("You can't have more than 255 arguments");
-            return None;
+            self.report(Some(closing_parenthesis));
         }
 
         // Body
-        self.expect(TokenDiscriminant::LeftBrace)?;
-        let body = self.block_statement()?;
+        let brace = self.expect(TokenDiscriminant::LeftBrace)?;
+        let body = self.block_statement(brace);
 
         Some(FunctionDeclarationStatement {
             name,
             parameters,
+            rest,
             body: vec![Statement::Block(body)],
+            generator,
         })
     }
 
-    fn statement(&mut self) -> Option<Statement> {
-        if self.advance_on_match(&[TokenDiscriminant::Print]).is_some() {
-            self.print_statement().map(Statement::Print)
-        } else if self
-            .peek()
-            .filter(|&t| t.discriminant() == TokenDiscriminant::Return)
-            .is_some()
-        {
-            self.return_statement().map(Statement::Return)
+    fn statement(&mut self) -> Statement {
+        if self.mode == ParsingMode::ErrorRecovery {
+            // The statement is part of a malformed one, e.g. the body of `if (a print a;`.
+            return self.error_statement();
+        }
+        let statement = if self.advance_on_match(&[TokenDiscriminant::Print]).is_some() {
+            Some(Statement::Print(self.print_statement()))
+        } else if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::Return]) {
+            Some(Statement::Return(self.return_statement(keyword)))
         } else if self.advance_on_match(&[TokenDiscriminant::While]).is_some() {
             self.while_statement().map(Statement::While)
-        } else if self.advance_on_match(&[TokenDiscriminant::For]).is_some() {
-            self.for_statement()
+        } else if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::For]) {
+            self.for_statement(keyword)
         } else if self.advance_on_match(&[TokenDiscriminant::If]).is_some() {
             self.if_else_statement().map(Statement::IfElse)
-        } else if self
-            .advance_on_match(&[TokenDiscriminant::LeftBrace])
-            .is_some()
-        {
-            self.block_statement().map(Statement::Block)
+        } else if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::Throw]) {
+            Some(Statement::Throw(self.throw_statement(keyword)))
+        } else if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::Yield]) {
+            Some(Statement::Yield(self.yield_statement(keyword)))
+        } else if self.advance_on_match(&[TokenDiscriminant::Try]).is_some() {
+            self.try_statement().map(Statement::Try)
+        } else if let Some(brace) = self.advance_on_match(&[TokenDiscriminant::LeftBrace]) {
+            Some(Statement::Block(self.block_statement(brace)))
         } else {
-            self.expression_statement().map(Statement::Expression)
-        }
+            Some(Statement::Expression(self.expression_statement()))
+        };
+        statement.unwrap_or_else(|| self.error_statement())
+    }
+
+    fn return_statement(&mut self, keyword: Token) -> ReturnStatement {
+        let value = match self.advance_on_match(&[TokenDiscriminant::Semicolon]) {
+            Some(semicolon) => Expression::null(semicolon),
+            None => {
+                let value = self.expression();
+                self.expect(TokenDiscriminant::Semicolon);
+                value
+            }
+        };
+        ReturnStatement { keyword, value }
     }
 
-    fn return_statement(&mut self) -> Option<ReturnStatement> {
-        let keyword = self.expect(TokenDiscriminant::Return)?;
-        let mut value = None;
+    fn yield_statement(&mut self, keyword: Token) -> YieldStatement {
+        let value = match self.advance_on_match(&[TokenDiscriminant::Semicolon]) {
+            Some(semicolon) => Expression::null(semicolon),
+            None => {
+                let value = self.expression();
+                self.expect(TokenDiscriminant::Semicolon);
+                value
+            }
+        };
+        YieldStatement { keyword, value }
+    }
+
+    fn throw_statement(&mut self, keyword: Token) -> ThrowStatement {
+        let value = self.expression();
+        self.expect(TokenDiscriminant::Semicolon);
+        ThrowStatement { keyword, value }
+    }
+
+    fn try_statement(&mut self) -> Option<TryStatement> {
+        let brace = self.expect(TokenDiscriminant::LeftBrace)?;
+        let body = self.block_statement(brace);
+
+        let mut catch = None;
+        if self.advance_on_match(&[TokenDiscriminant::Catch]).is_some() {
+            self.expect(TokenDiscriminant::LeftParen)?;
+            let variable = self.expect(TokenDiscriminant::Identifier)?;
+            self.expect(TokenDiscriminant::RightParen)?;
+            let brace = self.expect(TokenDiscriminant::LeftBrace)?;
+            let body = self.block_statement(brace);
+            catch = Some(CatchClause { variable, body });
+        }
+
+        let mut finally = None;
         if self
-            .peek()
-            .filter(|&t| t.discriminant() == TokenDiscriminant::Semicolon)
-            .is_none()
+            .advance_on_match(&[TokenDiscriminant::Finally])
+            .is_some()
         {
-            value = Some(self.expression()?);
+            let brace = self.expect(TokenDiscriminant::LeftBrace)?;
+            finally = Some(self.block_statement(brace));
         }
-        let semicolon = self.expect(TokenDiscriminant::Semicolon)?;
-        Some(ReturnStatement {
-            keyword,
-            value: value.unwrap_or_else(|| Expression::null(semicolon)),
+
+        if catch.is_none() && finally.is_none() {
+            // A `try` block must be followed by `catch`, `finally` or both.
+            self.error();
+            return None;
+        }
+        Some(TryStatement {
+            body,
+            catch,
+            finally,
         })
     }
 
-    fn for_statement(&mut self) -> Option<Statement> {
+    fn for_statement(&mut self, keyword: Token) -> Option<Statement> {
         self.expect(TokenDiscriminant::LeftParen)?;
         let initializer = if self
             .advance_on_match(&[TokenDiscriminant::Semicolon])
@@ -162,45 +413,79 @@ where
             .map(|t| t.discriminant() == TokenDiscriminant::Var)
             .unwrap_or(false)
         {
-            Some(self.declaration()?)
+            Some(self.declaration())
         } else {
-            Some(Statement::Expression(self.expression_statement()?))
+            let expr = self.expression();
+            if let Expression::VariableReference(VariableReferenceExpression { identifier }) = &expr
+            {
+                if let Some(keyword) = self.advance_on_match(&[TokenDiscriminant::In]) {
+                    let variable = identifier.to_owned();
+                    return Some(Statement::ForIn(self.for_in_statement(variable, keyword)));
+                }
+            }
+            self.expect(TokenDiscriminant::Semicolon);
+            Some(Statement::Expression(ExpressionStatement(expr)))
         };
-        let condition = if self.peek()?.discriminant() == TokenDiscriminant::Semicolon {
+        let condition = if self
+            .peek()
+            .map(|t| t.discriminant() == TokenDiscriminant::Semicolon)
+            .unwrap_or(false)
+        {
             None
         } else {
-            Some(self.expression()?)
+            Some(self.expression())
         };
-        self.expect(TokenDiscriminant::Semicolon)?;
-        let increment = if self.peek()?.discriminant() == TokenDiscriminant::RightParen {
+        self.expect(TokenDiscriminant::Semicolon);
+        let increment = if self
+            .peek()
+            .map(|t| t.discriminant() == TokenDiscriminant::RightParen)
+            .unwrap_or(false)
+        {
             None
         } else {
-            Some(self.expression()?)
+            Some(self.expression())
         };
-        self.expect(TokenDiscriminant::RightParen)?;
-        let mut body = self.statement()?;
+        self.expect(TokenDiscriminant::RightParen);
+        let mut body = self.statement();
 
         // De-sugaring the for loop into an equivalent while loop
         if let Some(increment) = increment {
-            body = Statement::Block(BlockStatement(vec![
-                body,
-                Statement::Expression(ExpressionStatement(increment)),
-            ]))
+            body = Statement::Block(BlockStatement {
+                brace: keyword.clone(),
+                statements: vec![body, Statement::Expression(ExpressionStatement(increment))],
+            })
         }
 
         body = Statement::While(WhileStatement {
-            condition: condition.unwrap_or_else(|| Expression::boolean(true)),
+            condition: condition.unwrap_or_else(|| Expression::boolean(true, keyword.clone())),
             body: Box::new(body),
         });
 
         if let Some(initializer) = initializer {
-            body = Statement::Block(BlockStatement(vec![initializer, body]))
+            body = Statement::Block(BlockStatement {
+                brace: keyword,
+                statements: vec![initializer, body],
+            })
         }
 
         Some(body)
     }
 
-    fn block_statement(&mut self) -> Option<BlockStatement> {
+    /// Parse the rest of a `for (variable in iterable) body` loop, starting right after `in`.
+    fn for_in_statement(&mut self, variable: Token, keyword: Token) -> ForInStatement {
+        let iterable = self.expression();
+        self.expect(TokenDiscriminant::RightParen);
+        let body = self.statement();
+        ForInStatement {
+            variable,
+            keyword,
+            iterable,
+            body: Box::new(body),
+        }
+    }
+
+    /// Parse the rest of a block, starting right after `brace`.
+    fn block_statement(&mut self, brace: Token) -> BlockStatement {
         let mut statements = vec![];
 
         loop {
@@ -212,17 +497,18 @@ where
                     break;
                 }
             }
-            statements.push(self.declaration()?);
+            statements.push(self.declaration());
+            self.advance_until_recovery_point();
         }
-        self.expect(TokenDiscriminant::RightBrace)?;
-        Some(BlockStatement(statements))
+        self.expect(TokenDiscriminant::RightBrace);
+        BlockStatement { brace, statements }
     }
 
     fn while_statement(&mut self) -> Option<WhileStatement> {
         self.expect(TokenDiscriminant::LeftParen)?;
-        let condition = self.expression()?;
-        self.expect(TokenDiscriminant::RightParen)?;
-        let body = self.statement()?;
+        let condition = self.expression();
+        self.expect(TokenDiscriminant::RightParen);
+        let body = self.statement();
         Some(WhileStatement {
             condition,
             body: Box::new(body),
@@ -231,12 +517,12 @@ where
 
     fn if_else_statement(&mut self) -> Option<IfElseStatement> {
         self.expect(TokenDiscriminant::LeftParen)?;
-        let condition = self.expression()?;
-        self.expect(TokenDiscriminant::RightParen)?;
-        let if_branch = self.statement()?;
+        let condition = self.expression();
+        self.expect(TokenDiscriminant::RightParen);
+        let if_branch = self.statement();
         let mut else_branch = None;
         if self.advance_on_match(&[TokenDiscriminant::Else]).is_some() {
-            else_branch = Some(Box::new(self.statement()?));
+            else_branch = Some(Box::new(self.statement()));
         }
         Some(IfElseStatement {
             condition,
@@ -245,70 +531,100 @@ where
         })
     }
 
-    fn print_statement(&mut self) -> Option<PrintStatement> {
-        let expr = self.expression()?;
-        self.expect(TokenDiscriminant::Semicolon)?;
-        Some(PrintStatement(expr))
+    fn print_statement(&mut self) -> PrintStatement {
+        let expr = self.expression();
+        self.expect(TokenDiscriminant::Semicolon);
+        PrintStatement(expr)
     }
 
-    fn expression_statement(&mut self) -> Option<ExpressionStatement> {
-        let expr = self.expression()?;
-        self.expect(TokenDiscriminant::Semicolon)?;
-        Some(ExpressionStatement(expr))
+    fn expression_statement(&mut self) -> ExpressionStatement {
+        let expr = self.expression();
+        self.expect(TokenDiscriminant::Semicolon);
+        ExpressionStatement(expr)
     }
 
-    fn expression(&mut self) -> Option<Expression> {
+    fn expression(&mut self) -> Expression {
         self.assignment()
     }
 
-    fn assignment(&mut self) -> Option<Expression> {
-        let expr = self.or()?;
+    fn assignment(&mut self) -> Expression {
+        let expr = self.conditional();
 
-        if self.advance_on_match(&[TokenDiscriminant::Equal]).is_some() {
-            let value = self.assignment()?;
+        if let Some(equal) = self.advance_on_match(&[TokenDiscriminant::Equal]) {
+            let value = self.assignment();
             if let Expression::VariableReference(variable) = expr {
                 let name = variable.identifier;
-                Some(Expression::variable_assignment(name, value))
+                Expression::variable_assignment(name, value)
             } else {
-                // Invalid assignment target!
-                None
+                self.invalid_target(equal, vec![expr, value])
             }
+        } else if let Some(operator) = self.advance_on_match(&[
+            TokenDiscriminant::PlusEqual,
+            TokenDiscriminant::MinusEqual,
+            TokenDiscriminant::StarEqual,
+            TokenDiscriminant::SlashEqual,
+        ]) {
+            let value = self.assignment();
+            if let Expression::VariableReference(variable) = expr {
+                let name = variable.identifier;
+                Expression::compound_assignment(name, operator, value)
+            } else {
+                self.invalid_target(operator, vec![expr, value])
+            }
+        } else {
+            expr
+        }
+    }
+
+    fn conditional(&mut self) -> Expression {
+        let condition = self.or();
+
+        if self
+            .advance_on_match(&[TokenDiscriminant::Question])
+            .is_some()
+        {
+            let then_branch = self.expression();
+            self.expect(TokenDiscriminant::Colon);
+            // Recursing into `conditional` makes the operator right-associative:
+            // `a ? b : c ? d : e` is parsed as `a ? b : (c ? d : e)`.
+            let else_branch = self.conditional();
+            Expression::conditional(condition, then_branch, else_branch)
         } else {
-            Some(expr)
+            condition
         }
     }
 
-    fn or(&mut self) -> Option<Expression> {
-        let mut expr = self.and()?;
+    fn or(&mut self) -> Expression {
+        let mut expr = self.and();
 
         while let Some(operator) = self.advance_on_match(&[TokenDiscriminant::Or]) {
-            expr = Expression::binary(expr, operator, self.and()?);
+            expr = Expression::binary(expr, operator, self.and());
         }
-        Some(expr)
+        expr
     }
 
-    fn and(&mut self) -> Option<Expression> {
-        let mut expr = self.equality()?;
+    fn and(&mut self) -> Expression {
+        let mut expr = self.equality();
 
         while let Some(operator) = self.advance_on_match(&[TokenDiscriminant::And]) {
-            expr = Expression::binary(expr, operator, self.equality()?);
+            expr = Expression::binary(expr, operator, self.equality());
         }
-        Some(expr)
+        expr
     }
 
-    fn equality(&mut self) -> Option<Expression> {
-        let mut expr = self.comparison()?;
+    fn equality(&mut self) -> Expression {
+        let mut expr = self.comparison();
 
         while let Some(operator) =
-            self.advance_on_match(&[TokenDiscriminant::EqualEqual, TokenDiscriminant::EqualEqual])
+            self.advance_on_match(&[TokenDiscriminant::EqualEqual, TokenDiscriminant::BangEqual])
         {
-            expr = Expression::binary(expr, operator, self.comparison()?);
+            expr = Expression::binary(expr, operator, self.comparison());
         }
-        Some(expr)
+        expr
     }
 
-    fn comparison(&mut self) -> Option<Expression> {
-        let mut expr = self.term()?;
+    fn comparison(&mut self) -> Expression {
+        let mut expr = self.term();
 
         while let Some(operator) = self.advance_on_match(&[
             TokenDiscriminant::Greater,
@@ -316,101 +632,139 @@ where
             TokenDiscriminant::Less,
             TokenDiscriminant::LessEqual,
         ]) {
-            expr = Expression::binary(expr, operator, self.term()?);
+            expr = Expression::binary(expr, operator, self.term());
         }
-        Some(expr)
+        expr
     }
 
-    fn term(&mut self) -> Option<Expression> {
-        let mut expr = self.factor()?;
+    fn term(&mut self) -> Expression {
+        let mut expr = self.factor();
 
         while let Some(operator) =
             self.advance_on_match(&[TokenDiscriminant::Minus, TokenDiscriminant::Plus])
         {
-            expr = Expression::binary(expr, operator, self.factor()?);
+            expr = Expression::binary(expr, operator, self.factor());
         }
-        Some(expr)
+        expr
     }
 
-    fn factor(&mut self) -> Option<Expression> {
-        let mut expr = self.unary()?;
+    fn factor(&mut self) -> Expression {
+        let mut expr = self.unary();
 
         while let Some(operator) =
             self.advance_on_match(&[TokenDiscriminant::Slash, TokenDiscriminant::Star])
         {
-            expr = Expression::binary(expr, operator, self.unary()?);
+            expr = Expression::binary(expr, operator, self.unary());
         }
-        Some(expr)
+        expr
     }
 
-    fn unary(&mut self) -> Option<Expression> {
+    fn unary(&mut self) -> Expression {
         if let Some(operator) =
             self.advance_on_match(&[TokenDiscriminant::Bang, TokenDiscriminant::Minus])
         {
-            Some(Expression::unary(operator, self.unary()?))
+            Expression::unary(operator, self.unary())
+        } else if let Some(operator) =
+            self.advance_on_match(&[TokenDiscriminant::PlusPlus, TokenDiscriminant::MinusMinus])
+        {
+            match self.unary() {
+                Expression::VariableReference(variable) => {
+                    Expression::update(variable.identifier, operator, true)
+                }
+                operand => self.invalid_target(operator, vec![operand]),
+            }
         } else {
-            self.call()
+            self.postfix()
         }
     }
 
-    fn call(&mut self) -> Option<Expression> {
-        let mut callee = self.primary()?;
+    fn postfix(&mut self) -> Expression {
+        let expr = self.call();
+
+        if let Some(operator) =
+            self.advance_on_match(&[TokenDiscriminant::PlusPlus, TokenDiscriminant::MinusMinus])
+        {
+            if let Expression::VariableReference(variable) = expr {
+                Expression::update(variable.identifier, operator, false)
+            } else {
+                self.invalid_target(operator, vec![expr])
+            }
+        } else {
+            expr
+        }
+    }
+
+    fn call(&mut self) -> Expression {
+        let mut callee = self.primary();
 
         loop {
-            if self
-                .advance_on_match(&[TokenDiscriminant::LeftParen])
-                .is_some()
+            if let Some(opening_parenthesis) =
+                self.advance_on_match(&[TokenDiscriminant::LeftParen])
             {
-                callee = self.finish_call(callee)?;
+                callee = self.finish_call(callee, opening_parenthesis);
+            } else if self.advance_on_match(&[TokenDiscriminant::Dot]).is_some() {
+                callee = match self.expect(TokenDiscriminant::Identifier) {
+                    Some(name) => Expression::get(callee, name),
+                    None => self.error_expression(vec![callee]),
+                };
             } else {
                 break;
             }
         }
-        Some(callee)
+        callee
     }
 
-    fn finish_call(&mut self, callee: Expression) -> Option<Expression> {
+    fn finish_call(&mut self, callee: Expression, opening_parenthesis: Token) -> Expression {
         let mut arguments = vec![];
-        if self.peek()?.discriminant() != TokenDiscriminant::RightParen {
+        if self
+            .peek()
+            .map(|t| t.discriminant() != TokenDiscriminant::RightParen)
+            .unwrap_or(false)
+        {
             loop {
-                arguments.push(self.expression()?);
+                arguments.push(self.expression());
                 if self.advance_on_match(&[TokenDiscriminant::Comma]).is_none() {
                     break;
                 }
             }
         }
-        let closing_parenthesis = self.expect(TokenDiscriminant::RightParen)?;
+        let closing_parenthesis = self
+            .expect(TokenDiscriminant::RightParen)
+            .unwrap_or(opening_parenthesis);
         if arguments.len() >= 255 {
-            // Ugly, we should set `has_errored` here.
             println!("You can't have more than 255 arguments");
-            return None;
+            self.report(Some(closing_parenthesis.clone()));
+            let children = std::iter::once(callee).chain(arguments).collect();
+            return Expression::error(Some(closing_parenthesis), children);
         }
-        Some(Expression::call(callee, closing_parenthesis, arguments))
+        Expression::call(callee, closing_parenthesis, arguments)
     }
 
-    fn primary(&mut self) -> Option<Expression> {
-        if self.advance_on_match(&[TokenDiscriminant::True]).is_some() {
-            Some(Expression::boolean(true))
-        } else if self.advance_on_match(&[TokenDiscriminant::False]).is_some() {
-            Some(Expression::boolean(false))
+    fn primary(&mut self) -> Expression {
+        if let Some(t) = self.advance_on_match(&[TokenDiscriminant::True]) {
+            Expression::boolean(true, t)
+        } else if let Some(t) = self.advance_on_match(&[TokenDiscriminant::False]) {
+            Expression::boolean(false, t)
         } else if let Some(t) = self.advance_on_match(&[TokenDiscriminant::Nil]) {
-            Some(Expression::null(t))
-        } else if let Some(t) = self.advance_on_match(&[TokenDiscriminant::Number]) {
-            Some(Expression::number(t))
+            Expression::null(t)
+        } else if let Some(t) =
+            self.advance_on_match(&[TokenDiscriminant::Number, TokenDiscriminant::Integer])
+        {
+            Expression::number(t)
         } else if let Some(t) = self.advance_on_match(&[TokenDiscriminant::String]) {
-            Some(Expression::string(t))
+            Expression::string(t)
         } else if let Some(t) = self.advance_on_match(&[TokenDiscriminant::Identifier]) {
-            Some(Expression::variable_reference(t))
+            Expression::variable_reference(t)
         } else if self
             .advance_on_match(&[TokenDiscriminant::LeftParen])
             .is_some()
         {
-            let expr = self.expression()?;
-            self.expect(TokenDiscriminant::RightParen)?;
-            Some(Expression::grouping(expr))
+            let expr = self.expression();
+            self.expect(TokenDiscriminant::RightParen);
+            Expression::grouping(expr)
         } else {
-            self.mode = ParsingMode::ErrorRecovery;
-            None
+            self.error();
+            self.error_expression(vec![])
         }
     }
 
@@ -422,38 +776,96 @@ where
         None
     }
 
+    /// If the parser is recovering from an error, leave error recovery mode and skip
+    /// the rest of the malformed statement.
     fn advance_until_recovery_point(&mut self) {
-        // Using a closure that returns `Option` to be able to use the `?` operator.
-        // Looking forward to try blocks.
-        let mut recover = || -> Option<()> {
-            loop {
-                let current = self.tokens.next()?;
-                if current.discriminant() == TokenDiscriminant::Semicolon {
-                    break None;
+        if self.mode == ParsingMode::Normal {
+            return;
+        }
+        self.mode = ParsingMode::Normal;
+        while !starts_test_block(&mut self.tokens) {
+            let Some(upcoming) = self.tokens.peek() else {
+                break;
+            };
+            match upcoming.discriminant() {
+                TokenDiscriminant::Semicolon => {
+                    self.tokens.next();
+                    break;
                 }
-                let upcoming = self.tokens.peek()?;
-                match upcoming.discriminant() {
-                    TokenDiscriminant::Class
-                    | TokenDiscriminant::Fun
-                    | TokenDiscriminant::Var
-                    | TokenDiscriminant::For
-                    | TokenDiscriminant::If
-                    | TokenDiscriminant::Print
-                    | TokenDiscriminant::Return
-                    | TokenDiscriminant::While => {
-                        break None;
-                    }
-                    _ => {}
+                // The end of the enclosing block, or a keyword that starts a statement.
+                // Parsing the statement consumes the keyword, so the parser can't get stuck.
+                TokenDiscriminant::RightBrace
+                | TokenDiscriminant::Import
+                | TokenDiscriminant::Export
+                | TokenDiscriminant::Fun
+                | TokenDiscriminant::Var
+                | TokenDiscriminant::Const
+                | TokenDiscriminant::For
+                | TokenDiscriminant::If
+                | TokenDiscriminant::Print
+                | TokenDiscriminant::Return
+                | TokenDiscriminant::Yield
+                | TokenDiscriminant::Throw
+                | TokenDiscriminant::Try
+                | TokenDiscriminant::While => break,
+                _ => {
+                    self.tokens.next();
                 }
             }
+        }
+    }
+
+    /// Report a syntax error at the upcoming token, then stop parsing the current statement:
+    /// the rest of it is made of error nodes.
+    /// Only the first error of a statement is reported: the ones that follow are likely
+    /// caused by it.
+    fn error(&mut self) {
+        if self.mode == ParsingMode::Normal {
+            let token = self.tokens.peek().cloned();
+            self.report(token);
+        }
+        self.mode = ParsingMode::ErrorRecovery;
+    }
+
+    /// Report a syntax error without stopping parsing, e.g. for a well-formed expression
+    /// in the wrong place.
+    /// A token causes at most one error: the ones that follow from it are not reported.
+    fn report(&mut self, token: Option<Token>) {
+        let span = |token: &Option<Token>| token.as_ref().map(Token::span);
+        if let Some(last) = self.errors.last() {
+            if span(&last.token) == span(&token) {
+                return;
+            }
+        }
+        let line = match &token {
+            Some(t) => t.line(),
+            None => self.tokens.line,
         };
-        let _ = recover();
+        self.errors.push(SyntaxError { token, line });
+    }
+
+    /// The node standing in for the statement the parser is recovering from.
+    fn error_statement(&mut self) -> Statement {
+        Statement::Error(ErrorStatement {
+            token: self.tokens.peek().cloned(),
+        })
+    }
+
+    /// The node standing in for the expression the parser is recovering from.
+    fn error_expression(&mut self, children: Vec<Expression>) -> Expression {
+        Expression::error(self.tokens.peek().cloned(), children)
+    }
+
+    /// Assignments, increments and decrements require a variable as their target.
+    fn invalid_target(&mut self, operator: Token, children: Vec<Expression>) -> Expression {
+        self.report(Some(operator.clone()));
+        Expression::error(Some(operator), children)
     }
 
     fn expect(&mut self, token_type: TokenDiscriminant) -> Option<Token> {
         let t = self.advance_on_match(&[token_type]);
         if t.is_none() {
-            self.mode = ParsingMode::ErrorRecovery;
+            self.error();
         }
         t
     }
@@ -480,12 +892,65 @@ where
     }
 }
 
+/// `test` is a contextual keyword: it starts a test block only when it is followed by the
+/// name of the test, a string literal. Anywhere else, it is an ordinary identifier.
+fn starts_test_block<TokenIter>(tokens: &mut Source<TokenIter>) -> bool
+where
+    TokenIter: Iterator<Item = Token>,
+{
+    let is_test_identifier = tokens
+        .peek()
+        .map(|t| t.discriminant() == TokenDiscriminant::Identifier && t.clone().lexeme() == "test")
+        .unwrap_or(false);
+    is_test_identifier
+        && tokens
+            .peek_nth(1)
+            .map(|t| t.discriminant() == TokenDiscriminant::String)
+            .unwrap_or(false)
+}
+
 /// Our parser does not care about trivia tokens.
 /// We give `Source` to our parser instead of the raw token stream: `Source` wraps the underlying
 /// token stream and makes sure to skip all trivia tokens, making them invisible to the parser.
-struct Source<TokenIter>(TokenIter)
+struct Source<TokenIter>
+where
+    TokenIter: Iterator<Item = Token>,
+{
+    tokens: Fuse<TokenIter>,
+    /// The tokens returned by [`Source::peek_nth`], in order.
+    peeked: VecDeque<Token>,
+    /// The line where the last token pulled from `tokens` ends, trivia included:
+    /// once `tokens` are over, the last line of the source code.
+    line: u64,
+}
+
+impl<TokenIter> Source<TokenIter>
 where
-    TokenIter: Iterator<Item = Token>;
+    TokenIter: Iterator<Item = Token>,
+{
+    fn peek(&mut self) -> Option<&Token> {
+        self.peek_nth(0)
+    }
+
+    /// The `n`-th upcoming token, without consuming it.
+    fn peek_nth(&mut self, n: usize) -> Option<&Token> {
+        while self.peeked.len() <= n {
+            let t = self.next_significant()?;
+            self.peeked.push_back(t);
+        }
+        self.peeked.get(n)
+    }
+
+    fn next_significant(&mut self) -> Option<Token> {
+        for t in self.tokens.by_ref() {
+            self.line = t.line();
+            if t.discriminant() != TokenDiscriminant::Trivia {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
 
 impl<TokenIter> Iterator for Source<TokenIter>
 where
@@ -494,13 +959,7 @@ where
     type Item = Token;
 
     fn next(&mut self) -> Option<Self::Item> {
-        loop {
-            match self.0.next() {
-                None => break None,
-                Some(t) if t.discriminant() == TokenDiscriminant::Trivia => continue,
-                Some(t) => break Some(t),
-            }
-        }
+        self.peeked.pop_front().or_else(|| self.next_significant())
     }
 }
 
@@ -526,14 +985,19 @@ fn _display_statement(w: &mut impl Write, s: &Statement, depth: u8) -> Result<()
         Statement::VariableDeclaration(VariableDeclarationStatement {
             initializer,
             identifier,
+            constant,
         }) => {
-            writeln!(w, "Variable Declaration")?;
+            if *constant {
+                writeln!(w, "Constant Declaration")?;
+            } else {
+                writeln!(w, "Variable Declaration")?;
+            }
             _display_token(w, identifier, depth + 1)?;
             if let Some(e) = initializer {
                 _display_expression(w, e, depth + 1)?;
             }
         }
-        Statement::Block(BlockStatement(statements)) => {
+        Statement::Block(BlockStatement { statements, .. }) => {
             writeln!(w, "Block")?;
             for statement in statements {
                 _display_statement(w, statement, depth + 1)?;
@@ -556,16 +1020,40 @@ fn _display_statement(w: &mut impl Write, s: &Statement, depth: u8) -> Result<()
             _display_expression(w, condition, depth + 1)?;
             _display_statement(w, body, depth + 1)?;
         }
+        Statement::ForIn(ForInStatement {
+            variable,
+            iterable,
+            body,
+            ..
+        }) => {
+            writeln!(w, "For In")?;
+            _display_token(w, variable, depth + 1)?;
+            _display_expression(w, iterable, depth + 1)?;
+            _display_statement(w, body, depth + 1)?;
+        }
         Statement::FunctionDeclaration(FunctionDeclarationStatement {
             name,
             parameters,
+            rest,
             body,
+            generator,
         }) => {
-            writeln!(w, "Function Declaration")?;
+            if *generator {
+                writeln!(w, "Generator Declaration")?;
+            } else {
+                writeln!(w, "Function Declaration")?;
+            }
             _display_token(w, name, depth + 1)?;
             _display_string(w, "Parameters", depth + 1)?;
-            for parameter in parameters {
-                _display_token(w, parameter, depth + 2)?;
+            for Parameter { name, default } in parameters {
+                _display_token(w, name, depth + 2)?;
+                if let Some(default) = default {
+                    _display_expression(w, default, depth + 3)?;
+                }
+            }
+            if let Some(rest) = rest {
+                _display_string(w, "Rest", depth + 1)?;
+                _display_token(w, rest, depth + 2)?;
             }
             _display_string(w, "Body", depth + 1)?;
             for s in body {
@@ -576,6 +1064,61 @@ fn _display_statement(w: &mut impl Write, s: &Statement, depth: u8) -> Result<()
             writeln!(w, "Return")?;
             _display_expression(w, value, depth + 1)?;
         }
+        Statement::Throw(ThrowStatement { value, .. }) => {
+            writeln!(w, "Throw")?;
+            _display_expression(w, value, depth + 1)?;
+        }
+        Statement::Yield(YieldStatement { value, .. }) => {
+            writeln!(w, "Yield")?;
+            _display_expression(w, value, depth + 1)?;
+        }
+        Statement::Import(ImportStatement { path, alias, .. }) => {
+            writeln!(w, "Import")?;
+            _display_token(w, path, depth + 1)?;
+            if let Some(alias) = alias {
+                _display_token(w, alias, depth + 1)?;
+            }
+        }
+        Statement::Export(ExportStatement { declaration, .. }) => {
+            writeln!(w, "Export")?;
+            _display_statement(w, declaration, depth + 1)?;
+        }
+        Statement::Test(TestStatement { name, body, .. }) => {
+            writeln!(w, "Test")?;
+            _display_token(w, name, depth + 1)?;
+            _display_block(w, body, depth + 1)?;
+        }
+        Statement::Try(TryStatement {
+            body,
+            catch,
+            finally,
+        }) => {
+            writeln!(w, "Try")?;
+            _display_block(w, body, depth + 1)?;
+            if let Some(CatchClause { variable, body }) = catch {
+                _display_string(w, "Catch", depth + 1)?;
+                _display_token(w, variable, depth + 2)?;
+                _display_block(w, body, depth + 2)?;
+            }
+            if let Some(finally) = finally {
+                _display_string(w, "Finally", depth + 1)?;
+                _display_block(w, finally, depth + 2)?;
+            }
+        }
+        Statement::Error(ErrorStatement { token }) => {
+            writeln!(w, "Error")?;
+            if let Some(token) = token {
+                _display_token(w, token, depth + 1)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn _display_block(w: &mut impl Write, b: &BlockStatement, depth: u8) -> std::fmt::Result {
+    _display_string(w, "Block", depth)?;
+    for statement in &b.statements {
+        _display_statement(w, statement, depth + 1)?;
     }
     Ok(())
 }
@@ -607,7 +1150,7 @@ fn _display_expression(
                 | LiteralExpression::Number(t) => {
                     _display_token(w, t, depth + 1)?;
                 }
-                LiteralExpression::Boolean(b) => {
+                LiteralExpression::Boolean(b, _) => {
                     let s = if *b { "True" } else { "False" };
                     _display_string(w, s, depth + 1)?;
                 }
@@ -626,6 +1169,39 @@ fn _display_expression(
             _display_token(w, identifier, depth + 1)?;
             _display_expression(w, value, depth + 1)?;
         }
+        Expression::CompoundAssignment(CompoundAssignmentExpression {
+            identifier,
+            operator,
+            value,
+        }) => {
+            writeln!(w, "Compound Assignment")?;
+            _display_token(w, identifier, depth + 1)?;
+            _display_token(w, operator, depth + 1)?;
+            _display_expression(w, value, depth + 1)?;
+        }
+        Expression::Update(UpdateExpression {
+            identifier,
+            operator,
+            prefix,
+        }) => {
+            if *prefix {
+                writeln!(w, "Prefix Update")?;
+            } else {
+                writeln!(w, "Postfix Update")?;
+            }
+            _display_token(w, identifier, depth + 1)?;
+            _display_token(w, operator, depth + 1)?;
+        }
+        Expression::Conditional(ConditionalExpression {
+            condition,
+            then_branch,
+            else_branch,
+        }) => {
+            writeln!(w, "Conditional")?;
+            _display_expression(w, condition, depth + 1)?;
+            _display_expression(w, then_branch, depth + 1)?;
+            _display_expression(w, else_branch, depth + 1)?;
+        }
         Expression::Call(CallExpression {
             callee, arguments, ..
         }) => {
@@ -636,6 +1212,20 @@ fn _display_expression(
                 _display_expression(w, argument, depth + 2)?;
             }
         }
+        Expression::Get(GetExpression { object, name }) => {
+            writeln!(w, "Get")?;
+            _display_expression(w, object, depth + 1)?;
+            _display_token(w, name, depth + 1)?;
+        }
+        Expression::Error(ErrorExpression { token, children }) => {
+            writeln!(w, "Error")?;
+            if let Some(token) = token {
+                _display_token(w, token, depth + 1)?;
+            }
+            for child in children {
+                _display_expression(w, child, depth + 1)?;
+            }
+        }
     }
     Ok(())
 }
@@ -647,6 +1237,7 @@ fn _display_token(w: &mut impl Write, t: &Token, depth: u8) -> std::fmt::Result
     match t.ty() {
         TokenType::String(s) => writeln!(w, " \"{}\"", s)?,
         TokenType::Number(n) => writeln!(w, " {}", n)?,
+        TokenType::Integer(n) => writeln!(w, " {}", n)?,
         _ => writeln!(w)?,
     }
     Ok(())
@@ -663,7 +1254,7 @@ fn _display_string(w: &mut impl Write, s: &str, depth: u8) -> std::fmt::Result {
 mod tests {
     use crate::parser::{display_ast, Parser};
     use crate::scanner::Scanner;
-    use insta::assert_display_snapshot;
+    use insta::assert_snapshot;
 
     fn parse(source: &str) -> String {
         if let Ok(statements) = Parser::parse(Scanner::new(source)) {
@@ -673,10 +1264,17 @@ mod tests {
         }
     }
 
+    /// The AST of an invalid program, error nodes included.
+    fn parse_invalid(source: &str) -> String {
+        let (statements, errors) = Parser::parse_reporting_errors(Scanner::new(source));
+        assert!(!errors.is_empty(), "The source code is valid");
+        statements.iter().map(|s| display_ast(s).unwrap()).collect()
+    }
+
     #[test]
     fn parse_string_expression() {
         let ast = parse(r#""My name is Luça";"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Literal
           String "My name is Luça"
@@ -686,7 +1284,7 @@ mod tests {
     #[test]
     fn parse_number() {
         let ast = parse(r#"12.65;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Literal
           Number 12.65
@@ -696,21 +1294,21 @@ mod tests {
     #[test]
     fn parse_binary() {
         let ast = parse(r#"12.65 + 2;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Binary
           Literal
            Number 12.65
           Plus
           Literal
-           Number 2
+           Integer 2
         "###)
     }
 
     #[test]
     fn parse_binary_without_parens() {
         let ast = parse(r#"12.65 + 2 * 3;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Binary
           Literal
@@ -718,17 +1316,17 @@ mod tests {
           Plus
           Binary
            Literal
-            Number 2
+            Integer 2
            Star
            Literal
-            Number 3
+            Integer 3
         "###)
     }
 
     #[test]
     fn parse_binary_with_parens() {
         let ast = parse(r#"(12.65 + 2) * 3;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Binary
           Grouping
@@ -737,17 +1335,31 @@ mod tests {
              Number 12.65
             Plus
             Literal
-             Number 2
+             Integer 2
           Star
           Literal
-           Number 3
+           Integer 3
+        "###)
+    }
+
+    #[test]
+    fn parse_inequality() {
+        let ast = parse("1 != 2;");
+        assert_snapshot!(ast, @r###"
+        Expression
+         Binary
+          Literal
+           Integer 1
+          BangEqual
+          Literal
+           Integer 2
         "###)
     }
 
     #[test]
     fn parse_complex_equality() {
         let ast = parse(r#"!((12 + 2) * 3) == 50 / 12;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Binary
           Unary
@@ -757,41 +1369,41 @@ mod tests {
              Grouping
               Binary
                Literal
-                Number 12
+                Integer 12
                Plus
                Literal
-                Number 2
+                Integer 2
              Star
              Literal
-              Number 3
+              Integer 3
           EqualEqual
           Binary
            Literal
-            Number 50
+            Integer 50
            Slash
            Literal
-            Number 12
+            Integer 12
         "###)
     }
 
     #[test]
     fn parse_print_statement() {
         let ast = parse(r#"print 2+5;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Print
          Binary
           Literal
-           Number 2
+           Integer 2
           Plus
           Literal
-           Number 5
+           Integer 5
         "###)
     }
 
     #[test]
     fn parse_logical_statement() {
         let ast = parse(r#"true and 2+5 or true;"#);
-        assert_display_snapshot!(ast, @r###"
+        assert_snapshot!(ast, @r###"
         Expression
          Binary
           Binary
@@ -800,13 +1412,349 @@ mod tests {
            And
            Binary
             Literal
-             Number 2
+             Integer 2
             Plus
             Literal
-             Number 5
+             Integer 5
           Or
           Literal
            True
         "###)
     }
+
+    #[test]
+    fn parse_compound_assignment() {
+        let ast = parse(r#"a += 2 * 3;"#);
+        assert_snapshot!(ast, @r###"
+        Expression
+         Compound Assignment
+          Identifier
+          PlusEqual
+          Binary
+           Literal
+            Integer 2
+           Star
+           Literal
+            Integer 3
+        "###)
+    }
+
+    #[test]
+    fn parse_prefix_and_postfix_updates() {
+        let ast = parse(r#"++a - b--;"#);
+        assert_snapshot!(ast, @r###"
+        Expression
+         Binary
+          Prefix Update
+           Identifier
+           PlusPlus
+          Minus
+          Postfix Update
+           Identifier
+           MinusMinus
+        "###)
+    }
+
+    #[test]
+    fn updates_require_a_variable_as_target() {
+        assert!(Parser::parse(Scanner::new("1++;")).is_err());
+        assert!(Parser::parse(Scanner::new("(a) += 1;")).is_err());
+    }
+
+    #[test]
+    fn parse_conditional() {
+        let ast = parse(r#"a ? 1 : b ? 2 : 3;"#);
+        assert_snapshot!(ast, @r###"
+        Expression
+         Conditional
+          Variable Reference
+           Identifier
+          Literal
+           Integer 1
+          Conditional
+           Variable Reference
+            Identifier
+           Literal
+            Integer 2
+           Literal
+            Integer 3
+        "###)
+    }
+
+    #[test]
+    fn conditional_binds_looser_than_logical_operators() {
+        let ast = parse(r#"a = true or false ? 1 : 2;"#);
+        assert_snapshot!(ast, @r###"
+        Expression
+         Variable Assignment
+          Identifier
+          Conditional
+           Binary
+            Literal
+             True
+            Or
+            Literal
+             False
+           Literal
+            Integer 1
+           Literal
+            Integer 2
+        "###)
+    }
+
+    #[test]
+    fn parse_constant_declaration() {
+        let ast = parse(r#"const a = 1;"#);
+        assert_snapshot!(ast, @r###"
+        Constant Declaration
+         Identifier
+         Literal
+          Integer 1
+        "###)
+    }
+
+    #[test]
+    fn parse_try_catch_finally() {
+        let ast = parse(r#"try { throw "oops"; } catch (e) { print e.message; } finally {}"#);
+        assert_snapshot!(ast, @r###"
+        Try
+         Block
+          Throw
+           Literal
+            String "oops"
+         Catch
+          Identifier
+          Block
+           Print
+            Get
+             Variable Reference
+              Identifier
+             Identifier
+         Finally
+          Block
+        "###)
+    }
+
+    #[test]
+    fn try_requires_catch_or_finally() {
+        assert!(Parser::parse(Scanner::new("try { print 1; }")).is_err());
+    }
+
+    #[test]
+    fn parse_for_in() {
+        let ast = parse("for (c in \"ab\") print c;");
+        assert_snapshot!(ast, @r###"
+        For In
+         Identifier
+         Literal
+          String "ab"
+         Print
+          Variable Reference
+           Identifier
+        "###)
+    }
+
+    #[test]
+    fn parse_for_loop_with_expression_initializer() {
+        let ast = parse("for (i = 0; i < 2; i = i + 1) print i;");
+        assert_snapshot!(ast, @r###"
+        Block
+         Expression
+          Variable Assignment
+           Identifier
+           Literal
+            Integer 0
+         While
+          Binary
+           Variable Reference
+            Identifier
+           Less
+           Literal
+            Integer 2
+          Block
+           Print
+            Variable Reference
+             Identifier
+           Expression
+            Variable Assignment
+             Identifier
+             Binary
+              Variable Reference
+               Identifier
+              Plus
+              Literal
+               Integer 1
+        "###)
+    }
+
+    #[test]
+    fn parse_generator() {
+        let ast = parse("fun* numbers() { yield 1; yield; }");
+        assert_snapshot!(ast, @r###"
+        Generator Declaration
+         Identifier
+         Parameters
+         Body
+          Block
+           Yield
+            Literal
+             Integer 1
+           Yield
+            Literal
+             Semicolon
+        "###)
+    }
+
+    #[test]
+    fn parse_test() {
+        let ast = parse(r#"test "addition" { assert(1 + 1 == 2); }"#);
+        assert_snapshot!(ast, @r###"
+        Test
+         String "addition"
+         Block
+          Expression
+           Call
+            Variable Reference
+             Identifier
+            Arguments
+             Binary
+              Binary
+               Literal
+                Integer 1
+               Plus
+               Literal
+                Integer 1
+              EqualEqual
+              Literal
+               Integer 2
+        "###)
+    }
+
+    #[test]
+    fn input_that_ends_too_early_is_incomplete() {
+        let is_incomplete = |source| Parser::is_incomplete(Scanner::new(source));
+        assert!(is_incomplete("fun f() {"));
+        assert!(is_incomplete("print (1 +"));
+        assert!(is_incomplete("var a = 1"));
+        assert!(is_incomplete("print \"unterminated"));
+        assert!(is_incomplete("if (true) { print 1; } else"));
+        assert!(!is_incomplete("print 1;"));
+        assert!(!is_incomplete("print 1;)"));
+        assert!(!is_incomplete("print );\nfun f() {"));
+    }
+
+    #[test]
+    fn only_lone_expressions_parse_as_expressions() {
+        let parse_expression = |source| Parser::parse_expression(Scanner::new(source));
+        assert!(parse_expression("1 + 2").is_some());
+        assert!(parse_expression("a = f(1)\n").is_some());
+        assert!(parse_expression("1 + 2;").is_none());
+        assert!(parse_expression("1 + 2 3").is_none());
+        assert!(parse_expression("print 1").is_none());
+        assert!(parse_expression("").is_none());
+    }
+
+    #[test]
+    fn parse_import() {
+        let ast = parse(r#"import "lib/math.lox" as math;"#);
+        assert_snapshot!(ast, @r###"
+        Import
+         String "lib/math.lox"
+         Identifier
+        "###)
+    }
+
+    #[test]
+    fn only_declarations_can_be_exported() {
+        assert!(Parser::parse(Scanner::new("export fun f() {}")).is_ok());
+        assert!(Parser::parse(Scanner::new("export print 1;")).is_err());
+    }
+
+    #[test]
+    fn parse_default_and_rest_parameters() {
+        let ast = parse(r#"fun f(a, b = a * 2, ...rest) {}"#);
+        assert_snapshot!(ast, @r###"
+        Function Declaration
+         Identifier
+         Parameters
+          Identifier
+          Identifier
+           Binary
+            Variable Reference
+             Identifier
+            Star
+            Literal
+             Integer 2
+         Rest
+          Identifier
+         Body
+          Block
+        "###)
+    }
+
+    #[test]
+    fn invalid_parameter_lists_are_rejected() {
+        // Required parameters can't follow parameters with a default value
+        assert!(Parser::parse(Scanner::new("fun f(a = 1, b) {}")).is_err());
+        // The rest parameter must be the last one
+        assert!(Parser::parse(Scanner::new("fun f(...a, b) {}")).is_err());
+    }
+
+    #[test]
+    fn malformed_expressions_are_replaced_by_error_nodes() {
+        let ast = parse_invalid("print f(1, 2;\nprint a.;\n1 = -;");
+        assert_snapshot!(ast, @r###"
+        Print
+         Call
+          Variable Reference
+           Identifier
+          Arguments
+           Literal
+            Integer 1
+           Literal
+            Integer 2
+        Print
+         Error
+          Semicolon
+          Variable Reference
+           Identifier
+        Expression
+         Error
+          Equal
+          Literal
+           Integer 1
+          Unary
+           Minus
+           Error
+            Semicolon
+        "###)
+    }
+
+    #[test]
+    fn parsing_resumes_after_a_malformed_statement() {
+        let ast = parse_invalid("var = 1;\nfun f() {\n  if (a print a;\n  return\n}\nprint 2;");
+        assert_snapshot!(ast, @r###"
+        Error
+         Equal
+        Function Declaration
+         Identifier
+         Parameters
+         Body
+          Block
+           IfElse
+            Variable Reference
+             Identifier
+            Error
+             Print
+           Print
+            Variable Reference
+             Identifier
+           Return
+            Error
+             RightBrace
+        Print
+         Literal
+          Integer 2
+        "###)
+    }
 }
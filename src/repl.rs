@@ -1,23 +1,458 @@
-use crate::{Environment, Interpreter};
+use crate::parser::Parser;
+use crate::scanner::{keywords, Scanner};
+use crate::{
+    display_syntax_tree, display_tokens, Environment, ExecuteModuleError, ExecuteRawError,
+    FileSystemLoader, Interpreter,
+};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, Editor, Helper};
 use std::cell::RefCell;
-use std::io::{stdout, Write};
+use std::io::{stderr, stdout, Write};
+use std::path::PathBuf;
 use std::rc::Rc;
+use std::time::Instant;
 
 /// Read-print-evaluation loop.
 /// It prompts the user to enter lox code and then interprets it on the fly.
+///
+/// A lone expression, without a trailing semicolon, is evaluated and its value echoed.
+/// Input that is not complete yet (e.g. an open block) continues on the next line, with a
+/// `... ` prompt. An empty line submits the input as it is.
+///
+/// Lines can be edited, `Tab` completes keywords and global names, and the history of
+/// previous sessions is available via the arrow keys and `Ctrl-R`.
+///
+/// Lines starting with `:` are commands for the REPL itself, e.g. `:load script.lox`:
+/// `:help` lists them.
 pub fn repl() -> Result<(), std::io::Error> {
     let environment = Rc::new(RefCell::new(Environment::new()));
-    let mut interpreter = Interpreter::new(stdout(), environment);
+    // Imports are resolved relative to the directory the REPL was launched from.
+    let loader = FileSystemLoader::new(".");
+    let mut interpreter = Interpreter::new(stdout(), Rc::clone(&environment), loader);
+
+    let mut editor: Editor<LoxHelper, DefaultHistory> = Editor::new().map_err(into_io_error)?;
+    editor.set_helper(Some(LoxHelper {
+        environment: Rc::clone(&environment),
+    }));
+    let history = history_path();
+    if let Some(history) = &history {
+        // There is no history yet the first time the REPL is launched.
+        let _ = editor.load_history(history);
+    }
+
+    let mut input = String::new();
     loop {
-        print!("> ");
-        stdout().flush()?;
-        let mut input = String::new();
-        if std::io::stdin().read_line(&mut input).is_err() || input.is_empty() {
-            break Ok(());
+        let prompt = if input.is_empty() { "> " } else { "... " };
+        let line = match editor.readline(prompt) {
+            Ok(line) => line,
+            // `Ctrl-C` discards the input, `Ctrl-D` quits.
+            Err(ReadlineError::Interrupted) => {
+                input.clear();
+                continue;
+            }
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(into_io_error(e)),
+        };
+        let outcome = if input.is_empty() && line.trim_start().starts_with(':') {
+            let _ = editor.add_history_entry(line.trim());
+            meta_command(
+                line.trim(),
+                &mut interpreter,
+                &environment,
+                &mut stdout(),
+                &mut stderr(),
+            )?
+        } else {
+            let submitted = !input.is_empty() && line.trim().is_empty();
+            input.push_str(&line);
+            input.push('\n');
+            if !submitted
+                && Parser::parse_expression(Scanner::new(&input)).is_none()
+                && Parser::is_incomplete(Scanner::new(&input))
+            {
+                continue;
+            }
+            let _ = editor.add_history_entry(input.trim_end());
+            let outcome = evaluate(&mut interpreter, &input);
+            input.clear();
+            if let Ok(Some(echo)) = &outcome {
+                writeln!(stdout(), "{echo}")?;
+            }
+            outcome.map(|_| ()).map_err(ExecuteModuleError::from)
+        };
+        match outcome {
+            Ok(()) => {}
+            Err(ExecuteModuleError::ExecuteRawError(ExecuteRawError::RuntimeError(e)))
+                if e.exit_code().is_some() =>
+            {
+                save_history(&mut editor, history.as_ref());
+                std::process::exit(e.exit_code().unwrap())
+            }
+            // The message of the loader error is more helpful than the generic one.
+            Err(ExecuteModuleError::LoaderError(e)) => eprintln!("{}", e),
+            Err(e) => eprintln!("{}", e),
+        }
+    }
+    save_history(&mut editor, history.as_ref());
+    Ok(())
+}
+
+/// Execute `input`, unless it is a lone expression: it is evaluated instead, and the
+/// representation of its value is returned, to be echoed.
+fn evaluate(interpreter: &mut Interpreter, input: &str) -> Result<Option<String>, ExecuteRawError> {
+    match Parser::parse_expression(Scanner::new(input)) {
+        Some(expression) => interpreter.evaluate(expression).map(Some),
+        None => interpreter.execute_raw(input).map(|()| None),
+    }
+}
+
+const HELP: &str = ":load <file>    Execute a file in the current session
+:env            List the global bindings and their values
+:ast <code>     Print the syntax tree of the code
+:tokens <code>  Print the tokens of the code
+:reset          Discard all the bindings defined in the session
+:time <code>    Execute the code and report how long it took
+:help           Print this message";
+
+/// Execute a colon-prefixed command, e.g. `:load script.lox`, writing what it prints to
+/// `output` and complaints about the command itself to `errors`.
+/// The outer error is a failure to write, the inner one a failure of the command.
+fn meta_command(
+    line: &str,
+    interpreter: &mut Interpreter,
+    environment: &Rc<RefCell<Environment>>,
+    output: &mut impl Write,
+    errors: &mut impl Write,
+) -> std::io::Result<Result<(), ExecuteModuleError>> {
+    let (command, argument) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let argument = argument.trim();
+    match (command, argument) {
+        (":load", path) if !path.is_empty() => {
+            if let Err(e) = interpreter.execute_module(path) {
+                return Ok(Err(e));
+            }
+        }
+        (":env", "") => {
+            for (name, value) in environment.borrow().describe_globals() {
+                writeln!(output, "{name} = {value}")?;
+            }
         }
-        let input = input.trim().to_string();
-        if let Err(e) = interpreter.execute_raw(&input) {
-            eprintln!("{}", e);
+        (":ast", code) if !code.is_empty() => match display_syntax_tree(code) {
+            Ok(tree) => write!(output, "{tree}")?,
+            Err(e) => return Ok(Err(e.into())),
+        },
+        (":tokens", code) if !code.is_empty() => write!(output, "{}", display_tokens(code))?,
+        (":reset", "") => *environment.borrow_mut() = Environment::new(),
+        (":time", code) if !code.is_empty() => {
+            let start = Instant::now();
+            let outcome = evaluate(interpreter, code);
+            let elapsed = start.elapsed();
+            if let Ok(Some(echo)) = &outcome {
+                writeln!(output, "{echo}")?;
+            }
+            writeln!(output, "Took {elapsed:?}")?;
+            return Ok(outcome.map(|_| ()).map_err(ExecuteModuleError::from));
         }
+        (":help", "") => writeln!(output, "{HELP}")?,
+        _ => writeln!(
+            errors,
+            "Unknown command `{line}`. Available commands:\n{HELP}"
+        )?,
+    }
+    Ok(Ok(()))
+}
+
+/// Where the REPL history is persisted, in the user's configuration directory.
+fn history_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|config| config.join("jlox").join("history"))
+}
+
+fn save_history(editor: &mut Editor<LoxHelper, DefaultHistory>, path: Option<&PathBuf>) {
+    let Some(path) = path else { return };
+    let saved = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .map_err(ReadlineError::from)
+        .and_then(|()| editor.save_history(path));
+    // Losing the history is not worth failing the session for.
+    if let Err(e) = saved {
+        eprintln!(
+            "Failed to save the REPL history to `{}`: {e}",
+            path.display()
+        );
+    }
+}
+
+fn into_io_error(e: ReadlineError) -> std::io::Error {
+    match e {
+        ReadlineError::Io(e) => e,
+        e => std::io::Error::other(e),
+    }
+}
+
+/// Completes keywords and the names defined in the global scope.
+struct LoxHelper {
+    environment: Rc<RefCell<Environment>>,
+}
+
+impl Completer for LoxHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+        let start = line[..pos]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| !is_identifier(*c))
+            .map_or(0, |(i, c)| i + c.len_utf8());
+        let prefix = &line[start..pos];
+        if prefix.is_empty() {
+            return Ok((pos, vec![]));
+        }
+        let environment = self.environment.borrow();
+        let mut candidates: Vec<String> = keywords()
+            .map(str::to_owned)
+            .chain(environment.global_names().map(str::to_owned))
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for LoxHelper {
+    type Hint = String;
+}
+
+impl Highlighter for LoxHelper {}
+
+impl Validator for LoxHelper {}
+
+impl Helper for LoxHelper {}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, meta_command, LoxHelper};
+    use crate::{Environment, InMemoryLoader, Interpreter};
+    use insta::assert_snapshot;
+    use rustyline::completion::Completer;
+    use rustyline::history::DefaultHistory;
+    use rustyline::Context;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Run the `commands` in a session where `source` was executed first, with `loader`
+    /// resolving the modules. It returns what the commands wrote to the output and to
+    /// the errors, and what the programs of the session printed.
+    fn run_session(
+        loader: InMemoryLoader,
+        source: &str,
+        commands: &[&str],
+    ) -> (String, String, String) {
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        let mut program_output = Vec::new();
+        let mut interpreter =
+            Interpreter::new(&mut program_output, Rc::clone(&environment), loader);
+        interpreter.execute_raw(source).unwrap();
+        let (mut output, mut errors) = (Vec::new(), Vec::new());
+        for command in commands {
+            meta_command(
+                command,
+                &mut interpreter,
+                &environment,
+                &mut output,
+                &mut errors,
+            )
+            .unwrap()
+            .unwrap();
+        }
+        drop(interpreter);
+        (
+            String::from_utf8(output).unwrap(),
+            String::from_utf8(errors).unwrap(),
+            String::from_utf8(program_output).unwrap(),
+        )
+    }
+
+    /// Like [`run_session`], without any module.
+    fn run_commands(source: &str, commands: &[&str]) -> (String, String) {
+        let (output, errors, _) = run_session(InMemoryLoader::new(), source, commands);
+        (output, errors)
+    }
+
+    /// The completion candidates for `line` with the cursor at its end.
+    fn complete(source: &str, line: &str) -> String {
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        let mut output = Vec::new();
+        Interpreter::new(&mut output, Rc::clone(&environment), InMemoryLoader::new())
+            .execute_raw(source)
+            .unwrap();
+        let helper = LoxHelper { environment };
+        let history = DefaultHistory::new();
+        let (start, candidates) = helper
+            .complete(line, line.len(), &Context::new(&history))
+            .unwrap();
+        format!("{start}: {}", candidates.join(", "))
+    }
+
+    #[test]
+    fn lone_expressions_echo_their_value() {
+        let mut output = Vec::new();
+        let environment = Rc::new(RefCell::new(Environment::new()));
+        let mut interpreter = Interpreter::new(&mut output, environment, InMemoryLoader::new());
+        let mut echo = |input| evaluate(&mut interpreter, input).unwrap();
+        assert_snapshot!(echo("\"lo\" + \"x\"").unwrap(), @r###"
+        "lox"
+        "###);
+        assert_snapshot!(echo("1 + 2").unwrap(), @"3");
+        assert_snapshot!(echo("nil").unwrap(), @"`nil`");
+        assert_eq!(echo("print \"statement\";"), None);
+        drop(interpreter);
+        assert_snapshot!(String::from_utf8(output).unwrap(), @"statement");
+    }
+
+    #[test]
+    fn load_executes_a_module_in_the_session() {
+        let mut loader = InMemoryLoader::new();
+        loader.insert("greet.lox", r#"var greeting = "hi"; print "loading";"#);
+        let (output, errors, printed) = run_session(loader, "", &[":load greet.lox", ":env"]);
+        assert_snapshot!(output, @r###"
+        assert = <native fn assert>
+        exit = <native fn exit>
+        greeting = hi
+        range = <native fn range>
+        repr = <native fn repr>
+        "###);
+        assert_snapshot!(errors, @"");
+        assert_snapshot!(printed, @"loading");
+    }
+
+    #[test]
+    fn env_lists_the_global_bindings() {
+        let (output, errors) =
+            run_commands("var b = \"two\"; var a = 1; { var c = 3; }", &[":env"]);
+        assert_snapshot!(output, @r###"
+        a = 1
+        assert = <native fn assert>
+        b = two
+        exit = <native fn exit>
+        range = <native fn range>
+        repr = <native fn repr>
+        "###);
+        assert_snapshot!(errors, @"");
+    }
+
+    #[test]
+    fn reset_discards_the_bindings_of_the_session() {
+        let (output, _) = run_commands("var a = 1;", &[":reset", ":env"]);
+        assert_snapshot!(output, @r###"
+        assert = <native fn assert>
+        exit = <native fn exit>
+        range = <native fn range>
+        repr = <native fn repr>
+        "###);
+    }
+
+    #[test]
+    fn ast_and_tokens_print_the_code_they_are_given() {
+        let (output, _) = run_commands("", &[":ast print 1 + a;"]);
+        assert_snapshot!(output, @r###"
+        Print
+         Binary
+          Literal
+           Integer 1
+          Plus
+          Variable Reference
+           Identifier
+        "###);
+        let (output, _) = run_commands("", &[":tokens print 1;"]);
+        assert_snapshot!(output, @r###"
+        L1 - Print print
+        L1 - Trivia  
+        L1 - Integer 1 1
+        L1 - Semicolon ;
+        "###);
+    }
+
+    #[test]
+    fn time_reports_how_long_the_code_took() {
+        let (output, _, printed) = run_session(
+            InMemoryLoader::new(),
+            "",
+            &[":time \"lo\" + \"x\"", ":time print 1;"],
+        );
+        let lines: Vec<&str> = output.lines().collect();
+        let [echo, took, took_again] = lines.as_slice() else {
+            panic!("Unexpected output: {output}");
+        };
+        assert_snapshot!(echo, @r###"
+        "lox"
+        "###);
+        assert!(took.starts_with("Took "), "{took}");
+        assert!(took_again.starts_with("Took "), "{took_again}");
+        assert_snapshot!(printed, @"1");
+    }
+
+    #[test]
+    fn help_lists_the_commands() {
+        let (output, errors) = run_commands("", &[":help"]);
+        assert_snapshot!(output, @r###"
+        :load <file>    Execute a file in the current session
+        :env            List the global bindings and their values
+        :ast <code>     Print the syntax tree of the code
+        :tokens <code>  Print the tokens of the code
+        :reset          Discard all the bindings defined in the session
+        :time <code>    Execute the code and report how long it took
+        :help           Print this message
+        "###);
+        assert_snapshot!(errors, @"");
+    }
+
+    #[test]
+    fn unknown_commands_are_reported_with_the_available_ones() {
+        let (output, errors) = run_commands("", &[":frobnicate", ":env extra"]);
+        assert_snapshot!(output, @"");
+        assert_snapshot!(errors, @r###"
+        Unknown command `:frobnicate`. Available commands:
+        :load <file>    Execute a file in the current session
+        :env            List the global bindings and their values
+        :ast <code>     Print the syntax tree of the code
+        :tokens <code>  Print the tokens of the code
+        :reset          Discard all the bindings defined in the session
+        :time <code>    Execute the code and report how long it took
+        :help           Print this message
+        Unknown command `:env extra`. Available commands:
+        :load <file>    Execute a file in the current session
+        :env            List the global bindings and their values
+        :ast <code>     Print the syntax tree of the code
+        :tokens <code>  Print the tokens of the code
+        :reset          Discard all the bindings defined in the session
+        :time <code>    Execute the code and report how long it took
+        :help           Print this message
+        "###);
+    }
+
+    #[test]
+    fn keywords_are_completed() {
+        assert_snapshot!(complete("", "fo"), @"0: for");
+        assert_snapshot!(complete("", "var x = "), @"8:");
+    }
+
+    #[test]
+    fn global_names_are_completed() {
+        let source = "var counter = 0; fun count() {} { var country = 1; }";
+        assert_snapshot!(complete(source, "print cou"), @"6: count, counter");
+        assert_snapshot!(complete(source, "f(a, cl"), @"5: class");
     }
 }
@@ -0,0 +1,276 @@
+use crate::parser::ast::{
+    BlockStatement, CallExpression, CatchClause, CompoundAssignmentExpression,
+    ConditionalExpression, ErrorExpression, ExportStatement, Expression, ExpressionStatement,
+    ForInStatement, FunctionDeclarationStatement, GetExpression, IfElseStatement, ImportStatement,
+    Parameter, PrintStatement, ReturnStatement, Statement, TestStatement, ThrowStatement,
+    TryStatement, UpdateExpression, VariableAssignmentExpression, VariableDeclarationStatement,
+    WhileStatement, YieldStatement,
+};
+use crate::scanner::Token;
+use std::collections::HashMap;
+
+/// Perform static checks on a series of statements before they get executed.
+///
+/// The resolver mirrors the lexical scopes that the interpreter is going to create at runtime,
+/// keeping track of which bindings were declared as constants.
+/// It can only reason about declarations that are part of `statements`: bindings that were
+/// defined elsewhere (e.g. in a previous REPL input) are checked at runtime instead.
+pub fn resolve(statements: &[Statement]) -> Result<(), Vec<ResolverError>> {
+    let mut resolver = Resolver {
+        scopes: vec![HashMap::new()],
+        in_generator: false,
+        errors: vec![],
+    };
+    for statement in statements {
+        resolver.statement(statement);
+    }
+    if resolver.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+struct Resolver {
+    /// The innermost scope is the last one.
+    /// For each binding we track if it can be re-assigned or not.
+    scopes: Vec<HashMap<String, bool>>,
+    /// `true` if the innermost enclosing function is a generator.
+    in_generator: bool,
+    errors: Vec<ResolverError>,
+}
+
+impl Resolver {
+    fn statement(&mut self, s: &Statement) {
+        match s {
+            Statement::Expression(ExpressionStatement(e)) | Statement::Print(PrintStatement(e)) => {
+                self.expression(e);
+            }
+            Statement::VariableDeclaration(VariableDeclarationStatement {
+                initializer,
+                identifier,
+                constant,
+            }) => {
+                if let Some(initializer) = initializer {
+                    self.expression(initializer);
+                }
+                self.declare(identifier, !constant);
+            }
+            Statement::FunctionDeclaration(FunctionDeclarationStatement {
+                name,
+                parameters,
+                rest,
+                body,
+                generator,
+            }) => {
+                self.declare(name, true);
+                let enclosing_in_generator = std::mem::replace(&mut self.in_generator, *generator);
+                self.scopes.push(HashMap::new());
+                for Parameter { name, default } in parameters {
+                    // Defaults are evaluated in the scope of the function, where
+                    // the preceding parameters have already been defined.
+                    if let Some(default) = default {
+                        self.expression(default);
+                    }
+                    self.declare(name, true);
+                }
+                if let Some(rest) = rest {
+                    self.declare(rest, true);
+                }
+                for statement in body {
+                    self.statement(statement);
+                }
+                self.scopes.pop();
+                self.in_generator = enclosing_in_generator;
+            }
+            Statement::Block(block) => self.block(block),
+            Statement::IfElse(IfElseStatement {
+                condition,
+                if_branch,
+                else_branch,
+            }) => {
+                self.expression(condition);
+                self.statement(if_branch);
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch);
+                }
+            }
+            Statement::While(WhileStatement { condition, body }) => {
+                self.expression(condition);
+                self.statement(body);
+            }
+            Statement::ForIn(ForInStatement {
+                variable,
+                iterable,
+                body,
+                ..
+            }) => {
+                self.expression(iterable);
+                // Each iteration gets its own scope, holding the loop variable.
+                self.scopes.push(HashMap::new());
+                self.declare(variable, true);
+                self.statement(body);
+                self.scopes.pop();
+            }
+            Statement::Return(ReturnStatement { value, .. })
+            | Statement::Throw(ThrowStatement { value, .. }) => {
+                self.expression(value);
+            }
+            Statement::Yield(YieldStatement { keyword, value }) => {
+                if !self.in_generator {
+                    self.errors.push(ResolverError::new(
+                        keyword.to_owned(),
+                        "Can only yield from a generator.",
+                    ));
+                }
+                self.expression(value);
+            }
+            Statement::Try(TryStatement {
+                body,
+                catch,
+                finally,
+            }) => {
+                self.block(body);
+                if let Some(CatchClause { variable, body }) = catch {
+                    self.scopes.push(HashMap::new());
+                    self.declare(variable, true);
+                    self.block(body);
+                    self.scopes.pop();
+                }
+                if let Some(finally) = finally {
+                    self.block(finally);
+                }
+            }
+            Statement::Import(ImportStatement { alias, .. }) => {
+                // Modules are bound as constants.
+                if let Some(alias) = alias {
+                    self.declare(alias, false);
+                }
+            }
+            Statement::Export(ExportStatement {
+                keyword,
+                declaration,
+            }) => {
+                if self.scopes.len() > 1 {
+                    self.errors.push(ResolverError::new(
+                        keyword.to_owned(),
+                        "Can only export top-level declarations.",
+                    ));
+                }
+                self.statement(declaration);
+            }
+            Statement::Test(TestStatement { keyword, body, .. }) => {
+                if self.scopes.len() > 1 {
+                    self.errors.push(ResolverError::new(
+                        keyword.to_owned(),
+                        "Can only declare tests at the top level.",
+                    ));
+                }
+                self.block(body);
+            }
+            Statement::Error(_) => {}
+        }
+    }
+
+    fn block(&mut self, BlockStatement { statements, .. }: &BlockStatement) {
+        self.scopes.push(HashMap::new());
+        for statement in statements {
+            self.statement(statement);
+        }
+        self.scopes.pop();
+    }
+
+    fn expression(&mut self, e: &Expression) {
+        match e {
+            Expression::Binary(b) => {
+                self.expression(&b.left);
+                self.expression(&b.right);
+            }
+            Expression::Unary(u) => self.expression(&u.operand),
+            Expression::Literal(_) | Expression::VariableReference(_) => {}
+            Expression::Grouping(g) => self.expression(&g.0),
+            Expression::VariableAssignment(VariableAssignmentExpression { identifier, value })
+            | Expression::CompoundAssignment(CompoundAssignmentExpression {
+                identifier,
+                value,
+                ..
+            }) => {
+                self.expression(value);
+                self.check_assignment(identifier);
+            }
+            Expression::Update(UpdateExpression { identifier, .. }) => {
+                self.check_assignment(identifier);
+            }
+            Expression::Conditional(ConditionalExpression {
+                condition,
+                then_branch,
+                else_branch,
+            }) => {
+                self.expression(condition);
+                self.expression(then_branch);
+                self.expression(else_branch);
+            }
+            Expression::Call(CallExpression {
+                callee, arguments, ..
+            }) => {
+                self.expression(callee);
+                for argument in arguments {
+                    self.expression(argument);
+                }
+            }
+            Expression::Get(GetExpression { object, .. }) => self.expression(object),
+            Expression::Error(ErrorExpression { children, .. }) => {
+                for child in children {
+                    self.expression(child);
+                }
+            }
+        }
+    }
+
+    fn declare(&mut self, identifier: &Token, mutable: bool) {
+        self.scopes
+            .last_mut()
+            .unwrap()
+            .insert(identifier.clone().lexeme(), mutable);
+    }
+
+    /// Report an error if `identifier` resolves to a constant.
+    fn check_assignment(&mut self, identifier: &Token) {
+        let name = identifier.clone().lexeme();
+        let mutable = self
+            .scopes
+            .iter()
+            .rev()
+            .find_map(|scope| scope.get(&name).copied());
+        // `None` means that we don't know where the binding is coming from - we'll
+        // have to wait until runtime to find out if the assignment is legal.
+        if mutable == Some(false) {
+            self.errors.push(ResolverError::new(
+                identifier.to_owned(),
+                "Cannot assign to a constant.",
+            ));
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("[line {}] Error at '{}': {msg}", .t.line(), .t.clone().lexeme())]
+pub struct ResolverError {
+    t: Token,
+    msg: String,
+}
+
+impl ResolverError {
+    fn new(t: Token, msg: impl Into<String>) -> Self {
+        Self { t, msg: msg.into() }
+    }
+
+    /// The token the error refers to.
+    pub(crate) fn token(&self) -> &Token {
+        &self.t
+    }
+
+    pub fn message(&self) -> &str {
+        &self.msg
+    }
+}
@@ -1,13 +1,69 @@
 use multipeek::{IteratorExt as _, MultiPeek};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fmt::Formatter;
+use std::ops::Range;
 use std::str::{Chars, FromStr};
 use strum_macros::EnumDiscriminants;
 
+const UNTERMINATED_STRING: &str = "Unterminated string";
+
+static KEYWORDS: [(&str, TokenType); 26] = [
+    ("and", TokenType::And),
+    ("as", TokenType::As),
+    ("catch", TokenType::Catch),
+    ("class", TokenType::Class),
+    ("const", TokenType::Const),
+    ("else", TokenType::Else),
+    ("export", TokenType::Export),
+    ("false", TokenType::False),
+    ("finally", TokenType::Finally),
+    ("for", TokenType::For),
+    ("fun", TokenType::Fun),
+    ("if", TokenType::If),
+    ("import", TokenType::Import),
+    ("in", TokenType::In),
+    ("nil", TokenType::Nil),
+    ("or", TokenType::Or),
+    ("print", TokenType::Print),
+    ("return", TokenType::Return),
+    ("super", TokenType::Super),
+    ("this", TokenType::This),
+    ("throw", TokenType::Throw),
+    ("true", TokenType::True),
+    ("try", TokenType::Try),
+    ("var", TokenType::Var),
+    ("while", TokenType::While),
+    ("yield", TokenType::Yield),
+];
+
+/// The reserved words of the language.
+pub fn keywords() -> impl Iterator<Item = &'static str> {
+    KEYWORDS.iter().map(|(keyword, _)| *keyword)
+}
+
+/// A change to a source code: the bytes in `range` are replaced by `text`.
+#[derive(Debug, Clone)]
+pub struct TextEdit {
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+impl TextEdit {
+    /// How many bytes the edit moves the code that follows it.
+    pub(crate) fn shift(&self) -> isize {
+        self.text.len() as isize - self.range.len() as isize
+    }
+}
+
 pub struct Scanner<'a> {
     source: MultiPeek<Chars<'a>>,
     current_token_buffer: Vec<char>,
+    /// The line of the next character to scan. Lines are numbered from 1, like
+    /// the `[line N]` messages of reference Lox and its test suite expectations.
     current_line: u64,
+    /// The byte offset of the next character to scan.
+    current_offset: usize,
     keywords: HashMap<String, TokenType>,
 }
 
@@ -21,32 +77,77 @@ impl<'a> std::iter::Iterator for Scanner<'a> {
 
 impl<'a> Scanner<'a> {
     pub fn new(source: &'a str) -> Self {
-        let keywords = HashMap::from_iter([
-            ("and".into(), TokenType::And),
-            ("class".into(), TokenType::Class),
-            ("else".into(), TokenType::Else),
-            ("false".into(), TokenType::False),
-            ("for".into(), TokenType::For),
-            ("fun".into(), TokenType::Fun),
-            ("if".into(), TokenType::If),
-            ("nil".into(), TokenType::Nil),
-            ("or".into(), TokenType::Or),
-            ("print".into(), TokenType::Print),
-            ("return".into(), TokenType::Return),
-            ("super".into(), TokenType::Super),
-            ("this".into(), TokenType::This),
-            ("true".into(), TokenType::True),
-            ("var".into(), TokenType::Var),
-            ("while".into(), TokenType::While),
-        ]);
+        let keywords = KEYWORDS
+            .iter()
+            .map(|(keyword, ty)| ((*keyword).to_owned(), ty.clone()))
+            .collect();
         Self {
             source: source.chars().multipeek(),
             current_token_buffer: Vec::new(),
-            current_line: 0,
+            current_line: 1,
+            current_offset: 0,
             keywords,
         }
     }
 
+    /// A scanner that starts at `offset`, the beginning of a token on `line`.
+    fn resume(source: &'a str, offset: usize, line: u64) -> Self {
+        Self {
+            source: source[offset..].chars().multipeek(),
+            current_line: line,
+            current_offset: offset,
+            ..Self::new("")
+        }
+    }
+
+    /// Update `tokens`, the tokens of a source code before `edit`, to match `source`,
+    /// the source code after `edit`.
+    /// Only the tokens around the edit are scanned again: the ones after them are kept and
+    /// moved. It returns the bytes of `source` that were scanned again.
+    pub fn rescan(tokens: &mut Vec<Token>, source: &str, edit: &TextEdit) -> Range<usize> {
+        // The scanner looks at most two characters past the end of a token: the two tokens
+        // before the first one that touches the edit might change too.
+        let first = tokens
+            .partition_point(|t| t.span().end < edit.range.start)
+            .saturating_sub(2);
+        let start = tokens.get(first).map(|t| t.offset).unwrap_or(0);
+        let line = first
+            .checked_sub(1)
+            .map(|previous| tokens[previous].line)
+            .unwrap_or(1);
+
+        let edit_end = edit.range.start + edit.text.len();
+        let mut rescanned = vec![];
+        // The first old token to keep, and how many lines it moves.
+        let mut kept = None;
+        let mut candidate = first;
+        for token in Scanner::resume(source, start, line) {
+            if token.offset >= edit_end {
+                // Once the scanner reaches the start of an old token after the edit, it
+                // would scan exactly the same tokens as before.
+                let old_offset = (token.offset as isize - edit.shift()) as usize;
+                while candidate < tokens.len() && tokens[candidate].offset < old_offset {
+                    candidate += 1;
+                }
+                if candidate < tokens.len() && tokens[candidate].offset == old_offset {
+                    let moved_lines = token.line as i64 - tokens[candidate].line as i64;
+                    kept = Some((candidate, moved_lines));
+                    break;
+                }
+            }
+            rescanned.push(token);
+        }
+        let (kept, moved_lines) = kept.unwrap_or((tokens.len(), 0));
+
+        let end = rescanned.last().map(|t| t.span().end).unwrap_or(start);
+        let count = rescanned.len();
+        tokens.splice(first..kept, rescanned);
+        for token in &mut tokens[first + count..] {
+            token.shift(edit.shift(), moved_lines);
+        }
+        start..end
+    }
+
     fn scan_token(&mut self) -> Option<Token> {
         let c = self.advance()?;
         let token = match c {
@@ -55,11 +156,43 @@ impl<'a> Scanner<'a> {
             '{' => self.finalize_current_token(TokenType::LeftBrace),
             '}' => self.finalize_current_token(TokenType::RightBrace),
             ',' => self.finalize_current_token(TokenType::Comma),
-            '.' => self.finalize_current_token(TokenType::Dot),
-            '-' => self.finalize_current_token(TokenType::Minus),
-            '+' => self.finalize_current_token(TokenType::Plus),
+            '.' => {
+                if self.peek() == Some(&'.') && self.peek_nth(1) == Some(&'.') {
+                    self.advance();
+                    self.advance();
+                    self.finalize_current_token(TokenType::Ellipsis)
+                } else {
+                    self.finalize_current_token(TokenType::Dot)
+                }
+            }
+            '-' => {
+                if self.advance_on_match('=') {
+                    self.finalize_current_token(TokenType::MinusEqual)
+                } else if self.advance_on_match('-') {
+                    self.finalize_current_token(TokenType::MinusMinus)
+                } else {
+                    self.finalize_current_token(TokenType::Minus)
+                }
+            }
+            '+' => {
+                if self.advance_on_match('=') {
+                    self.finalize_current_token(TokenType::PlusEqual)
+                } else if self.advance_on_match('+') {
+                    self.finalize_current_token(TokenType::PlusPlus)
+                } else {
+                    self.finalize_current_token(TokenType::Plus)
+                }
+            }
             ';' => self.finalize_current_token(TokenType::Semicolon),
-            '*' => self.finalize_current_token(TokenType::Star),
+            '?' => self.finalize_current_token(TokenType::Question),
+            ':' => self.finalize_current_token(TokenType::Colon),
+            '*' => {
+                if self.advance_on_match('=') {
+                    self.finalize_current_token(TokenType::StarEqual)
+                } else {
+                    self.finalize_current_token(TokenType::Star)
+                }
+            }
             '!' => {
                 if self.advance_on_match('=') {
                     self.finalize_current_token(TokenType::BangEqual)
@@ -90,11 +223,13 @@ impl<'a> Scanner<'a> {
             }
             '/' => {
                 if self.advance_on_match('/') {
-                    // Eat the entire comment, until we encounter a line break
+                    // Eat the entire comment, until we encounter a line break.
+                    // Comments are kept, as trivia, for the tools that need to preserve them
+                    // (e.g. the formatter).
                     self.advance_until('\n');
-                    // Empty the token buffer - we don't care about comments.
-                    self.current_token_buffer.clear();
-                    self.scan_token()?
+                    self.finalize_current_token(TokenType::Trivia)
+                } else if self.advance_on_match('=') {
+                    self.finalize_current_token(TokenType::SlashEqual)
                 } else {
                     self.finalize_current_token(TokenType::Slash)
                 }
@@ -102,17 +237,13 @@ impl<'a> Scanner<'a> {
             '"' => {
                 self.advance_until('"');
                 if self.is_at_end() {
-                    return Some(self.finalize_error_token(Some("Unterminated string")));
+                    return Some(self.finalize_error_token(Some(UNTERMINATED_STRING)));
                 }
                 // Eat the closing `"`
                 self.advance();
                 let lexeme = self.finalize_buffer_into_lexeme();
                 let literal = lexeme.trim_matches('"').to_string();
-                Token {
-                    ty: TokenType::String(literal),
-                    lexeme,
-                    line: self.current_line,
-                }
+                self.token(TokenType::String(literal), lexeme)
             }
             d if d.is_ascii_digit() => {
                 self.advance_while_true(|c| c.is_ascii_digit());
@@ -126,31 +257,30 @@ impl<'a> Scanner<'a> {
                     }
                 }
                 let lexeme = String::from_iter(self.current_token_buffer.drain(..));
-                match f64::from_str(&lexeme) {
-                    Ok(f) => Token {
-                        ty: TokenType::Number(f),
-                        lexeme,
-                        line: self.current_line,
-                    },
-                    Err(_) => self.finalize_error_token(Some("Failed to parse number")),
+                // Literals without a decimal point are integers, unless they are too large
+                // for 64 bits: those are approximated by floats.
+                let ty = if lexeme.contains('.') {
+                    f64::from_str(&lexeme).ok().map(TokenType::Number)
+                } else {
+                    i64::from_str(&lexeme)
+                        .map(TokenType::Integer)
+                        .or_else(|_| f64::from_str(&lexeme).map(TokenType::Number))
+                        .ok()
+                };
+                match ty {
+                    Some(ty) => self.token(ty, lexeme),
+                    None => self.finalize_error_token(Some("Failed to parse number")),
                 }
             }
             c => {
                 if Self::is_alpha(&c) {
                     self.advance_while_true(|c| Self::is_alpha(c) || c.is_ascii_digit());
                     let lexeme = self.finalize_buffer_into_lexeme();
-                    match self.keywords.get(&lexeme) {
-                        None => Token {
-                            ty: TokenType::Identifier,
-                            lexeme,
-                            line: self.current_line,
-                        },
-                        Some(ty) => Token {
-                            ty: ty.clone(),
-                            lexeme,
-                            line: self.current_line,
-                        },
-                    }
+                    let ty = match self.keywords.get(&lexeme) {
+                        None => TokenType::Identifier,
+                        Some(ty) => ty.clone(),
+                    };
+                    self.token(ty, lexeme)
                 } else if Self::is_trivia(&c) {
                     self.advance_while_true(Self::is_trivia);
                     self.finalize_current_token(TokenType::Trivia)
@@ -172,8 +302,14 @@ impl<'a> Scanner<'a> {
 
     fn finalize_current_token(&mut self, ty: TokenType) -> Token {
         let lexeme = self.finalize_buffer_into_lexeme();
+        self.token(ty, lexeme)
+    }
+
+    /// A token that ends at the current position.
+    fn token(&self, ty: TokenType, lexeme: String) -> Token {
         Token {
             ty,
+            offset: self.current_offset - lexeme.len(),
             lexeme,
             line: self.current_line,
         }
@@ -189,6 +325,7 @@ impl<'a> Scanner<'a> {
         if char == '\n' {
             self.current_line += 1;
         }
+        self.current_offset += char.len_utf8();
         self.current_token_buffer.push(char);
         Some(char)
     }
@@ -243,11 +380,13 @@ impl<'a> Scanner<'a> {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(into = "TokenJson", try_from = "TokenJson")]
 pub struct Token {
     ty: TokenType,
     lexeme: String,
     line: u64,
+    offset: usize,
 }
 
 impl Token {
@@ -255,6 +394,21 @@ impl Token {
         &self.ty
     }
 
+    /// Whether the token is a `//` comment.
+    pub fn is_comment(&self) -> bool {
+        self.ty == TokenType::Trivia && self.lexeme.starts_with("//")
+    }
+
+    /// Whether the token is a string literal that is missing its closing quote.
+    pub fn is_unterminated_string(&self) -> bool {
+        matches!(
+            self.ty,
+            TokenType::SyntaxError {
+                error_msg: Some(UNTERMINATED_STRING)
+            }
+        )
+    }
+
     pub fn discriminant(&self) -> TokenDiscriminant {
         (&self.ty).into()
     }
@@ -262,6 +416,88 @@ impl Token {
     pub fn lexeme(self) -> String {
         self.lexeme
     }
+
+    /// The (1-based) line where the token ends.
+    pub fn line(&self) -> u64 {
+        self.line
+    }
+
+    /// The range of bytes of the source code covered by the token.
+    pub fn span(&self) -> Range<usize> {
+        self.offset..self.offset + self.lexeme.len()
+    }
+
+    /// Move the token after an edit of the source code before it.
+    pub(crate) fn shift(&mut self, bytes: isize, lines: i64) {
+        self.offset = (self.offset as isize + bytes) as usize;
+        self.line = (self.line as i64 + lines) as u64;
+    }
+}
+
+/// How a token is represented in JSON, e.g.
+/// `{"kind": "Integer", "lexeme": "1", "line": 1, "span": {"start": 6, "end": 7}, "value": 1}`.
+///
+/// The type of the token, and its value if it is a literal, are derived from its lexeme:
+/// `value` is only exported for the convenience of other tools, and ignored when importing.
+#[derive(Serialize, Deserialize)]
+struct TokenJson {
+    /// The name of the [`TokenDiscriminant`].
+    kind: String,
+    lexeme: String,
+    line: u64,
+    span: Range<usize>,
+    #[serde(skip_deserializing, skip_serializing_if = "Option::is_none")]
+    value: Option<serde_json::Value>,
+}
+
+impl From<Token> for TokenJson {
+    fn from(token: Token) -> Self {
+        let value = match &token.ty {
+            TokenType::String(s) => Some(s.clone().into()),
+            TokenType::Number(n) => Some((*n).into()),
+            TokenType::Integer(n) => Some((*n).into()),
+            _ => None,
+        };
+        Self {
+            kind: format!("{:?}", token.discriminant()),
+            span: token.span(),
+            line: token.line,
+            lexeme: token.lexeme,
+            value,
+        }
+    }
+}
+
+impl TryFrom<TokenJson> for Token {
+    type Error = String;
+
+    fn try_from(json: TokenJson) -> Result<Self, Self::Error> {
+        let mut scanner = Scanner::new(&json.lexeme);
+        let ty = match (scanner.next(), scanner.next()) {
+            (Some(token), None) if token.lexeme == json.lexeme => token.ty,
+            _ => return Err(format!("`{}` is not a single token", json.lexeme)),
+        };
+        let token = Token {
+            ty,
+            lexeme: json.lexeme,
+            line: json.line,
+            offset: json.span.start,
+        };
+        let kind = format!("{:?}", token.discriminant());
+        if kind != json.kind {
+            return Err(format!(
+                "`{}` is a token of kind {kind}, not {}",
+                token.lexeme, json.kind
+            ));
+        }
+        if token.span() != json.span {
+            return Err(format!(
+                "The span of `{}` does not match its length",
+                token.lexeme
+            ));
+        }
+        Ok(token)
+    }
 }
 
 impl std::fmt::Display for Token {
@@ -280,6 +516,9 @@ impl std::fmt::Display for Token {
             TokenType::Number(n) => {
                 write!(f, " {}", n)?;
             }
+            TokenType::Integer(n) => {
+                write!(f, " {}", n)?;
+            }
             _ => {}
         }
         Ok(())
@@ -301,39 +540,59 @@ pub enum TokenType {
     Semicolon,
     Slash,
     Star,
+    Question,
+    Colon,
 
-    // One or two character tokens
+    // One, two or three character tokens
     Bang,
     BangEqual,
+    Ellipsis,
     Equal,
     EqualEqual,
     Greater,
     GreaterEqual,
     Less,
     LessEqual,
+    MinusEqual,
+    MinusMinus,
+    PlusEqual,
+    PlusPlus,
+    SlashEqual,
+    StarEqual,
 
     // Literals
     Identifier,
     String(String),
     Number(f64),
+    Integer(i64),
     False,
     True,
 
     // Keywords
     And,
+    As,
+    Catch,
     Class,
+    Const,
     Else,
+    Export,
+    Finally,
     Fun,
     For,
     If,
+    Import,
+    In,
     Nil,
     Or,
     Print,
     Return,
     Super,
     This,
+    Throw,
+    Try,
     Var,
     While,
+    Yield,
 
     // Tokens that do not affect the functional meaning of
     // the scanned code - whitespace, new lines, comments, etc.
@@ -363,12 +622,20 @@ impl TokenType {
             None
         }
     }
+
+    pub fn integer(self) -> Option<i64> {
+        if let Self::Integer(n) = self {
+            Some(n)
+        } else {
+            None
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::scanner::{Scanner, Token};
-    use insta::assert_display_snapshot;
+    use insta::assert_snapshot;
     use std::fmt::Formatter;
 
     /// Short-hand to convert source code into a vec of tokens.
@@ -401,19 +668,36 @@ mod tests {
     #[test]
     fn an_empty_source_translates_into_an_empty_iterator() {
         let tokens = scan("");
-        assert_display_snapshot!(tokens, @"[]")
+        assert_snapshot!(tokens, @"[]")
+    }
+
+    #[test]
+    fn comments_are_trivia() {
+        let tokens = scan("a; // a comment\nb;");
+        assert_snapshot!(tokens, @r###"
+        [
+        	L1 - Identifier a,
+        	L1 - Semicolon ;,
+        	L1 - Trivia  ,
+        	L1 - Trivia // a comment,
+        	L2 - Trivia 
+        ,
+        	L2 - Identifier b,
+        	L2 - Semicolon ;,
+        ]
+        "###);
     }
 
     #[test]
     fn scan_a_string() {
         let tokens = scan(r#"s = "My name is Luça""#);
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier s,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L0 - String "My name is Luça" My name is Luça,
+        	L1 - Identifier s,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L1 - String "My name is Luça" My name is Luça,
         ]
         "###)
     }
@@ -421,13 +705,13 @@ mod tests {
     #[test]
     fn scan_a_float_number() {
         let tokens = scan(r#"x = 12.3"#);
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier x,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L0 - Number 12.3 12.3,
+        	L1 - Identifier x,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L1 - Number 12.3 12.3,
         ]
         "###)
     }
@@ -435,13 +719,27 @@ mod tests {
     #[test]
     fn scan_an_integer_number() {
         let tokens = scan(r#"x = 12"#);
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
+        [
+        	L1 - Identifier x,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L1 - Integer 12 12,
+        ]
+        "###)
+    }
+
+    #[test]
+    fn integer_literals_too_large_for_64_bits_are_floats() {
+        let tokens = scan(r#"x = 9223372036854775808"#);
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier x,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L0 - Number 12 12,
+        	L1 - Identifier x,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L1 - Number 9223372036854775808 9223372036854776000,
         ]
         "###)
     }
@@ -452,20 +750,20 @@ mod tests {
             r#"x = 12
             y = "My kid is 12""#,
         );
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier x,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L0 - Number 12 12,
-        	L1 - Trivia 
-                    ,
-        	L1 - Identifier y,
+        	L1 - Identifier x,
         	L1 - Trivia  ,
         	L1 - Equal =,
         	L1 - Trivia  ,
-        	L1 - String "My kid is 12" My kid is 12,
+        	L1 - Integer 12 12,
+        	L2 - Trivia 
+                    ,
+        	L2 - Identifier y,
+        	L2 - Trivia  ,
+        	L2 - Equal =,
+        	L2 - Trivia  ,
+        	L2 - String "My kid is 12" My kid is 12,
         ]
         "###)
     }
@@ -476,13 +774,13 @@ mod tests {
             r#"x = "My kid is 12.
             How old is yours?""#,
         );
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier x,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L1 - String "My kid is 12.
+        	L1 - Identifier x,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L2 - String "My kid is 12.
                     How old is yours?" My kid is 12.
                     How old is yours?,
         ]
@@ -492,14 +790,40 @@ mod tests {
     #[test]
     fn syntax_error() {
         let tokens = scan(r#"x = "Missing quote, ops"#);
-        assert_display_snapshot!(tokens, @r###"
+        assert_snapshot!(tokens, @r###"
         [
-        	L0 - Identifier x,
-        	L0 - Trivia  ,
-        	L0 - Equal =,
-        	L0 - Trivia  ,
-        	L0 - SyntaxError "Missing quote, ops,
+        	L1 - Identifier x,
+        	L1 - Trivia  ,
+        	L1 - Equal =,
+        	L1 - Trivia  ,
+        	L1 - SyntaxError "Missing quote, ops,
         ]
         "###)
     }
+
+    #[test]
+    fn scan_compound_operators() {
+        let tokens = scan(r#"a+=1-=--b++*=/="#);
+        assert_snapshot!(tokens, @r###"
+        [
+        	L1 - Identifier a,
+        	L1 - PlusEqual +=,
+        	L1 - Integer 1 1,
+        	L1 - MinusEqual -=,
+        	L1 - MinusMinus --,
+        	L1 - Identifier b,
+        	L1 - PlusPlus ++,
+        	L1 - StarEqual *=,
+        	L1 - SlashEqual /=,
+        ]
+        "###)
+    }
+
+    #[test]
+    fn tokens_know_their_span() {
+        let source = "var é = \"ü\";\n// comment";
+        for token in Scanner::new(source) {
+            assert_eq!(&source[token.span()], token.clone().lexeme());
+        }
+    }
 }
@@ -0,0 +1,75 @@
+/// What a conformance test expects from the interpreter, as specified by the annotations
+/// in its comments.
+///
+/// The annotations follow the conventions of the upstream test runner (`tool/bin/test.dart`
+/// in the Crafting Interpreters repository):
+///
+/// - `// expect: <output>`: a line printed to standard output;
+/// - `// expect runtime error: <message>`: a runtime error, raised at the annotated line;
+/// - `// Error at '<lexeme>': <message>`: a compile error at the annotated line;
+/// - `// [line <n>] Error ...`: a compile error at line `n`, for errors that can't be
+///   annotated on the line they occur at (e.g. at the end of the file). `[java line <n>]`
+///   annotations are specific to jlox and apply to us as well, `[c line <n>]` annotations
+///   are specific to clox and are ignored.
+#[derive(Debug, Default, PartialEq)]
+pub struct Expectations {
+    pub output: Vec<String>,
+    /// Formatted as `[line <n>] Error...`.
+    pub compile_errors: Vec<String>,
+    pub runtime_error: Option<RuntimeErrorExpectation>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct RuntimeErrorExpectation {
+    pub line: u64,
+    pub message: String,
+}
+
+impl Expectations {
+    /// `None` if the file is marked with `// nontest`, i.e. it is not meant to be executed
+    /// on its own.
+    pub fn parse(source: &str) -> Option<Self> {
+        let mut expectations = Self::default();
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i as u64 + 1;
+            let Some((_, comment)) = line.split_once("// ") else {
+                continue;
+            };
+            if comment.starts_with("nontest") {
+                return None;
+            }
+            if let Some(output) = comment.strip_prefix("expect:") {
+                let output = output.strip_prefix(' ').unwrap_or(output);
+                expectations.output.push(output.to_owned());
+            } else if let Some(message) = comment.strip_prefix("expect runtime error: ") {
+                expectations.runtime_error = Some(RuntimeErrorExpectation {
+                    line: line_number,
+                    message: message.to_owned(),
+                });
+            } else if comment.starts_with("Error") {
+                let error = format!("[line {line_number}] {comment}");
+                expectations.compile_errors.push(error);
+            } else if let Some(error) = parse_error_with_line(comment) {
+                expectations.compile_errors.push(error);
+            }
+        }
+        Some(expectations)
+    }
+
+    pub fn expects_error(&self) -> bool {
+        !self.compile_errors.is_empty() || self.runtime_error.is_some()
+    }
+}
+
+/// Parse `[line <n>] Error...` and `[java line <n>] Error...`, normalizing them to the
+/// former.
+fn parse_error_with_line(comment: &str) -> Option<String> {
+    let rest = comment.strip_prefix('[')?;
+    let rest = rest.strip_prefix("java ").unwrap_or(rest);
+    let rest = rest.strip_prefix("line ")?;
+    let (line, error) = rest.split_once("] ")?;
+    let line: u64 = line.parse().ok()?;
+    error
+        .starts_with("Error")
+        .then(|| format!("[line {line}] {error}"))
+}
@@ -0,0 +1,127 @@
+use crate::expectations::Expectations;
+use crate::runner::{check, run};
+use insta::assert_debug_snapshot;
+use std::path::Path;
+
+#[test]
+fn parse_all_annotations() {
+    let source = r#"print 1; // expect: 1
+print ""; // expect:
+var a = "a" + 1; // expect runtime error: Operands must be two numbers or two strings.
+var = 1; // Error at '=': Expect variable name.
+// [line 7] Error at end: Expect ';' after value.
+// [java line 8] Error at 'b': Java only.
+// [c line 9] Error at 'c': C only.
+print 1"#;
+    let expectations = Expectations::parse(source);
+    assert_debug_snapshot!(expectations, @r###"
+    Some(
+        Expectations {
+            output: [
+                "1",
+                "",
+            ],
+            compile_errors: [
+                "[line 4] Error at '=': Expect variable name.",
+                "[line 7] Error at end: Expect ';' after value.",
+                "[line 8] Error at 'b': Java only.",
+            ],
+            runtime_error: Some(
+                RuntimeErrorExpectation {
+                    line: 3,
+                    message: "Operands must be two numbers or two strings.",
+                },
+            ),
+        },
+    )
+    "###);
+}
+
+#[test]
+fn nontest_files_are_not_executed() {
+    let source = "// nontest\nprint 1; // expect: 1";
+    assert!(Expectations::parse(source).is_none());
+}
+
+fn run_and_check(source: &str) -> Result<(), Vec<String>> {
+    let expectations = Expectations::parse(source).unwrap();
+    check(&expectations, &run(source, Path::new(".")))
+}
+
+#[test]
+fn matching_output_passes() {
+    let source = r#"print 1 + 2; // expect: 3
+print "a" != "b"; // expect: true"#;
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Ok(
+        (),
+    )
+    "###);
+}
+
+#[test]
+fn output_before_a_runtime_error_is_checked() {
+    let source = r#"print "before"; // expect: before
+print -"a"; // expect runtime error: Operand must be a number."#;
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Ok(
+        (),
+    )
+    "###);
+}
+
+#[test]
+fn runtime_errors_must_be_raised_at_the_annotated_line() {
+    let source = r#"print -"a";
+// expect runtime error: Operand must be a number."#;
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Err(
+        [
+            "Unexpected error: Runtime { line: Some(1), message: \"Operand must be a number.\" }",
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn compile_errors_are_compared_with_the_resolver_diagnostics() {
+    let source = r#"const a = 1;
+a = 2; // Error at 'a': Cannot assign to a constant."#;
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Ok(
+        (),
+    )
+    "###);
+}
+
+#[test]
+fn syntax_errors_are_compared_by_location() {
+    let source = r#"var a = ; // Error at ';': Expect expression.
+print "unterminated
+// [line 3] Error: Unterminated string."#;
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Ok(
+        (),
+    )
+    "###);
+    let source = "print (1; // Error at 'print': Expect expression.";
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Err(
+        [
+            "Unexpected error: Syntax([\"[line 1] Error at ';': Unexpected `;`.\"])",
+        ],
+    )
+    "###);
+}
+
+#[test]
+fn mismatched_output_fails() {
+    let source = "print 1; // expect: 2";
+    assert_debug_snapshot!(run_and_check(source), @r###"
+    Err(
+        [
+            "Expected output [\"2\"], got [\"1\"]",
+        ],
+    )
+    "###);
+}
@@ -0,0 +1,151 @@
+//! Run the test suite of the reference Lox implementation, from
+//! [Crafting Interpreters](https://github.com/munificent/craftinginterpreters).
+//!
+//! The upstream `test/` directory goes in `tests/conformance/suite`: each `.lox` file is
+//! executed and its output and errors are compared against the annotations in its comments
+//! (see [`expectations::Expectations`]).
+mod expectations;
+mod harness;
+mod runner;
+
+use expectations::Expectations;
+use std::path::{Path, PathBuf};
+
+/// Upstream directories (roughly one per chapter of the book) that we don't run,
+/// with the reason why.
+const SKIPPED_DIRECTORIES: &[(&str, &str)] = &[
+    (
+        "benchmark",
+        "Performance benchmarks, they have no expectations",
+    ),
+    ("limit", "Limits of the bytecode virtual machine (clox)"),
+    ("scanning", "Token dumps from the 'Scanning' chapter"),
+    (
+        "expressions",
+        "AST dumps from the 'Parsing Expressions' chapter",
+    ),
+    ("class", "Classes are not implemented"),
+    ("constructor", "Classes are not implemented"),
+    ("field", "Classes are not implemented"),
+    ("inheritance", "Classes are not implemented"),
+    ("method", "Classes are not implemented"),
+    ("super", "Classes are not implemented"),
+    ("this", "Classes are not implemented"),
+];
+
+/// Individual upstream files that we don't run, relative to the suite directory
+/// (e.g. `"operator/equals_class.lox"`), with the reason why.
+const SKIPPED_FILES: &[(&str, &str)] = &[
+    ("assignment/to_this.lox", "Classes are not implemented"),
+    ("call/object.lox", "Classes are not implemented"),
+    (
+        "closure/close_over_method_parameter.lox",
+        "Classes are not implemented",
+    ),
+    ("operator/equals_class.lox", "Classes are not implemented"),
+    ("operator/equals_method.lox", "Classes are not implemented"),
+    ("operator/not_class.lox", "Classes are not implemented"),
+    ("regression/394.lox", "Classes are not implemented"),
+    ("return/in_method.lox", "Classes are not implemented"),
+    (
+        "variable/local_from_method.lox",
+        "Classes are not implemented",
+    ),
+    ("function/print.lox", "There is no `clock` native function"),
+    (
+        "function/too_many_arguments.lox",
+        "The 255 arguments limit prints a warning instead of an error",
+    ),
+    (
+        "function/too_many_parameters.lox",
+        "The 255 parameters limit prints a warning instead of an error",
+    ),
+    ("number/literals.lox", "`-0` is an integer, printed as `0`"),
+    ("operator/negate.lox", "`--` is the decrement operator"),
+    (
+        "return/at_top_level.lox",
+        "The resolver does not reject `return` outside of functions",
+    ),
+    (
+        "variable/collide_with_parameter.lox",
+        "The resolver does not reject duplicate local variables",
+    ),
+    (
+        "variable/duplicate_local.lox",
+        "The resolver does not reject duplicate local variables",
+    ),
+    (
+        "variable/duplicate_parameter.lox",
+        "The resolver does not reject duplicate parameters",
+    ),
+    (
+        "variable/use_local_in_initializer.lox",
+        "The resolver does not reject reading a local variable in its own initializer",
+    ),
+    (
+        "unexpected_character.lox",
+        "The parser skips the rest of the statement after an invalid character",
+    ),
+];
+
+#[test]
+fn upstream_suite() {
+    let suite = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/conformance/suite");
+    let mut files = vec![];
+    collect_lox_files(&suite, &mut files);
+    files.sort();
+    if files.is_empty() {
+        // The suite is not vendored, see `suite/README.md` to fetch it.
+        eprintln!(
+            "Skipping the conformance suite: no `.lox` file in {}",
+            suite.display()
+        );
+        return;
+    }
+
+    let mut n_executed = 0;
+    let mut failures = vec![];
+    for file in files {
+        let relative_path = file.strip_prefix(&suite).unwrap();
+        let name = relative_path.to_string_lossy().replace('\\', "/");
+        if is_skipped(&name) {
+            continue;
+        }
+        let source = std::fs::read_to_string(&file).unwrap();
+        let Some(expectations) = Expectations::parse(&source) else {
+            continue;
+        };
+        n_executed += 1;
+        let outcome = runner::run(&source, file.parent().unwrap());
+        if let Err(mismatches) = runner::check(&expectations, &outcome) {
+            failures.push(format!("{name}:\n  {}", mismatches.join("\n  ")));
+        }
+    }
+    assert!(n_executed > 0, "Every file of the suite is skipped");
+    assert!(
+        failures.is_empty(),
+        "{} out of {n_executed} conformance tests failed.\n\n{}",
+        failures.len(),
+        failures.join("\n\n")
+    );
+}
+
+fn is_skipped(name: &str) -> bool {
+    let directory = name.split('/').next().unwrap_or_default();
+    SKIPPED_DIRECTORIES.iter().any(|(d, _)| *d == directory)
+        || SKIPPED_FILES.iter().any(|(f, _)| *f == name)
+}
+
+fn collect_lox_files(directory: &Path, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(directory) else {
+        return;
+    };
+    for entry in entries {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            collect_lox_files(&path, files);
+        } else if path.extension().map(|e| e == "lox").unwrap_or(false) {
+            files.push(path);
+        }
+    }
+}
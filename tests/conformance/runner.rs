@@ -0,0 +1,99 @@
+use crate::expectations::Expectations;
+use jlox::{Environment, ExecuteRawError, FileSystemLoader, Interpreter};
+use std::cell::RefCell;
+use std::path::Path;
+use std::rc::Rc;
+
+/// What happened when executing a conformance test.
+#[derive(Debug)]
+pub struct Outcome {
+    pub output: Vec<String>,
+    pub error: Option<Failure>,
+}
+
+#[derive(Debug)]
+pub enum Failure {
+    /// Errors reported by the parser, formatted as `[line <n>] Error...`.
+    Syntax(Vec<String>),
+    /// Errors reported by the resolver, formatted as `[line <n>] Error...`.
+    Compile(Vec<String>),
+    Runtime {
+        line: Option<u64>,
+        message: String,
+    },
+}
+
+/// Execute `source`, resolving imports relative to `directory`.
+pub fn run(source: &str, directory: &Path) -> Outcome {
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let loader = FileSystemLoader::new(directory);
+    let result = Interpreter::new(&mut buffer, environment, loader).execute_raw(source);
+    let error = result.err().map(|e| match e {
+        ExecuteRawError::ParserError(errors) => {
+            Failure::Syntax(errors.iter().map(|e| e.to_string()).collect())
+        }
+        ExecuteRawError::ResolverError(errors) => {
+            Failure::Compile(errors.iter().map(|e| e.to_string()).collect())
+        }
+        ExecuteRawError::RuntimeError(e) => Failure::Runtime {
+            line: e.line(),
+            message: e.message().to_owned(),
+        },
+    });
+    // Output printed before an error must match the expectations as well.
+    let output = String::from_utf8(buffer).unwrap();
+    Outcome {
+        output: output.lines().map(ToOwned::to_owned).collect(),
+        error,
+    }
+}
+
+/// Compare an outcome against the expectations of the test, describing the mismatches
+/// (if any).
+pub fn check(expectations: &Expectations, outcome: &Outcome) -> Result<(), Vec<String>> {
+    let mut mismatches = vec![];
+    if expectations.output != outcome.output {
+        mismatches.push(format!(
+            "Expected output {:?}, got {:?}",
+            expectations.output, outcome.output
+        ));
+    }
+    match &outcome.error {
+        None if expectations.expects_error() => {
+            mismatches.push("Expected an error, but the program succeeded".into())
+        }
+        None => {}
+        // Our parser words its messages differently from the reference implementation
+        // (e.g. "Unexpected `;`." instead of "Expect expression."): only the locations of
+        // the errors are compared.
+        Some(Failure::Syntax(errors))
+            if locations(errors) == locations(&expectations.compile_errors) => {}
+        Some(Failure::Compile(errors)) if errors == &expectations.compile_errors => {}
+        Some(Failure::Runtime { line, message })
+            if expectations
+                .runtime_error
+                .as_ref()
+                .map(|e| Some(e.line) == *line && &e.message == message)
+                .unwrap_or(false) => {}
+        Some(failure) => mismatches.push(format!("Unexpected error: {failure:?}")),
+    }
+    if mismatches.is_empty() {
+        Ok(())
+    } else {
+        Err(mismatches)
+    }
+}
+
+/// `[line <n>] Error at '<lexeme>'` (or `[line <n>] Error` for the errors of the scanner)
+/// for each error, without its message.
+fn locations(errors: &[String]) -> Vec<&str> {
+    errors
+        .iter()
+        .map(|e| {
+            e.split_once(": ")
+                .map(|(location, _)| location)
+                .unwrap_or(e)
+        })
+        .collect()
+}
@@ -0,0 +1,86 @@
+use crate::helpers::{execute, try_execute};
+use insta::assert_snapshot;
+
+#[test]
+fn compound_assignment_works() {
+    let source = r#"var a = 10;
+a += 5;
+print a;
+a -= 3;
+print a;
+a *= 2;
+print a;
+a /= 4;
+print a;
+var s = "Hello";
+s += ", world!";
+print s;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    15
+    12
+    24
+    6
+    Hello, world!
+    "###);
+}
+
+#[test]
+fn compound_assignment_evaluates_to_the_assigned_value() {
+    let source = r#"var a = 1;
+var b = a += 2;
+print a;
+print b;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    3
+    3
+    "###);
+}
+
+#[test]
+fn prefix_and_postfix_updates_work() {
+    let source = r#"var i = 0;
+print i++;
+print i;
+print ++i;
+print i--;
+print --i;
+print i;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    2
+    2
+    0
+    0
+    "###);
+}
+
+#[test]
+fn updates_can_drive_a_for_loop() {
+    let source = r#"for (var i = 0; i < 3; i++) {
+    print i;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    2
+    "###);
+}
+
+#[test]
+fn incrementing_a_string_is_an_error() {
+    let source = r#"var s = "a";
+s++;"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Operand of `++` and `--` must be a number");
+}
+
+#[test]
+fn compound_assignment_to_an_undefined_variable_is_an_error() {
+    let error = try_execute("a += 1;").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Undefined variable 'a'.");
+}
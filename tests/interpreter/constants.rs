@@ -0,0 +1,87 @@
+use crate::helpers::{execute, try_execute};
+use insta::assert_snapshot;
+use jlox::{Environment, ExecuteRawError, InMemoryLoader, Interpreter};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn constants_can_be_read() {
+    let source = r#"const greeting = "Hello";
+{
+    const greeting = "Hi";
+    print greeting;
+}
+print greeting;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    Hi
+    Hello
+    "###);
+}
+
+#[test]
+fn assigning_to_a_constant_is_a_static_error() {
+    let source = r#"const a = 1;
+print "unreachable";
+{
+    a = 2;
+    a += 3;
+    a++;
+}"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @r###"
+    [line 4] Error at 'a': Cannot assign to a constant.
+    [line 5] Error at 'a': Cannot assign to a constant.
+    [line 6] Error at 'a': Cannot assign to a constant.
+    "###);
+}
+
+#[test]
+fn shadowing_a_constant_with_a_variable_is_allowed() {
+    let source = r#"const a = 1;
+{
+    var a = 2;
+    a = 3;
+    print a;
+}
+print a;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    3
+    1
+    "###);
+}
+
+#[test]
+fn constants_must_be_initialized() {
+    assert!(try_execute("const a;").is_err());
+}
+
+#[test]
+fn assignments_to_constants_inside_functions_are_static_errors() {
+    let source = r#"const x = 1;
+fun f() {
+    x = 2;
+}"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"[line 3] Error at 'x': Cannot assign to a constant.");
+}
+
+#[test]
+fn constants_can_be_redefined_across_executions() {
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let mut interpreter = Interpreter::new(&mut buffer, environment, InMemoryLoader::new());
+    interpreter.execute_raw("const a = 1;").unwrap();
+    let error = interpreter.execute_raw("\na = 2;").unwrap_err();
+    interpreter
+        .execute_raw("var a = 3; a = 4; print a;")
+        .unwrap();
+    drop(interpreter);
+    let ExecuteRawError::RuntimeError(error) = error else {
+        panic!("Assigning to a constant should fail at runtime");
+    };
+    assert_eq!(error.line(), Some(2));
+    assert_snapshot!(error, @"An error occurred at runtime. Cannot assign to a, it was declared as a constant");
+    assert_snapshot!(String::from_utf8(buffer).unwrap(), @"4");
+}
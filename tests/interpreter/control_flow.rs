@@ -1,5 +1,5 @@
 use crate::helpers::execute;
-use insta::assert_display_snapshot;
+use insta::assert_snapshot;
 
 #[test]
 fn two_branch_conditional_works() {
@@ -9,7 +9,7 @@ fn two_branch_conditional_works() {
     print false;
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     false
     "###);
 }
@@ -20,7 +20,7 @@ fn single_branch_conditional_works() {
     print true;
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     true
     "###);
 }
@@ -35,7 +35,7 @@ fn ambiguous_if_else_is_execute_correctly() {
     else
         print "else";"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     "###);
 }
 
@@ -47,7 +47,7 @@ while (i < 2) {
     i = i + 1;    
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     0
     1
     "###);
@@ -59,8 +59,38 @@ fn for_works() {
     print i;
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     0
     1
     "###);
 }
+
+#[test]
+fn conditional_expression_works() {
+    let source = r#"var a = 3 > 5 ? "bigger" : "smaller";
+print a;
+print true ? 1 : false ? 2 : 3;
+print false ? 1 : false ? 2 : 3;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    smaller
+    1
+    3
+    "###);
+}
+
+#[test]
+fn conditional_expression_only_evaluates_the_chosen_branch() {
+    let source = r#"var calls = 0;
+fun touch(v) {
+    calls += 1;
+    return v;
+}
+print true ? touch("then") : touch("else");
+print calls;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    then
+    1
+    "###);
+}
@@ -0,0 +1,155 @@
+use crate::helpers::{execute, execute_until_error, try_execute};
+use insta::assert_snapshot;
+use jlox::ExecuteRawError;
+
+#[test]
+fn thrown_values_can_be_caught() {
+    let source = r#"try {
+    print "before";
+    throw "oops";
+    print "unreachable";
+} catch (e) {
+    print e;
+}
+print "after";"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    before
+    oops
+    after
+    "###);
+}
+
+#[test]
+fn exceptions_unwind_through_function_calls() {
+    let source = r#"fun fail(n) {
+    if (n < 1) {
+        throw n;
+    }
+    fail(n - 1);
+}
+try {
+    fail(3);
+} catch (e) {
+    print e;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @"0");
+}
+
+#[test]
+fn runtime_errors_are_caught_as_error_values() {
+    let source = r#"try {
+    print -"a";
+} catch (e) {
+    print e;
+    print e.message;
+    print e.line;
+}
+try {
+    print undefined;
+} catch (e) {
+    print e.message;
+    print e.line;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    <error: Operand must be a number.>
+    Operand must be a number.
+    2
+    Undefined variable 'undefined'.
+    9
+    "###);
+}
+
+#[test]
+fn arity_mismatches_can_be_caught() {
+    let source = r#"fun f(a) {}
+try {
+    f(1, 2);
+} catch (e) {
+    print e.message;
+    print e.line;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    Expected 1 arguments but got 2.
+    3
+    "###);
+}
+
+#[test]
+fn finally_always_runs() {
+    let source = r#"fun f() {
+    try {
+        return "try";
+    } finally {
+        print "finally";
+    }
+}
+print f();
+try {
+    try {
+        throw "inner";
+    } finally {
+        print "inner finally";
+    }
+} catch (e) {
+    print e;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    finally
+    try
+    inner finally
+    inner
+    "###);
+}
+
+#[test]
+fn errors_can_be_rethrown() {
+    let source = r#"try {
+    try {
+        true();
+    } catch (e) {
+        throw e;
+    }
+} catch (e) {
+    print e.message;
+}"#;
+    let output = execute(source);
+    assert_snapshot!(output, @"Can only call functions and classes.");
+}
+
+#[test]
+fn uncaught_exceptions_are_runtime_errors() {
+    let error = try_execute(r#"throw "oops";"#).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Uncaught exception: oops");
+}
+
+#[test]
+fn exit_cannot_be_caught_but_runs_finally_blocks() {
+    let source = r#"try {
+    exit(3);
+} catch (e) {
+    print "caught";
+} finally {
+    print "finally";
+}
+print "unreachable";"#;
+    let (output, ExecuteRawError::RuntimeError(error)) = execute_until_error(source) else {
+        panic!("`exit` should stop the program");
+    };
+    assert_eq!(error.exit_code(), Some(3));
+    assert_snapshot!(output, @"finally");
+}
+
+#[test]
+fn exit_defaults_to_a_successful_status() {
+    let Err(ExecuteRawError::RuntimeError(error)) = try_execute("exit();") else {
+        panic!("`exit` should stop the program");
+    };
+    assert_eq!(error.exit_code(), Some(0));
+    let error = try_execute("exit(1.5);").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. The exit status must be an integer, found `1.5`.");
+}
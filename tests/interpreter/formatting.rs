@@ -0,0 +1,65 @@
+use crate::helpers::execute;
+use insta::assert_snapshot;
+
+#[test]
+fn print_matches_the_reference_implementation() {
+    let source = r#"print nil;
+print true;
+print "text";
+print 3;
+print 3.0;
+print 0.5;
+print -0.0;
+print 123.456;
+print 10000000.0;
+print 0.0001;
+print 1.5 * 10000000;
+print 1 / 0;
+print -1 / 0;
+print (0 / 0);"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    nil
+    true
+    text
+    3
+    3
+    0.5
+    -0
+    123.456
+    1.0E7
+    1.0E-4
+    1.5E7
+    Infinity
+    -Infinity
+    NaN
+    "###);
+}
+
+#[test]
+fn repr_keeps_the_debugging_style() {
+    let source = r#"print repr(nil);
+print repr("text");
+print repr(1.0);
+print repr(0.0001);
+print repr(1 / 0);
+fun list(...elements) { return elements; }
+print repr(list(1, 2.5, nil));"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    `nil`
+    text
+    1
+    0.0001
+    inf
+    [1, 2.5, `nil`]
+    "###);
+}
+
+#[test]
+fn list_elements_are_printed_like_values() {
+    let source = r#"fun list(...elements) { return elements; }
+print list(1, 2.0, "three", nil);"#;
+    let output = execute(source);
+    assert_snapshot!(output, @"[1, 2, three, nil]");
+}
@@ -1,5 +1,5 @@
 use crate::helpers::{execute, try_execute};
-use insta::assert_display_snapshot;
+use insta::assert_snapshot;
 
 #[test]
 fn declare_and_invoke_function() {
@@ -9,7 +9,7 @@ fn declare_and_invoke_function() {
 
 sayHi("Dear", "Reader");"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     Hi, Dear Reader!
     "###);
 }
@@ -22,7 +22,7 @@ fn function_scope_does_not_leak() {
 
 print c;"#;
     let error = try_execute(source).unwrap_err();
-    assert_display_snapshot!(error, @"An error occurred at runtime. Undefined variable named c");
+    assert_snapshot!(error, @"An error occurred at runtime. Undefined variable 'c'.");
 }
 
 #[test]
@@ -39,7 +39,7 @@ var a = "global";
   showA();
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @"
+    assert_snapshot!(output, @"
 global
 global")
 }
@@ -59,7 +59,7 @@ var f = returnClosure();
 f();
 "#;
     let output = execute(source);
-    assert_display_snapshot!(output, @"
+    assert_snapshot!(output, @"
 3")
 }
 
@@ -80,7 +80,7 @@ var counter = makeCounter();
 counter();
 counter();"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @"
+    assert_snapshot!(output, @"
 1
 2")
 }
@@ -97,9 +97,55 @@ fun count(n) {
 
 count(3);"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @"
-0
-1
-2
-3")
+    assert_snapshot!(output, @r###"
+    1
+    2
+    3
+    "###)
+}
+
+#[test]
+fn default_parameters_are_evaluated_at_call_time() {
+    let source = r#"var greeting = "Hello";
+fun greet(name, punctuation = "!", message = greeting + ", " + name + punctuation) {
+    print message;
+}
+greet("Lox");
+greeting = "Hi";
+greet("Lox", "?");
+greet("Lox", "?", "Bye");"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    Hello, Lox!
+    Hi, Lox?
+    Bye
+    "###);
+}
+
+#[test]
+fn rest_parameters_collect_excess_arguments_in_a_list() {
+    let source = r#"fun f(first, ...rest) {
+    print first;
+    print rest;
+    print rest.length;
+}
+f(1);
+f(1, 2, "three");"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    1
+    []
+    0
+    1
+    [2, three]
+    2
+    "###);
+}
+
+#[test]
+fn arity_errors_report_the_accepted_range() {
+    let error = try_execute("fun f(a, b = 1) {} f();").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Expected 1 to 2 arguments but got 0.");
+    let error = try_execute("fun f(a, ...b) {} f();").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Expected at least 1 arguments but got 0.");
 }
@@ -0,0 +1,241 @@
+use crate::helpers::{execute, try_execute};
+use insta::assert_snapshot;
+
+#[test]
+fn next_resumes_the_generator_until_the_following_yield() {
+    let source = r#"fun* numbers() {
+    print "start";
+    yield 1;
+    print "resumed";
+    yield 2;
+}
+var g = numbers();
+print g;
+print g.next();
+print g.next();
+print g.next();
+print g.next();"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    <generator numbers>
+    start
+    1
+    resumed
+    2
+    nil
+    nil
+    "###);
+}
+
+#[test]
+fn generators_can_be_iterated() {
+    let source = r#"fun* fibonacci(limit) {
+    var a = 0;
+    var b = 1;
+    while (a < limit) {
+        yield a;
+        var next = a + b;
+        a = b;
+        b = next;
+    }
+}
+for (n in fibonacci(20)) print n;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    1
+    2
+    3
+    5
+    8
+    13
+    "###);
+}
+
+#[test]
+fn generators_can_yield_from_nested_loops_and_branches() {
+    let source = r#"fun* pairs(n) {
+    for (i in range(0, n)) {
+        for (j in range(0, n)) {
+            if (i < j) yield i * 10 + j;
+        }
+    }
+}
+for (p in pairs(3)) print p;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    1
+    2
+    12
+    "###);
+}
+
+#[test]
+fn each_call_returns_an_independent_generator() {
+    let source = r#"fun* count(from) {
+    var i = from;
+    while (true) {
+        yield i;
+        i += 1;
+    }
+}
+var a = count(0);
+var b = count(100);
+print a.next();
+print b.next();
+print a.next();
+print b.next();"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    100
+    1
+    101
+    "###);
+}
+
+#[test]
+fn return_ends_the_generator() {
+    let source = r#"fun* first_two(...values) {
+    var n = 0;
+    for (v in values) {
+        if (n == 2) return;
+        n += 1;
+        yield v;
+    }
+}
+for (v in first_two("a", "b", "c")) print v;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    a
+    b
+    "###);
+}
+
+#[test]
+fn errors_thrown_while_suspended_in_try_are_caught_by_the_generator() {
+    let source = r#"fun* guarded() {
+    try {
+        yield 1;
+        throw "oops";
+    } catch (e) {
+        print "caught " + e;
+        yield 2;
+    } finally {
+        print "finally";
+    }
+    yield 3;
+}
+for (v in guarded()) print v;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    1
+    caught oops
+    2
+    finally
+    3
+    "###);
+}
+
+#[test]
+fn finally_runs_when_returning_from_a_suspended_try() {
+    let source = r#"fun* once() {
+    try {
+        yield 1;
+        return;
+    } finally {
+        print "cleanup";
+    }
+    yield 2;
+}
+for (v in once()) print v;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    1
+    cleanup
+    "###);
+}
+
+#[test]
+fn uncaught_errors_propagate_to_the_caller_of_next() {
+    let source = r#"fun* failing() {
+    yield 1;
+    throw "oops";
+}
+var g = failing();
+print g.next();
+try {
+    g.next();
+} catch (e) {
+    print e;
+}
+print g.next();"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    1
+    oops
+    nil
+    "###);
+}
+
+#[test]
+fn closures_created_in_a_generator_capture_its_variables() {
+    let source = r#"fun* counters() {
+    for (i in range(0, 2)) {
+        fun get() { return i; }
+        yield get;
+    }
+}
+var g = counters();
+var first = g.next();
+var second = g.next();
+print first();
+print second();"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    "###);
+}
+
+#[test]
+fn a_generator_cannot_resume_itself() {
+    let source = r#"var g;
+fun* recursive() {
+    yield g.next();
+}
+g = recursive();
+g.next();"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. The generator is already running.");
+}
+
+#[test]
+fn yield_is_only_allowed_in_generators() {
+    let source = r#"fun f() {
+    yield 1;
+}"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"[line 2] Error at 'yield': Can only yield from a generator.");
+}
+
+#[test]
+fn dropping_a_suspended_generator_is_fine() {
+    let source = r#"fun* nested() {
+    {
+        for (i in range(0, 10)) {
+            yield i;
+        }
+    }
+}
+var g = nested();
+print g.next();
+g = nil;
+print "dropped";"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    dropped
+    "###);
+}
@@ -1,4 +1,7 @@
-use jlox::{Environment, ExecuteRawError, Interpreter};
+use jlox::{
+    Environment, ExecuteModuleError, ExecuteRawError, FileSystemLoader, InMemoryLoader,
+    Interpreter, TestReport,
+};
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -14,6 +17,50 @@ pub fn execute(source: &str) -> String {
 pub fn try_execute(source: &str) -> Result<String, ExecuteRawError> {
     let mut buffer = Vec::new();
     let environment = Rc::new(RefCell::new(Environment::new()));
-    let outcome = Interpreter::new(&mut buffer, environment).execute_raw(source);
+    let outcome =
+        Interpreter::new(&mut buffer, environment, InMemoryLoader::new()).execute_raw(source);
     outcome.map(|_| String::from_utf8(buffer).unwrap())
 }
+
+/// Execute the provided lox source code, which must fail.
+/// It returns the program's output stream, up to the failure, and the error.
+pub fn execute_until_error(source: &str) -> (String, ExecuteRawError) {
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let outcome =
+        Interpreter::new(&mut buffer, environment, InMemoryLoader::new()).execute_raw(source);
+    let error = outcome.expect_err("The program should fail");
+    (String::from_utf8(buffer).unwrap(), error)
+}
+
+/// Execute the lox source file at `path`, relative to the `fixtures` folder.
+/// It returns the program's output stream.
+pub fn try_execute_file(path: &str) -> Result<String, ExecuteModuleError> {
+    let fixtures =
+        std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/interpreter/fixtures");
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let loader = FileSystemLoader::new(fixtures);
+    let outcome = Interpreter::new(&mut buffer, environment, loader).execute_module(path);
+    outcome.map(|_| String::from_utf8(buffer).unwrap())
+}
+
+/// Run the `test` blocks in the provided lox source code.
+/// It returns the test report, followed by the program's output stream.
+/// Panics if the interpreter runs into an error outside of the tests.
+pub fn run_tests(source: &str) -> String {
+    let (report, output) = try_run_tests(source);
+    format!("{}\n---\n{output}", report.unwrap())
+}
+
+/// Run the `test` blocks in the provided lox source code.
+/// It returns the test report, or the error that stopped the run, and the program's
+/// output stream.
+pub fn try_run_tests(source: &str) -> (Result<TestReport, ExecuteModuleError>, String) {
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let mut loader = InMemoryLoader::new();
+    loader.insert("main.lox", source);
+    let report = Interpreter::new(&mut buffer, environment, loader).test_module("main.lox");
+    (report, String::from_utf8(buffer).unwrap())
+}
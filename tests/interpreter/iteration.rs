@@ -0,0 +1,106 @@
+use crate::helpers::{execute, try_execute};
+use insta::assert_snapshot;
+
+#[test]
+fn iterate_over_the_characters_of_a_string() {
+    let source = r#"for (c in "abc") print c;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    a
+    b
+    c
+    "###);
+}
+
+#[test]
+fn iterate_over_a_range() {
+    let source = r#"for (i in range(0, 3)) print i;
+for (i in range(10, 0, -4)) print i;
+for (i in range(3, 3)) print i;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    2
+    10
+    6
+    2
+    "###);
+}
+
+#[test]
+fn iterate_over_rest_parameters() {
+    let source = r#"fun sum(...numbers) {
+    var total = 0;
+    for (n in numbers) total += n;
+    return total;
+}
+print sum(1, 2, 3);"#;
+    let output = execute(source);
+    assert_snapshot!(output, @"6");
+}
+
+#[test]
+fn iterate_over_a_closure_until_it_returns_nil() {
+    let source = r#"fun countdown(from) {
+    fun next() {
+        if (from == 0) return nil;
+        from -= 1;
+        return from + 1;
+    }
+    return next;
+}
+for (n in countdown(3)) print n;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    3
+    2
+    1
+    "###);
+}
+
+#[test]
+fn each_iteration_has_its_own_binding() {
+    let source = r#"var first;
+var second;
+for (i in range(0, 2)) {
+    fun capture() { return i; }
+    if (i == 0) first = capture; else second = capture;
+}
+print first();
+print second();"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    0
+    1
+    "###);
+}
+
+#[test]
+fn the_loop_variable_is_scoped_to_the_loop() {
+    let source = r#"for (i in range(0, 1)) {}
+print i;"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Undefined variable 'i'.");
+}
+
+#[test]
+fn numbers_are_not_iterable() {
+    let source = "for (i in 3) print i;";
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. `3` is not iterable.");
+}
+
+#[test]
+fn range_rejects_a_zero_step() {
+    let source = "for (i in range(0, 3, 0)) print i;";
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. The step of a range cannot be zero.");
+}
+
+#[test]
+fn range_checks_its_arity() {
+    let source = "range(1);";
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Expected 2 to 3 arguments but got 1.");
+}
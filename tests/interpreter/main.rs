@@ -1,4 +1,13 @@
+mod assignment;
+mod constants;
 mod control_flow;
+mod exceptions;
+mod formatting;
 mod functions;
+mod generators;
 pub mod helpers;
+mod iteration;
+mod modules;
+mod numbers;
 mod scopes;
+mod testing;
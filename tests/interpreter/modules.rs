@@ -0,0 +1,97 @@
+use crate::helpers::{try_execute, try_execute_file};
+use insta::assert_snapshot;
+use jlox::{Environment, ExecuteModuleError, ExecuteRawError, InMemoryLoader, Interpreter};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[test]
+fn exported_bindings_are_accessible_through_the_module_namespace() {
+    let output = try_execute_file("modules/main.lox").unwrap();
+    assert_snapshot!(output, @r###"
+    Loading counter
+    12
+    2
+    "###);
+}
+
+#[test]
+fn modules_are_executed_only_once() {
+    let output = try_execute_file("modules/twice.lox").unwrap();
+    assert_snapshot!(output, @r###"
+    Loading counter
+    1
+    "###);
+}
+
+#[test]
+fn bindings_that_are_not_exported_are_private() {
+    let error = try_execute_file("modules/private.lox").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Module 'private' does not export 'secret'");
+}
+
+#[test]
+fn import_cycles_are_detected() {
+    let error = try_execute_file("modules/cycle/a.lox").unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Failed to import 'b.lox'. Failed to import 'a.lox'. Import cycle detected: a.lox -> b.lox -> a.lox");
+}
+
+#[test]
+fn exports_must_be_top_level_declarations() {
+    let error = try_execute("{ export var a = 1; }").unwrap_err();
+    assert_snapshot!(error, @"[line 1] Error at 'export': Can only export top-level declarations.");
+}
+
+#[test]
+fn modules_are_bound_as_constants() {
+    let error = try_execute_file("modules/reassign.lox").unwrap_err();
+    assert_snapshot!(error, @"[line 2] Error at 'constants': Cannot assign to a constant.");
+}
+
+#[test]
+fn hosts_can_serve_modules_from_memory() {
+    let mut loader = InMemoryLoader::new();
+    loader.insert(
+        "greetings",
+        r#"export fun greet(name) { return "Hello, " + name + "!"; }"#,
+    );
+    loader.insert(
+        "main",
+        r#"import "greetings"; print greetings.greet("Lox");"#,
+    );
+
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let mut interpreter = Interpreter::new(&mut buffer, environment, loader);
+    interpreter.execute_module("main").unwrap();
+    interpreter
+        .execute_raw(r#"import "greetings" as g; print g.greet("REPL");"#)
+        .unwrap();
+    drop(interpreter);
+    assert_snapshot!(String::from_utf8(buffer).unwrap(), @r###"
+    Hello, Lox!
+    Hello, REPL!
+    "###);
+}
+
+#[test]
+fn importing_a_missing_module_is_an_error() {
+    let error = try_execute(r#"import "missing";"#).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Failed to import 'missing'. There is no module named 'missing'");
+}
+
+#[test]
+fn exit_in_a_module_ends_the_program() {
+    let mut loader = InMemoryLoader::new();
+    loader.insert("exits", r#"print "exiting"; exit(4); print "unreachable";"#);
+    loader.insert("main", r#"import "exits"; print "unreachable";"#);
+
+    let mut buffer = Vec::new();
+    let environment = Rc::new(RefCell::new(Environment::new()));
+    let outcome = Interpreter::new(&mut buffer, environment, loader).execute_module("main");
+    let Err(ExecuteModuleError::ExecuteRawError(ExecuteRawError::RuntimeError(error))) = outcome
+    else {
+        panic!("`exit` should stop the program");
+    };
+    assert_eq!(error.exit_code(), Some(4));
+    assert_snapshot!(String::from_utf8(buffer).unwrap(), @"exiting");
+}
@@ -0,0 +1,99 @@
+use crate::helpers::{execute, try_execute};
+use insta::assert_snapshot;
+
+#[test]
+fn integer_literals_do_not_lose_precision() {
+    let source = r#"print 10000000000000001;
+print 10000000000000001 + 1;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    10000000000000001
+    10000000000000002
+    "###);
+}
+
+#[test]
+fn integers_are_promoted_to_floats_in_mixed_operations() {
+    let source = r#"print 1 + 2;
+print 1 + 0.5;
+print 2 * 1.5;
+print 3 - 0.25;
+print 1.5 * 2;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    3
+    1.5
+    3
+    2.75
+    3
+    "###);
+}
+
+#[test]
+fn dividing_integers_produces_a_float() {
+    let source = r#"print 6 / 3;
+print 7 / 2;
+print 1 / 0;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    2
+    3.5
+    Infinity
+    "###);
+}
+
+#[test]
+fn integers_and_floats_compare_by_value() {
+    let source = r#"print 1 == 1.0;
+print 1 == 1.5;
+print 2 > 1.5;
+print 2 >= 2;
+print 9007199254740993 > 9007199254740992;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    true
+    false
+    true
+    true
+    true
+    "###);
+}
+
+#[test]
+fn integer_overflow_is_a_runtime_error() {
+    let source = "print 9223372036854775807 + 1;";
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Integer overflow");
+}
+
+#[test]
+fn negation_overflow_is_a_runtime_error() {
+    let source = r#"var min = -9223372036854775807 - 1;
+print min;
+print -min;"#;
+    let error = try_execute(source).unwrap_err();
+    assert_snapshot!(error, @"An error occurred at runtime. Integer overflow");
+}
+
+#[test]
+fn loop_counters_stay_integers() {
+    let source = r#"var total = 0;
+for (var i = 0; i < 4; i++) total += i;
+print total;
+for (i in range(0, 1, 0.5)) print i;"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    6
+    0
+    0.5
+    "###);
+}
+
+#[test]
+fn integer_literals_too_large_for_64_bits_are_floats() {
+    let output = execute("print 99999999999999999999; print 9223372036854775807 + 0.0;");
+    assert_snapshot!(output, @r###"
+    1.0E20
+    9.223372036854776E18
+    "###);
+}
@@ -1,5 +1,5 @@
 use crate::helpers::execute;
-use insta::assert_display_snapshot;
+use insta::assert_snapshot;
 
 #[test]
 fn lexical_scopes_are_interpreted_correctly() {
@@ -23,7 +23,7 @@ print a;
 print b;
 print c;"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
     inner a
     outer b
     global c
@@ -49,7 +49,7 @@ fn function_and_lexical_scopes() {
   showA();
 }"#;
     let output = execute(source);
-    assert_display_snapshot!(output, @r###"
+    assert_snapshot!(output, @r###"
 global
 global"###);
 }
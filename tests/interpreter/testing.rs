@@ -0,0 +1,121 @@
+use crate::helpers::{execute, run_tests, try_execute, try_run_tests};
+use insta::assert_snapshot;
+use jlox::{ExecuteModuleError, ExecuteRawError};
+
+#[test]
+fn tests_are_skipped_in_normal_runs() {
+    let source = r#"print "before";
+test "never runs" {
+    print "inside";
+}
+print "after";"#;
+    let output = execute(source);
+    assert_snapshot!(output, @r###"
+    before
+    after
+    "###);
+}
+
+#[test]
+fn passing_and_failing_tests_are_reported() {
+    let source = r#"fun add(a, b) { return a + b; }
+
+test "addition" {
+    assert(add(1, 2) == 3);
+}
+
+test "broken addition" {
+    assert(add(1, 2) == 4, "1 + 2 should be 4");
+}
+
+test "throwing" {
+    throw "boom";
+}"#;
+    let output = run_tests(source);
+    assert_snapshot!(output, @r###"
+    PASS addition
+    FAIL broken addition (line 7)
+      [line 8] Assertion failed: 1 + 2 should be 4
+    FAIL throwing (line 11)
+      [line 12] Uncaught exception: boom
+    1 passed, 2 failed
+    ---
+    "###);
+}
+
+#[test]
+fn tests_run_after_the_module_and_in_their_own_scope() {
+    let source = r#"var counter = 0;
+test "increments" {
+    var local = 1;
+    counter = counter + local;
+    print counter;
+}
+test "sees the previous test" {
+    print counter;
+    assert(counter == 11);
+}
+counter = 10;
+print "module done";"#;
+    let output = run_tests(source);
+    assert_snapshot!(output, @r###"
+    PASS increments
+    PASS sees the previous test
+    2 passed, 0 failed
+    ---
+    module done
+    11
+    11
+    "###);
+}
+
+#[test]
+fn assert_raises_an_error_when_the_condition_is_falsy() {
+    assert_snapshot!(try_execute("assert(nil);").unwrap_err(), @"An error occurred at runtime. Assertion failed.");
+    assert_snapshot!(try_execute(r#"assert(false, "oops");"#).unwrap_err(), @"An error occurred at runtime. Assertion failed: oops");
+    assert_snapshot!(execute("assert(1); assert(true, \"fine\"); print \"ok\";"), @"ok");
+}
+
+#[test]
+fn tests_can_only_be_declared_at_the_top_level() {
+    let source = r#"fun f() {
+    test "nested" {}
+}"#;
+    assert_snapshot!(try_execute(source).unwrap_err(), @"[line 2] Error at 'test': Can only declare tests at the top level.");
+}
+
+#[test]
+fn test_is_only_a_keyword_before_a_test_name() {
+    let source = r#"var test = 1;
+fun check(test) { return test + 1; }
+test = check(test);
+print test;
+test "still a test" {
+    assert(test == 2);
+}"#;
+    assert_snapshot!(execute(source), @"2");
+    assert_snapshot!(run_tests(source), @r###"
+    PASS still a test
+    1 passed, 0 failed
+    ---
+    2
+    "###);
+}
+
+#[test]
+fn exit_in_a_test_ends_the_run() {
+    let source = r#"test "exits" {
+    print "exiting";
+    exit(3);
+}
+test "never runs" {
+    print "unreachable";
+}"#;
+    let (outcome, output) = try_run_tests(source);
+    let Err(ExecuteModuleError::ExecuteRawError(ExecuteRawError::RuntimeError(error))) = outcome
+    else {
+        panic!("`exit` should stop the test run");
+    };
+    assert_eq!(error.exit_code(), Some(3));
+    assert_snapshot!(output, @"exiting");
+}
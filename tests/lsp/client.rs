@@ -0,0 +1,178 @@
+use jlox::{serve_language_server, LanguageServerError};
+use lsp_server::{Connection, Message, RequestId, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Exit, Initialized,
+    Notification, PublishDiagnostics,
+};
+use lsp_types::request::{Initialize, Request, Shutdown};
+use lsp_types::{
+    Diagnostic, DidChangeTextDocumentParams, DidCloseTextDocumentParams, DidOpenTextDocumentParams,
+    InitializeParams, InitializedParams, Position, PublishDiagnosticsParams, Range,
+    TextDocumentContentChangeEvent, TextDocumentIdentifier, TextDocumentItem,
+    TextDocumentPositionParams, Uri, VersionedTextDocumentIdentifier,
+};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// The document all tests work on.
+pub const URI: &str = "file:///test.lox";
+
+pub fn uri() -> Uri {
+    URI.parse().unwrap()
+}
+
+/// A scripted LSP client, talking to a server running on another thread of the test.
+pub struct Client {
+    connection: Connection,
+    server: Option<JoinHandle<Result<(), LanguageServerError>>>,
+    next_id: i32,
+}
+
+impl Client {
+    /// Start a server and go through the initialization handshake.
+    pub fn start() -> Self {
+        let (server, connection) = Connection::memory();
+        let server = std::thread::spawn(move || serve_language_server(server));
+        let mut client = Self {
+            connection,
+            server: Some(server),
+            next_id: 0,
+        };
+        client.request::<Initialize>(InitializeParams::default());
+        client.notify::<Initialized>(InitializedParams {});
+        client
+    }
+
+    /// Send a request and wait for its response, which must be successful.
+    pub fn request<R: Request>(&mut self, params: R::Params) -> R::Result {
+        let response = self.try_request::<R>(params);
+        if let Some(error) = response.error {
+            panic!("`{}` failed: {}", R::METHOD, error.message);
+        }
+        serde_json::from_value(response.result.unwrap_or_default()).unwrap()
+    }
+
+    /// Send a request and wait for its response.
+    pub fn try_request<R: Request>(&mut self, params: R::Params) -> Response {
+        self.next_id += 1;
+        let id = RequestId::from(self.next_id);
+        let request = lsp_server::Request::new(id.clone(), R::METHOD.to_owned(), params);
+        self.connection.sender.send(request.into()).unwrap();
+        loop {
+            match self.receive() {
+                Message::Response(response) if response.id == id => return response,
+                _ => continue,
+            }
+        }
+    }
+
+    pub fn notify<N: Notification>(&self, params: N::Params) {
+        let notification = lsp_server::Notification::new(N::METHOD.to_owned(), params);
+        self.connection.sender.send(notification.into()).unwrap();
+    }
+
+    /// Open the test document and return the diagnostics the server publishes for it.
+    pub fn open(&mut self, source: &str) -> Vec<Diagnostic> {
+        self.notify::<DidOpenTextDocument>(DidOpenTextDocumentParams {
+            text_document: TextDocumentItem::new(uri(), "lox".to_owned(), 1, source.to_owned()),
+        });
+        self.diagnostics()
+    }
+
+    /// Replace the content of the test document and return the diagnostics the server
+    /// publishes for it.
+    pub fn change(&mut self, version: i32, source: &str) -> Vec<Diagnostic> {
+        self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri(), version),
+            content_changes: vec![TextDocumentContentChangeEvent {
+                range: None,
+                range_length: None,
+                text: source.to_owned(),
+            }],
+        });
+        self.diagnostics()
+    }
+
+    /// Replace ranges of the test document, one after the other, and return the diagnostics
+    /// the server publishes for it.
+    pub fn edit(&mut self, version: i32, edits: &[(Range, &str)]) -> Vec<Diagnostic> {
+        self.notify::<DidChangeTextDocument>(DidChangeTextDocumentParams {
+            text_document: VersionedTextDocumentIdentifier::new(uri(), version),
+            content_changes: edits
+                .iter()
+                .map(|(range, text)| TextDocumentContentChangeEvent {
+                    range: Some(*range),
+                    range_length: None,
+                    text: (*text).to_owned(),
+                })
+                .collect(),
+        });
+        self.diagnostics()
+    }
+
+    pub fn close(&mut self) -> Vec<Diagnostic> {
+        self.notify::<DidCloseTextDocument>(DidCloseTextDocumentParams {
+            text_document: TextDocumentIdentifier::new(uri()),
+        });
+        self.diagnostics()
+    }
+
+    fn diagnostics(&self) -> Vec<Diagnostic> {
+        loop {
+            if let Message::Notification(notification) = self.receive() {
+                if notification.method == PublishDiagnostics::METHOD {
+                    let params: PublishDiagnosticsParams =
+                        serde_json::from_value(notification.params).unwrap();
+                    assert_eq!(params.uri, uri());
+                    return params.diagnostics;
+                }
+            }
+        }
+    }
+
+    fn receive(&self) -> Message {
+        self.connection
+            .receiver
+            .recv_timeout(Duration::from_secs(5))
+            .expect("The server did not send a message.")
+    }
+
+    /// Go through the shutdown handshake and check that the server exits cleanly.
+    pub fn shutdown(mut self) {
+        self.request::<Shutdown>(());
+        self.notify::<Exit>(());
+        let server = self.server.take().unwrap();
+        server.join().unwrap().unwrap();
+    }
+}
+
+/// The position of the `n`-th (0-based) occurrence of `needle` in `source`, plus `shift`
+/// characters.
+pub fn position(source: &str, needle: &str, n: usize, shift: u32) -> TextDocumentPositionParams {
+    let offset = source
+        .match_indices(needle)
+        .nth(n)
+        .unwrap_or_else(|| panic!("`{needle}` does not occur {} times", n + 1))
+        .0;
+    let line = source[..offset].matches('\n').count();
+    let character = offset - source[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+    TextDocumentPositionParams::new(
+        TextDocumentIdentifier::new(uri()),
+        Position::new(line as u32, character as u32 + shift),
+    )
+}
+
+/// `line:character-line:character`, 0-based like in the protocol.
+pub fn display_range(range: &Range) -> String {
+    format!(
+        "{}:{}-{}:{}",
+        range.start.line, range.start.character, range.end.line, range.end.character
+    )
+}
+
+pub fn display_diagnostics(diagnostics: &[Diagnostic]) -> String {
+    diagnostics
+        .iter()
+        .map(|d| format!("{} {}\n", display_range(&d.range), d.message))
+        .collect()
+}
@@ -0,0 +1,74 @@
+use crate::client::{position, Client};
+use insta::assert_snapshot;
+use lsp_types::request::Completion;
+use lsp_types::{CompletionItemKind, CompletionParams, CompletionResponse};
+
+/// The labels and kinds of the suggestions at the `n`-th occurrence of `needle`, except
+/// keywords and natives.
+fn completion(client: &mut Client, source: &str, needle: &str, n: usize) -> String {
+    let response = client.request::<Completion>(CompletionParams {
+        text_document_position: position(source, needle, n, 0),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+        context: None,
+    });
+    let Some(CompletionResponse::Array(items)) = response else {
+        panic!("Unexpected response: {response:?}");
+    };
+    assert!(items.iter().any(|i| i.label == "while"));
+    assert!(items.iter().any(|i| i.label == "range"));
+    items
+        .iter()
+        .filter(|i| i.kind != Some(CompletionItemKind::KEYWORD))
+        .filter(|i| i.detail.as_deref() != Some("(native)"))
+        .map(|i| match &i.detail {
+            Some(detail) => format!("{} {:?} {detail}\n", i.label, i.kind.unwrap()),
+            None => format!("{} {:?}\n", i.label, i.kind.unwrap()),
+        })
+        .collect()
+}
+
+#[test]
+fn completion_suggests_names_in_scope() {
+    // The document does not parse: the cursor is where the user is typing.
+    let source = r#"var before = 1;
+fun f(a, b = before, ...rest) {
+    var local = 2;
+    try {
+        print ;
+    } catch (error) {
+        print ;
+    }
+    print ;
+}
+print ;
+const after = 3;
+"#;
+    let mut client = Client::start();
+    client.open(source);
+    assert_snapshot!(completion(&mut client, source, ";", 2), @r###"
+    a Variable (parameter) a
+    b Variable (parameter) b
+    rest Variable (parameter) ...rest
+    local Variable var local
+    before Variable var before
+    f Function fun f(a, b = before, ...rest)
+    after Constant const after
+    "###);
+    assert_snapshot!(completion(&mut client, source, ";", 3), @r###"
+    error Variable (variable) error
+    a Variable (parameter) a
+    b Variable (parameter) b
+    rest Variable (parameter) ...rest
+    local Variable var local
+    before Variable var before
+    f Function fun f(a, b = before, ...rest)
+    after Constant const after
+    "###);
+    assert_snapshot!(completion(&mut client, source, ";", 5), @r###"
+    before Variable var before
+    f Function fun f(a, b = before, ...rest)
+    after Constant const after
+    "###);
+    client.shutdown();
+}
@@ -0,0 +1,98 @@
+use crate::client::{display_diagnostics, Client};
+use insta::assert_snapshot;
+use lsp_types::{Position, Range};
+
+#[test]
+fn valid_documents_have_no_diagnostics() {
+    let mut client = Client::start();
+    let diagnostics = client.open("fun f(a) {\n    return a + 1;\n}\nprint f(1);\n");
+    assert!(diagnostics.is_empty());
+    client.shutdown();
+}
+
+#[test]
+fn scanner_and_parser_errors_are_reported() {
+    let mut client = Client::start();
+    let diagnostics = client.open("var a = 1 @ 2;\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @"0:10-0:11 Unexpected character `@`.
+");
+    let diagnostics = client.change(2, "var a = ;\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @"0:8-0:9 Unexpected `;`.
+");
+    let diagnostics = client.change(3, "print \"unterminated\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @"0:6-1:0 Unterminated string.
+");
+    let diagnostics = client.change(4, "fun f() {\n    print 1;\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @"2:0-2:0 Unexpected end of input.
+");
+    client.shutdown();
+}
+
+#[test]
+fn resolver_errors_are_reported() {
+    let mut client = Client::start();
+    let diagnostics = client.open("const a = 1;\na = 2;\nfun f() { yield 1; }\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @r###"
+    1:0-1:1 Cannot assign to a constant.
+    2:10-2:15 Can only yield from a generator.
+    "###);
+    client.shutdown();
+}
+
+#[test]
+fn every_syntax_error_is_reported() {
+    let mut client = Client::start();
+    let diagnostics = client.open("var a = ;\nprint 1;\nprint );\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @r###"
+    0:8-0:9 Unexpected `;`.
+    2:6-2:7 Unexpected `)`.
+    "###);
+    client.shutdown();
+}
+
+#[test]
+fn the_valid_part_of_an_invalid_document_is_resolved() {
+    let mut client = Client::start();
+    let diagnostics = client.open("var a = ;\nconst b = 1;\nb = 2;\n");
+    assert_snapshot!(display_diagnostics(&diagnostics), @r###"
+    0:8-0:9 Unexpected `;`.
+    2:0-2:1 Cannot assign to a constant.
+    "###);
+    client.shutdown();
+}
+
+#[test]
+fn diagnostics_are_cleared_when_the_document_is_fixed_or_closed() {
+    let mut client = Client::start();
+    assert_eq!(client.open("print ;").len(), 1);
+    assert_eq!(client.change(2, "print 1;").len(), 0);
+    assert_eq!(client.change(3, "print ;").len(), 1);
+    assert_eq!(client.close().len(), 0);
+    client.shutdown();
+}
+
+fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+    Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+}
+
+#[test]
+fn edits_are_applied_one_after_the_other() {
+    let mut client = Client::start();
+    assert!(client.open("var a = 1;\nprint a;\n").is_empty());
+    // Break the first line, then add a line before it: the error moves down.
+    let diagnostics = client.edit(
+        2,
+        &[
+            (range((0, 8), (0, 9)), ""),
+            (range((0, 0), (0, 0)), "const b = 2;\n"),
+        ],
+    );
+    assert_snapshot!(display_diagnostics(&diagnostics), @"1:8-1:9 Unexpected `;`.
+");
+    let diagnostics = client.edit(3, &[(range((1, 8), (1, 8)), "b")]);
+    assert!(diagnostics.is_empty());
+    let diagnostics = client.edit(4, &[(range((2, 6), (2, 7)), "b = 3")]);
+    assert_snapshot!(display_diagnostics(&diagnostics), @"2:6-2:7 Cannot assign to a constant.
+");
+    client.shutdown();
+}
@@ -0,0 +1,5 @@
+mod client;
+mod completion;
+mod diagnostics;
+mod navigation;
+mod symbols;
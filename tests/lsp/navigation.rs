@@ -0,0 +1,117 @@
+use crate::client::{display_range, position, uri, Client};
+use insta::assert_snapshot;
+use lsp_types::request::{GotoDefinition, HoverRequest, References};
+use lsp_types::{
+    GotoDefinitionParams, GotoDefinitionResponse, HoverContents, HoverParams, ReferenceContext,
+    ReferenceParams,
+};
+
+const SOURCE: &str = r#"var count = 0;
+fun add(a, b = 1, ...rest) {
+    var count = a + b;
+    return count + double(range(rest));
+}
+fun* double(n) {
+    count += 1;
+    return n * 2;
+}
+print add(count);
+"#;
+
+fn definition(client: &mut Client, needle: &str, n: usize) -> String {
+    let response = client.request::<GotoDefinition>(GotoDefinitionParams {
+        text_document_position_params: position(SOURCE, needle, n, 1),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    match response {
+        Some(GotoDefinitionResponse::Scalar(location)) => {
+            assert_eq!(location.uri, uri());
+            display_range(&location.range)
+        }
+        None => "none".to_owned(),
+        Some(response) => panic!("Unexpected response: {response:?}"),
+    }
+}
+
+#[test]
+fn definitions_follow_scopes() {
+    let mut client = Client::start();
+    client.open(SOURCE);
+    // The local `count`, in `add`
+    assert_eq!(definition(&mut client, "count", 2), "2:8-2:13");
+    // The global `count`, in `double` and at the top level
+    assert_eq!(definition(&mut client, "count", 3), "0:4-0:9");
+    assert_eq!(definition(&mut client, "count", 4), "0:4-0:9");
+    // `double` is used before being declared
+    assert_eq!(definition(&mut client, "double", 0), "5:5-5:11");
+    assert_eq!(definition(&mut client, "rest", 1), "1:21-1:25");
+    // Natives are not declared in the document
+    assert_eq!(definition(&mut client, "range", 0), "none");
+    client.shutdown();
+}
+
+#[test]
+fn references_include_the_declaration_on_request() {
+    let mut client = Client::start();
+    client.open(SOURCE);
+    let mut references = |include_declaration: bool| {
+        let locations = client
+            .request::<References>(ReferenceParams {
+                text_document_position: position(SOURCE, "count", 0, 0),
+                work_done_progress_params: Default::default(),
+                partial_result_params: Default::default(),
+                context: ReferenceContext {
+                    include_declaration,
+                },
+            })
+            .unwrap();
+        locations
+            .iter()
+            .map(|l| display_range(&l.range))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    assert_snapshot!(references(true), @"0:4-0:9 6:4-6:9 9:10-9:15");
+    assert_snapshot!(references(false), @"6:4-6:9 9:10-9:15");
+    client.shutdown();
+}
+
+#[test]
+fn hover_shows_declarations() {
+    let mut client = Client::start();
+    client.open(SOURCE);
+    let mut hover = |needle: &str, n: usize| {
+        let hover = client.request::<HoverRequest>(HoverParams {
+            text_document_position_params: position(SOURCE, needle, n, 1),
+            work_done_progress_params: Default::default(),
+        });
+        match hover.map(|h| h.contents) {
+            Some(HoverContents::Markup(content)) => content.value,
+            None => "none".to_owned(),
+            Some(contents) => panic!("Unexpected content: {contents:?}"),
+        }
+    };
+    assert_snapshot!(hover("add", 1), @r###"
+    ```lox
+    fun add(a, b = 1, ...rest)
+    ```
+    "###);
+    assert_snapshot!(hover("double", 0), @r###"
+    ```lox
+    fun* double(n)
+    ```
+    "###);
+    assert_snapshot!(hover("b", 1), @r###"
+    ```lox
+    (parameter) b
+    ```
+    "###);
+    assert_snapshot!(hover("count", 0), @r###"
+    ```lox
+    var count
+    ```
+    "###);
+    assert_snapshot!(hover("print", 0), @"none");
+    client.shutdown();
+}
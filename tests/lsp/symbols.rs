@@ -0,0 +1,106 @@
+use crate::client::{display_range, uri, Client};
+use insta::assert_snapshot;
+use lsp_types::request::{DocumentSymbolRequest, Request};
+use lsp_types::{
+    DocumentSymbol, DocumentSymbolParams, DocumentSymbolResponse, TextDocumentIdentifier,
+};
+use std::fmt::Write;
+
+fn display_symbols(output: &mut String, symbols: &[DocumentSymbol], depth: usize) {
+    for symbol in symbols {
+        writeln!(
+            output,
+            "{}{:?} {} {} {}",
+            "  ".repeat(depth),
+            symbol.kind,
+            symbol.name,
+            display_range(&symbol.range),
+            display_range(&symbol.selection_range),
+        )
+        .unwrap();
+        display_symbols(
+            output,
+            symbol.children.as_deref().unwrap_or_default(),
+            depth + 1,
+        );
+    }
+}
+
+#[test]
+fn document_symbols_are_nested_in_functions() {
+    let mut client = Client::start();
+    client.open(
+        r#"import "math.lox" as math;
+const limit = 10;
+fun outer(a) {
+    var total = 0;
+    fun inner() {
+        return total;
+    }
+    return inner;
+}
+var result = outer(1);
+"#,
+    );
+    let response = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let Some(DocumentSymbolResponse::Nested(symbols)) = response else {
+        panic!("Unexpected response: {response:?}");
+    };
+    let mut output = String::new();
+    display_symbols(&mut output, &symbols, 0);
+    assert_snapshot!(output, @r###"
+    Module math 0:21-0:25 0:21-0:25
+    Constant limit 1:6-1:11 1:6-1:11
+    Function outer 2:0-8:1 2:4-2:9
+      Variable total 3:8-3:13 3:8-3:13
+      Function inner 4:4-6:5 4:8-4:13
+    Variable result 9:4-9:10 9:4-9:10
+    "###);
+    client.shutdown();
+}
+
+#[test]
+fn imports_without_alias_are_named_after_their_module() {
+    let mut client = Client::start();
+    client.open(
+        r#"import "lib/strings.lox";
+import "my-module.lox";
+"#,
+    );
+    let response = client.request::<DocumentSymbolRequest>(DocumentSymbolParams {
+        text_document: TextDocumentIdentifier::new(uri()),
+        work_done_progress_params: Default::default(),
+        partial_result_params: Default::default(),
+    });
+    let Some(DocumentSymbolResponse::Nested(symbols)) = response else {
+        panic!("Unexpected response: {response:?}");
+    };
+    let mut output = String::new();
+    display_symbols(&mut output, &symbols, 0);
+    assert_snapshot!(output, @"Module strings 0:7-0:24 0:7-0:24");
+    client.shutdown();
+}
+
+#[test]
+fn unsupported_requests_are_rejected() {
+    let mut client = Client::start();
+    client.open("print 1;");
+    let response = client.try_request::<lsp_types::request::Rename>(lsp_types::RenameParams {
+        text_document_position: crate::client::position("print 1;", "print", 0, 0),
+        new_name: "x".to_owned(),
+        work_done_progress_params: Default::default(),
+    });
+    let error = response.error.unwrap();
+    assert_eq!(
+        error.message,
+        format!(
+            "Unsupported request `{}`.",
+            lsp_types::request::Rename::METHOD
+        )
+    );
+    client.shutdown();
+}